@@ -7,6 +7,12 @@ pub(crate) use run::*;
 mod init;
 pub(crate) use init::*;
 
+mod replay;
+pub(crate) use replay::*;
+
+mod wasm;
+pub(crate) use wasm::*;
+
 #[cfg(debug_assertions)]
 mod sub_chain;
 use seda_chains::{chain, Client};
@@ -1,7 +1,9 @@
 use clap::Args;
 use jsonrpsee::{core::client::ClientT, rpc_params, ws_client::WsClientBuilder};
 use seda_config::{AppConfig, PartialChainConfigs};
+use seda_runtime::DryRun;
 use seda_runtime_sdk::Chain;
+use serde_json::Value;
 
 use crate::Result;
 
@@ -17,6 +19,12 @@ pub struct Bridge {
     pub bridge_deposit:        u128,
     #[arg(short, long)]
     pub args:                  String,
+    /// Prints the transactions the bridge would send instead of sending them
+    #[arg(long)]
+    pub dry_run:               bool,
+    /// The result the transactions resolve with in a dry run
+    #[arg(long, default_value = "")]
+    pub chain_call_result:     String,
 }
 
 impl Bridge {
@@ -36,10 +44,18 @@ impl Bridge {
             self.args,
         ];
 
-        let response: Vec<String> = client.request("cli", rpc_params!(args)).await?;
+        let dry_run = self.dry_run.then(|| DryRun {
+            chain_call_result: self.chain_call_result.into_bytes(),
+        });
+
+        let response: Value = client.request("cli", rpc_params!(args, dry_run)).await?;
         // This is assuming we are always putting valid json in our wasm output...
         // This formatting is also a bit awkward...
-        serde_json::to_writer_pretty(std::io::stdout(), &response)?;
+        if self.dry_run {
+            serde_json::to_writer_pretty(std::io::stdout(), &response)?;
+        } else {
+            serde_json::to_writer_pretty(std::io::stdout(), &response["stderr"])?;
+        }
         Ok(())
     }
 }
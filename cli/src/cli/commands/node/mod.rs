@@ -1,12 +1,13 @@
 use clap::Subcommand;
 use seda_config::{AppConfig, PartialChainConfigs};
 
-use self::peers::Peers;
+use self::{peers::Peers, programs::Programs};
 use crate::Result;
 
 mod bridge;
 mod get;
 mod peers;
+mod programs;
 mod register;
 mod unregister;
 mod update;
@@ -41,6 +42,11 @@ pub enum Node {
         #[command(subcommand)]
         sub_peers_command: Peers,
     },
+    /// Commands for managing the WASM programs of a running node
+    Programs {
+        #[command(subcommand)]
+        sub_programs_command: Programs,
+    },
 }
 
 impl Node {
@@ -54,6 +60,7 @@ impl Node {
             Self::Update(update_node) => update_node.handle(config, chains_config).await,
             Self::Unregister(unregister_node) => unregister_node.handle(config, chains_config).await,
             Self::Peers { sub_peers_command } => sub_peers_command.handle(config).await,
+            Self::Programs { sub_programs_command } => sub_programs_command.handle(config).await,
         }
     }
 }
@@ -0,0 +1,28 @@
+use clap::Subcommand;
+use seda_config::AppConfig;
+
+use crate::Result;
+
+mod list;
+mod register;
+mod reload;
+
+#[derive(Debug, Subcommand)]
+pub enum Programs {
+    /// Registers a WASM program on a running node
+    Register(register::RegisterProgram),
+    /// Reloads a program from the file it was loaded from on a running node
+    Reload(reload::ReloadProgram),
+    /// Lists all programs registered on a running node
+    List(list::ListPrograms),
+}
+
+impl Programs {
+    pub async fn handle(self, config: AppConfig) -> Result<()> {
+        match self {
+            Self::Register(register_program) => register_program.handle(config).await,
+            Self::Reload(reload_program) => reload_program.handle(config).await,
+            Self::List(list_programs) => list_programs.handle(config).await,
+        }
+    }
+}
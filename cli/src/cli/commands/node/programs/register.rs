@@ -0,0 +1,47 @@
+use std::{fs, path::PathBuf};
+
+use clap::Args;
+use jsonrpsee::{core::client::ClientT, rpc_params, ws_client::WsClientBuilder};
+use seda_config::AppConfig;
+use seda_runtime::ProgramManifest;
+
+use crate::Result;
+
+#[derive(Debug, Args)]
+pub struct RegisterProgram {
+    /// The name the program can be run by
+    pub name:          String,
+    /// The path to the WASM binary of the program
+    pub wasm_path:     PathBuf,
+    /// The path to the TOML manifest of the program, defaults to the `.toml`
+    /// file next to the WASM binary. Without a manifest the program gets the
+    /// manifest in its custom section, or no access to the host at all.
+    #[arg(long)]
+    pub manifest_path: Option<PathBuf>,
+}
+
+impl RegisterProgram {
+    pub async fn handle(self, config: AppConfig) -> Result<()> {
+        let wasm_binary = fs::read(&self.wasm_path)?;
+        let manifest_path = self
+            .manifest_path
+            .unwrap_or_else(|| self.wasm_path.with_extension(ProgramManifest::EXTENSION));
+        let manifest_toml = manifest_path
+            .is_file()
+            .then(|| fs::read_to_string(&manifest_path))
+            .transpose()?;
+        let client = WsClientBuilder::default()
+            .build(format!("ws://{}", &config.seda_server_url))
+            .await?;
+
+        let hash: String = client
+            .request(
+                "register_program",
+                rpc_params!(&self.name, hex::encode(wasm_binary), manifest_toml),
+            )
+            .await?;
+        println!("Program {} has been registered ({hash})", &self.name);
+
+        Ok(())
+    }
+}
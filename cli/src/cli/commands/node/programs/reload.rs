@@ -0,0 +1,24 @@
+use clap::Args;
+use jsonrpsee::{core::client::ClientT, rpc_params, ws_client::WsClientBuilder};
+use seda_config::AppConfig;
+
+use crate::Result;
+
+#[derive(Debug, Args)]
+pub struct ReloadProgram {
+    /// The name of the program, `consensus` for the consensus program
+    pub name: String,
+}
+
+impl ReloadProgram {
+    pub async fn handle(self, config: AppConfig) -> Result<()> {
+        let client = WsClientBuilder::default()
+            .build(format!("ws://{}", &config.seda_server_url))
+            .await?;
+
+        let hash: String = client.request("reload_program", rpc_params!(&self.name)).await?;
+        println!("Program {} has been reloaded ({hash})", &self.name);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use std::{fs, path::PathBuf, sync::Arc};
+
+use clap::Args;
+use parking_lot::RwLock;
+use seda_config::{AppConfig, PartialChainConfigs, PartialNodeConfig};
+use seda_runtime::{ProgramRegistry, Recording};
+
+use crate::Result;
+
+#[derive(Debug, Args)]
+pub struct Replay {
+    /// The path to the recording of a runtime job
+    pub recording_path: PathBuf,
+    /// The path to the WASM binary of the recorded program, defaults to the
+    /// binary the node would load for it
+    #[arg(long)]
+    pub wasm_path:      Option<PathBuf>,
+    #[command(flatten)]
+    pub node_config:    PartialNodeConfig,
+    #[command(flatten)]
+    pub chains_config:  PartialChainConfigs,
+}
+
+impl Replay {
+    #[tokio::main]
+    pub async fn handle(self, config: AppConfig) -> Result<()> {
+        let node_config = config.node.to_config(self.node_config)?;
+        let chains_config = config.chains.to_config(self.chains_config)?;
+        let recording = Recording::read_from_path(&self.recording_path)?;
+
+        let wasm_path = self.wasm_path.unwrap_or_else(|| {
+            if recording.program_name == ProgramRegistry::CONSENSUS {
+                node_config.consensus_wasm_path.clone()
+            } else {
+                node_config.programs_path.join(format!(
+                    "{}.{}",
+                    recording.program_name,
+                    ProgramRegistry::WASM_EXTENSION
+                ))
+            }
+        });
+        let mut programs = ProgramRegistry::new(&node_config)?;
+        programs.register(&recording.program_name, &fs::read(wasm_path)?)?;
+
+        let replay =
+            seda_runtime::replay(&recording, node_config, chains_config, Arc::new(RwLock::new(programs))).await?;
+
+        if replay.differences.is_empty() {
+            println!("The replay matches the recording");
+        } else {
+            println!("The replay differs from the recording:");
+            for difference in &replay.differences {
+                println!("  {difference}");
+            }
+        }
+
+        Ok(())
+    }
+}
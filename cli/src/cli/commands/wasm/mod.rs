@@ -0,0 +1,21 @@
+use clap::Subcommand;
+use seda_config::AppConfig;
+
+use crate::Result;
+
+mod precompile;
+
+#[derive(Debug, Subcommand)]
+pub enum Wasm {
+    // seda wasm precompile ./programs/price_feed.wasm
+    /// Compiles WASM programs ahead of time into the module cache of the node
+    Precompile(precompile::Precompile),
+}
+
+impl Wasm {
+    pub fn handle(self, config: AppConfig) -> Result<()> {
+        match self {
+            Self::Precompile(precompile) => precompile.handle(config),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::{fs, path::PathBuf};
+
+use clap::Args;
+use seda_config::{AppConfig, PartialNodeConfig};
+use seda_runtime::{create_metered_store, uses_float_ops, validate_program, ModuleCache, ProgramManifest};
+
+use crate::Result;
+
+#[derive(Debug, Args)]
+pub struct Precompile {
+    /// The paths to the WASM binaries to compile
+    #[arg(required = true)]
+    pub wasm_paths:  Vec<PathBuf>,
+    /// The directory to write the compiled artifacts to, defaults to the WASM
+    /// cache of the node
+    #[arg(long)]
+    pub output_path: Option<PathBuf>,
+    #[command(flatten)]
+    pub node_config: PartialNodeConfig,
+}
+
+impl Precompile {
+    /// The artifacts are only loaded by nodes with the same compiler, memory
+    /// limit and CPU target as the configuration used here
+    pub fn handle(self, config: AppConfig) -> Result<()> {
+        let node_config = config.node.to_config(self.node_config)?;
+        let store = create_metered_store(node_config.wasm_compiler, node_config.max_memory_pages)?;
+        let cache = ModuleCache::new(
+            self.output_path.unwrap_or_else(|| node_config.wasm_cache_path.clone()),
+            node_config.wasm_compiler,
+        );
+
+        for wasm_path in &self.wasm_paths {
+            let name = wasm_path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .ok_or_else(|| format!("Invalid program file name: {}", wasm_path.display()))?;
+            let wasm_binary = fs::read(wasm_path)?;
+
+            // Only programs the node would load are cached
+            let (_, artifact_path) =
+                cache.precompile(&store, &wasm_binary, node_config.max_memory_pages, |module| {
+                    validate_program(name, &store, module, &wasm_binary, &node_config)?;
+                    ProgramManifest::for_program(wasm_path, &wasm_binary)?;
+                    uses_float_ops(&wasm_binary)?;
+
+                    Ok(())
+                })?;
+            println!(
+                "Compiled `{name}` with {} to {}",
+                node_config.wasm_compiler,
+                artifact_path.display()
+            );
+        }
+
+        Ok(())
+    }
+}
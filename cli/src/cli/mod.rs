@@ -56,6 +56,15 @@ pub enum Command {
     /// Creates a new config and secret key (if it doesn't exist) and outputs
     /// it's derived contents
     Init(Init),
+    // seda replay ./recordings/1670000000000-consensus-1-0.json
+    /// Replays a recorded runtime job offline and compares the outcome with
+    /// the recording.
+    Replay(Replay),
+    /// Commands for working with WASM programs offline.
+    Wasm {
+        #[command(subcommand)]
+        sub_wasm_command: Wasm,
+    },
 }
 
 impl Command {
@@ -85,6 +94,8 @@ impl Command {
                 sub_node_command,
             } => sub_node_command.handle(config, chains_config),
             Self::Run(run_command) => run_command.handle(config),
+            Self::Replay(replay_command) => replay_command.handle(config),
+            Self::Wasm { sub_wasm_command } => sub_wasm_command.handle(config),
             #[cfg(debug_assertions)]
             Self::SubChain {
                 chains_config,
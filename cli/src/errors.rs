@@ -2,6 +2,7 @@ use seda_chains::ChainAdapterError;
 use seda_config::ConfigError;
 use seda_crypto::CryptoError;
 use seda_node::NodeError;
+use seda_runtime::RuntimeError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -22,12 +23,14 @@ pub enum CliError {
     Bn254(#[from] bn254::Error),
     // #[error(transparent)]
     // Crypt(#[from] )
-    #[cfg(debug_assertions)]
     #[error(transparent)]
-    CLIDocument(#[from] std::io::Error),
+    Io(#[from] std::io::Error),
 
     #[error(transparent)]
     Crypto(#[from] CryptoError),
+
+    #[error(transparent)]
+    Runtime(#[from] RuntimeError),
 }
 
 impl From<&str> for CliError {
@@ -1,7 +1,6 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{fmt, path::PathBuf, sync::Arc, time::Duration};
 
 use seda_crypto::{Bn254KeyPair, Ed25519KeyPair, MasterKey};
-#[cfg(feature = "cli")]
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "cli")]
@@ -14,28 +13,104 @@ use crate::{env_overwrite, merge_config_cli, Config, ConfigError, Result};
 pub struct PartialNodeConfig {
     /// An option to override the node deposit config value.
     #[arg(short, long)]
-    pub deposit:                 Option<String>,
+    pub deposit:                    Option<String>,
     /// An option to override the node gas config value.
     #[arg(short, long)]
-    pub gas:                     Option<u64>,
+    pub gas:                        Option<u64>,
     /// An option to override the node secret key config value.
     #[arg(long)]
-    pub master_key:              Option<String>,
+    pub master_key:                 Option<String>,
     /// The path where you want to write to the generated secret key.
     #[arg(long)]
-    pub seda_sk_file_path:       Option<PathBuf>,
+    pub seda_sk_file_path:          Option<PathBuf>,
     /// An option to override the node contract account ID config value.
     #[arg(long)]
-    pub contract_account_id:     Option<String>,
+    pub contract_account_id:        Option<String>,
     /// An option to override the node job manager interval(ms) config value.
     #[arg(long)]
-    pub job_manager_interval_ms: Option<u64>,
+    pub job_manager_interval_ms:    Option<u64>,
     /// An option to override the node runtime worker threads config value.
     #[arg(long)]
-    pub runtime_worker_threads:  Option<u8>,
+    pub runtime_worker_threads:     Option<u8>,
     /// An option to override the path of the consensus WASM binary.
     #[arg(long)]
-    pub consensus_wasm_path:     Option<PathBuf>,
+    pub consensus_wasm_path:        Option<PathBuf>,
+    /// An option to override the maximum time(ms) a runtime job may run.
+    #[arg(long)]
+    pub runtime_job_timeout_ms:     Option<u64>,
+    /// An option to override the maximum linear memory pages(64KiB) of a WASM
+    /// instance.
+    #[arg(long)]
+    pub max_memory_pages:           Option<u32>,
+    /// An option to override the maximum size(bytes) of an execution result.
+    #[arg(long)]
+    pub max_result_bytes:           Option<usize>,
+    /// An option to override the maximum size(bytes) of the captured
+    /// stdout/stderr of an execution.
+    #[arg(long)]
+    pub max_log_bytes:              Option<usize>,
+    /// An option to override the directory compiled WASM modules are cached
+    /// in.
+    #[arg(long)]
+    pub wasm_cache_path:            Option<PathBuf>,
+    /// An option to override the directory WASM programs are loaded from.
+    #[arg(long)]
+    pub programs_path:              Option<PathBuf>,
+    /// An option to record every runtime job to a replay file in the given
+    /// directory.
+    #[arg(long)]
+    pub recordings_path:            Option<PathBuf>,
+    /// An option to reject programs with float instructions in deterministic
+    /// executions.
+    #[arg(long)]
+    pub reject_float_ops:           Option<bool>,
+    /// An option to persist the shared memory in the given directory, so it
+    /// survives restarts.
+    #[arg(long)]
+    pub shared_memory_path:         Option<PathBuf>,
+    /// An option to override the maximum size(bytes) of the shared memory
+    /// keys and values a program may own.
+    #[arg(long)]
+    pub max_shared_memory_bytes:    Option<usize>,
+    /// An option to override the duration(ms) of a main chain slot.
+    #[arg(long)]
+    pub slot_duration_ms:           Option<u64>,
+    /// An option to set the Unix timestamp(ms) of the first main chain slot,
+    /// executions only see the current slot and epoch when it is set.
+    #[arg(long)]
+    pub genesis_timestamp_ms:       Option<u64>,
+    /// An option to check the program files for changes every interval(ms)
+    /// and reload the changed programs.
+    #[arg(long)]
+    pub program_reload_interval_ms: Option<u64>,
+    /// An option to override the compiler WASM programs are compiled with.
+    #[arg(long)]
+    pub wasm_compiler:              Option<WasmCompiler>,
+}
+
+/// The compiler WASM programs are compiled with. Only the compilers enabled
+/// as features of `seda-runtime` can be used.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum WasmCompiler {
+    /// Generates fast code, the default
+    #[default]
+    Cranelift,
+    /// Compiles in linear time, for programs that can not be trusted
+    Singlepass,
+    /// Generates the fastest code, but compiles slowly
+    Llvm,
+}
+
+impl fmt::Display for WasmCompiler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cranelift => write!(f, "cranelift"),
+            Self::Singlepass => write!(f, "singlepass"),
+            Self::Llvm => write!(f, "llvm"),
+        }
+    }
 }
 
 fn default_consensus_wasm_path() -> PathBuf {
@@ -134,6 +209,66 @@ impl PartialNodeConfig {
             Ok(default_consensus_wasm_path())
         )?;
 
+        let runtime_job_timeout = merge_config_cli!(
+            self,
+            cli_options,
+            runtime_job_timeout_ms,
+            Ok(Duration::from_millis(NodeConfigInner::RUNTIME_JOB_TIMEOUT_MS)),
+            Duration::from_millis
+        )?;
+
+        let max_memory_pages = merge_config_cli!(
+            self,
+            cli_options,
+            max_memory_pages,
+            Ok(NodeConfigInner::MAX_MEMORY_PAGES)
+        )?;
+        let max_result_bytes = merge_config_cli!(
+            self,
+            cli_options,
+            max_result_bytes,
+            Ok(NodeConfigInner::MAX_RESULT_BYTES)
+        )?;
+        let max_log_bytes = merge_config_cli!(self, cli_options, max_log_bytes, Ok(NodeConfigInner::MAX_LOG_BYTES))?;
+
+        let wasm_cache_path = merge_config_cli!(
+            self,
+            cli_options,
+            wasm_cache_path,
+            Ok(PathBuf::from(NodeConfigInner::WASM_CACHE_PATH))
+        )?;
+        let programs_path = merge_config_cli!(
+            self,
+            cli_options,
+            programs_path,
+            Ok(PathBuf::from(NodeConfigInner::PROGRAMS_PATH))
+        )?;
+        let recordings_path = merge_config_cli!(self, cli_options, recordings_path);
+        let reject_float_ops = merge_config_cli!(
+            self,
+            cli_options,
+            reject_float_ops,
+            Ok(NodeConfigInner::REJECT_FLOAT_OPS)
+        )?;
+        let shared_memory_path = merge_config_cli!(self, cli_options, shared_memory_path);
+        let max_shared_memory_bytes = merge_config_cli!(
+            self,
+            cli_options,
+            max_shared_memory_bytes,
+            Ok(NodeConfigInner::MAX_SHARED_MEMORY_BYTES)
+        )?;
+        let slot_duration = merge_config_cli!(
+            self,
+            cli_options,
+            slot_duration_ms,
+            Ok(Duration::from_millis(NodeConfigInner::SLOT_DURATION_MS)),
+            Duration::from_millis
+        )?;
+        let genesis_timestamp_ms = merge_config_cli!(self, cli_options, genesis_timestamp_ms);
+        let program_reload_interval =
+            merge_config_cli!(self, cli_options, program_reload_interval_ms).map(Duration::from_millis);
+        let wasm_compiler = merge_config_cli!(self, cli_options, wasm_compiler, Ok(WasmCompiler::default()))?;
+
         // Make sure we will not run the node with the account secret key
         if std::env::var("ACCOUNT_SECRET_KEY").is_ok() {
             return Err(ConfigError::UnwantedConfig("ACCOUNT_SECRET_KEY".to_string()));
@@ -148,6 +283,20 @@ impl PartialNodeConfig {
             job_manager_interval_ms,
             runtime_worker_threads,
             consensus_wasm_path,
+            runtime_job_timeout,
+            max_memory_pages,
+            max_result_bytes,
+            max_log_bytes,
+            wasm_cache_path,
+            programs_path,
+            recordings_path,
+            reject_float_ops,
+            shared_memory_path,
+            max_shared_memory_bytes,
+            slot_duration,
+            genesis_timestamp_ms,
+            program_reload_interval,
+            wasm_compiler,
         }))
     }
 }
@@ -156,14 +305,28 @@ impl PartialNodeConfig {
 impl Config for PartialNodeConfig {
     fn template() -> Self {
         Self {
-            deposit:                 None,
-            gas:                     None,
-            master_key:              None,
-            seda_sk_file_path:       None,
-            contract_account_id:     None,
-            job_manager_interval_ms: None,
-            runtime_worker_threads:  None,
-            consensus_wasm_path:     None,
+            deposit:                    None,
+            gas:                        None,
+            master_key:                 None,
+            seda_sk_file_path:          None,
+            contract_account_id:        None,
+            job_manager_interval_ms:    None,
+            runtime_worker_threads:     None,
+            consensus_wasm_path:        None,
+            runtime_job_timeout_ms:     Some(NodeConfigInner::RUNTIME_JOB_TIMEOUT_MS),
+            max_memory_pages:           Some(NodeConfigInner::MAX_MEMORY_PAGES),
+            max_result_bytes:           Some(NodeConfigInner::MAX_RESULT_BYTES),
+            max_log_bytes:              Some(NodeConfigInner::MAX_LOG_BYTES),
+            wasm_cache_path:            None,
+            programs_path:              None,
+            recordings_path:            None,
+            reject_float_ops:           Some(NodeConfigInner::REJECT_FLOAT_OPS),
+            shared_memory_path:         None,
+            max_shared_memory_bytes:    Some(NodeConfigInner::MAX_SHARED_MEMORY_BYTES),
+            slot_duration_ms:           Some(NodeConfigInner::SLOT_DURATION_MS),
+            genesis_timestamp_ms:       None,
+            program_reload_interval_ms: None,
+            wasm_compiler:              Some(WasmCompiler::default()),
         }
     }
 
@@ -181,6 +344,24 @@ pub struct NodeConfigInner {
     pub job_manager_interval_ms: u64,
     pub runtime_worker_threads:  usize,
     pub consensus_wasm_path:     PathBuf,
+    pub runtime_job_timeout:     Duration,
+    pub max_memory_pages:        u32,
+    pub max_result_bytes:        usize,
+    pub max_log_bytes:           usize,
+    pub wasm_cache_path:         PathBuf,
+    pub programs_path:           PathBuf,
+    /// Runtime jobs are only recorded when set
+    pub recordings_path:         Option<PathBuf>,
+    pub reject_float_ops:        bool,
+    /// The shared memory is only persisted when set
+    pub shared_memory_path:      Option<PathBuf>,
+    pub max_shared_memory_bytes: usize,
+    pub slot_duration:           Duration,
+    /// Executions only see the current slot and epoch when set
+    pub genesis_timestamp_ms:    Option<u64>,
+    /// Changed program files are only reloaded when set
+    pub program_reload_interval: Option<Duration>,
+    pub wasm_compiler:           WasmCompiler,
 }
 
 impl NodeConfigInner {
@@ -197,6 +378,20 @@ impl NodeConfigInner {
             job_manager_interval_ms: Self::JOB_MANAGER_INTERVAL_MS,
             runtime_worker_threads:  Self::RUNTIME_WORKER_THREADS,
             consensus_wasm_path:     default_consensus_wasm_path(),
+            runtime_job_timeout:     Duration::from_millis(Self::RUNTIME_JOB_TIMEOUT_MS),
+            max_memory_pages:        Self::MAX_MEMORY_PAGES,
+            max_result_bytes:        Self::MAX_RESULT_BYTES,
+            max_log_bytes:           Self::MAX_LOG_BYTES,
+            wasm_cache_path:         std::env::temp_dir().join("seda_wasm_cache"),
+            programs_path:           PathBuf::from(Self::PROGRAMS_PATH),
+            recordings_path:         None,
+            reject_float_ops:        Self::REJECT_FLOAT_OPS,
+            shared_memory_path:      None,
+            max_shared_memory_bytes: Self::MAX_SHARED_MEMORY_BYTES,
+            slot_duration:           Duration::from_millis(Self::SLOT_DURATION_MS),
+            genesis_timestamp_ms:    None,
+            program_reload_interval: None,
+            wasm_compiler:           WasmCompiler::default(),
         })
     }
 }
@@ -205,8 +400,24 @@ impl NodeConfigInner {
     pub const DEPOSIT: u128 = 87 * 10_u128.pow(19);
     pub const GAS: u64 = 300_000_000_000_000;
     pub const JOB_MANAGER_INTERVAL_MS: u64 = 10;
+    // 1 MiB
+    pub const MAX_LOG_BYTES: usize = 1024 * 1024;
+    // 64 MiB
+    pub const MAX_MEMORY_PAGES: u32 = 1024;
+    // 1 MiB
+    pub const MAX_RESULT_BYTES: usize = 1024 * 1024;
+    // 16 MiB
+    pub const MAX_SHARED_MEMORY_BYTES: usize = 16 * 1024 * 1024;
+    pub const PROGRAMS_PATH: &str = "./programs";
+    pub const REJECT_FLOAT_OPS: bool = false;
+    // 30 seconds
+    pub const RUNTIME_JOB_TIMEOUT_MS: u64 = 30_000;
     pub const RUNTIME_WORKER_THREADS: usize = 2;
     pub const SEDA_SECRET_KEY_PATH: &str = "./seda_secret_key";
+    pub const SLOTS_PER_EPOCH: u64 = 32;
+    // 12 seconds
+    pub const SLOT_DURATION_MS: u64 = 12_000;
+    pub const WASM_CACHE_PATH: &str = "./wasm_cache";
 }
 
 pub type NodeConfig = Arc<NodeConfigInner>;
@@ -4,6 +4,8 @@ mod master_key;
 pub use master_key::*;
 mod keypair;
 pub use keypair::*;
+mod vrf;
+pub use vrf::*;
 
 #[cfg(test)]
 #[path = ""]
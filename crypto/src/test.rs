@@ -1,7 +1,7 @@
 use bn254::ECDSA;
 use ed25519_dalek::{Keypair, Signature, Signer};
 
-use crate::{CryptoError, MasterKey};
+use crate::{CryptoError, MasterKey, Vrf};
 
 #[test]
 fn generate_bn254_pair() {
@@ -108,3 +108,36 @@ fn derive_ed25519_different_keys() {
 
     assert_ne!(private_key_1, private_key_2)
 }
+
+#[test]
+fn vrf_prove_and_verify() {
+    let master_key = MasterKey::random();
+    let bn254_key = master_key.derive_bn254(0).unwrap();
+
+    let (output, proof) = Vrf::prove(&bn254_key.private_key, b"seda-vrf").unwrap();
+    assert_eq!(Vrf::verify(&bn254_key.public_key, b"seda-vrf", &proof).unwrap(), output);
+
+    // The same key and input always give the same output
+    assert_eq!(Vrf::prove(&bn254_key.private_key, b"seda-vrf").unwrap().0, output);
+    assert_ne!(Vrf::prove(&bn254_key.private_key, b"other-input").unwrap().0, output);
+}
+
+#[test]
+fn vrf_verify_invalid_proof() {
+    let master_key = MasterKey::random();
+    let bn254_key = master_key.derive_bn254(0).unwrap();
+    let other_key = master_key.derive_bn254(1).unwrap();
+
+    let (_, proof) = Vrf::prove(&bn254_key.private_key, b"seda-vrf").unwrap();
+    assert!(Vrf::verify(&other_key.public_key, b"seda-vrf", &proof).is_err());
+    assert!(Vrf::verify(&bn254_key.public_key, b"other-input", &proof).is_err());
+
+    // A regular signature over the input is not a proof
+    let signature = ECDSA::sign(b"seda-vrf", &bn254_key.private_key).unwrap();
+    assert!(Vrf::verify(
+        &bn254_key.public_key,
+        b"seda-vrf",
+        &signature.to_uncompressed().unwrap()
+    )
+    .is_err());
+}
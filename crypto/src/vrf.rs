@@ -0,0 +1,48 @@
+use bn254::{PrivateKey as Bn254PrivateKey, PublicKey as Bn254PublicKey, Signature as Bn254Signature, ECDSA};
+use sha2::{Digest, Sha256};
+
+use super::Result;
+
+/// A verifiable random function on top of the bn254 keys of the node.
+///
+/// bn254 signatures are deterministic and there is only one valid signature
+/// per key and message, so the signature over the input is the proof and its
+/// hash is the random output. Nobody can predict the output without the
+/// private key, and anyone with the public key can check that it was not
+/// chosen by the node.
+pub struct Vrf;
+
+impl Vrf {
+    /// Separates VRF proofs from regular signatures over the same bytes
+    const DOMAIN: &[u8] = b"SEDA_VRF_BN254";
+    pub const OUTPUT_LENGTH: usize = 32;
+
+    /// Proves the input, returns the random output and the proof
+    pub fn prove(private_key: &Bn254PrivateKey, input: &[u8]) -> Result<([u8; Self::OUTPUT_LENGTH], Vec<u8>)> {
+        let proof = ECDSA::sign(&Self::message(input), private_key)?.to_uncompressed()?;
+
+        Ok((Self::proof_to_output(&proof), proof))
+    }
+
+    /// Checks the proof of the input against the public key, returns the
+    /// random output when the proof is valid
+    pub fn verify(public_key: &Bn254PublicKey, input: &[u8], proof: &[u8]) -> Result<[u8; Self::OUTPUT_LENGTH]> {
+        let signature = Bn254Signature::from_uncompressed(proof)?;
+        ECDSA::verify(&Self::message(input), &signature, public_key)?;
+
+        // Hash the canonical encoding, so a proof can only map to one output
+        Ok(Self::proof_to_output(&signature.to_uncompressed()?))
+    }
+
+    fn proof_to_output(proof: &[u8]) -> [u8; Self::OUTPUT_LENGTH] {
+        Sha256::new()
+            .chain_update(Self::DOMAIN)
+            .chain_update(proof)
+            .finalize()
+            .into()
+    }
+
+    fn message(input: &[u8]) -> Vec<u8> {
+        [Self::DOMAIN, input].concat()
+    }
+}
@@ -32,7 +32,7 @@ impl<HA: HostAdapter> Handler<StartJobManager> for App<HA> {
         let mut event_queue = self.event_queue.write();
         let running_event_ids = self.running_event_ids.read();
         if let Some(event) = event_queue.get_next(running_event_ids.as_slice()) {
-            self.runtime_worker.do_send(RuntimeJob { event });
+            self.runtime_worker.do_send(RuntimeJob { event, dry_run: None });
         }
 
         ctx.notify_later(msg, Duration::from_millis(StartJobManager::JOB_MANAGER_INTERVAL));
@@ -1,10 +1,10 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use actix::prelude::*;
 use parking_lot::RwLock;
 use seda_config::{ChainConfigs, NodeConfig};
 use seda_p2p::DiscoveryStatus;
-use seda_runtime::{HostAdapter, InMemory};
+use seda_runtime::{HostAdapter, PersistentMemory, ProgramManifest, ProgramRegistry};
 use seda_runtime_sdk::{events::EventId, p2p::P2PCommand};
 use tokio::sync::mpsc::Sender;
 use tracing::info;
@@ -18,15 +18,20 @@ use crate::{
 
 mod job_manager;
 pub mod p2p_message_handler;
+mod program_watcher;
+pub(crate) use program_watcher::reload_program;
 mod shutdown;
 pub use shutdown::Shutdown;
 // Node Actor definition
 pub struct App<HA: HostAdapter> {
-    pub event_queue:       Arc<RwLock<EventQueue>>,
-    pub running_event_ids: Arc<RwLock<Vec<EventId>>>,
-    pub runtime_worker:    Addr<RuntimeWorker<HA>>,
-    pub rpc_server:        JsonRpcServer,
-    pub shared_memory:     Arc<RwLock<InMemory>>,
+    pub event_queue:             Arc<RwLock<EventQueue>>,
+    pub running_event_ids:       Arc<RwLock<Vec<EventId>>>,
+    pub runtime_worker:          Addr<RuntimeWorker<HA>>,
+    pub rpc_server:              JsonRpcServer,
+    pub shared_memory:           Arc<RwLock<PersistentMemory>>,
+    pub programs:                Arc<RwLock<ProgramRegistry>>,
+    /// Changed program files are only reloaded when set
+    pub program_reload_interval: Option<Duration>,
 }
 
 impl<HA: HostAdapter> App<HA> {
@@ -41,19 +46,24 @@ impl<HA: HostAdapter> App<HA> {
         // the same sender for the RPC)
         let p2p_command_sender_channel_clone = p2p_command_sender_channel.clone();
 
-        let shared_memory = Arc::new(RwLock::new(InMemory::default()));
+        let shared_memory = Arc::new(RwLock::new(load_shared_memory(&node_config)));
+        let programs = Arc::new(RwLock::new(load_programs(&node_config)));
+        let program_reload_interval = node_config.program_reload_interval;
         // Hack to get around Copy requirement for move closure.
         let sm_clone = shared_memory.clone();
+        let programs_clone = programs.clone();
         let runtime_worker = SyncArbiter::start(node_config.runtime_worker_threads, move || RuntimeWorker {
             runtime:                    None,
             node_config:                node_config.clone(),
             chain_configs:              chain_configs.clone(),
             p2p_command_sender_channel: p2p_command_sender_channel_clone.clone(),
             shared_memory:              sm_clone.clone(),
+            programs:                   programs_clone.clone(),
         });
 
         let rpc_server = JsonRpcServer::start(
             runtime_worker.clone(),
+            programs.clone(),
             rpc_server_address,
             p2p_command_sender_channel.clone(),
             disocvery_status.clone(),
@@ -67,10 +77,51 @@ impl<HA: HostAdapter> App<HA> {
             runtime_worker,
             rpc_server,
             shared_memory,
+            programs,
+            program_reload_interval,
         }
     }
 }
 
+/// Loads the shared memory persisted by a previous run, the shared memory only
+/// lives in memory when no path is configured
+fn load_shared_memory(node_config: &NodeConfig) -> PersistentMemory {
+    match &node_config.shared_memory_path {
+        Some(path) => {
+            let shared_memory = PersistentMemory::open(path, PersistentMemory::SNAPSHOT_INTERVAL)
+                .expect("Error loading persisted shared memory");
+            info!("Loaded shared memory from {}", path.display());
+
+            shared_memory
+        }
+        None => PersistentMemory::default(),
+    }
+}
+
+/// Compiles the consensus binary and all the programs in the programs
+/// directory, once for all runtime workers
+fn load_programs(node_config: &NodeConfig) -> ProgramRegistry {
+    let mut programs = ProgramRegistry::new(node_config).expect("Error creating program registry");
+
+    let consensus = programs
+        .compile_file(
+            ProgramRegistry::CONSENSUS,
+            &node_config.consensus_wasm_path,
+            Some(ProgramManifest::unrestricted()),
+        )
+        .expect("Error loading consensus program");
+    programs.insert(consensus);
+
+    if node_config.programs_path.is_dir() {
+        let names = programs
+            .load_dir(&node_config.programs_path)
+            .expect("Error loading programs");
+        info!("Loaded programs {names:?} from {}", node_config.programs_path.display());
+    }
+
+    programs
+}
+
 impl<HA: HostAdapter> Actor for App<HA> {
     type Context = Context<Self>;
 
@@ -92,6 +143,11 @@ impl<HA: HostAdapter> Actor for App<HA> {
         host.do_send(BatchTickManager);
 
         ctx.notify(job_manager::StartJobManager);
+
+        if let Some(interval) = self.program_reload_interval {
+            info!("Watching programs for changes every {interval:?}");
+            ctx.notify(program_watcher::WatchPrograms::new(interval));
+        }
     }
 
     fn stopped(&mut self, _ctx: &mut Self::Context) {
@@ -0,0 +1,85 @@
+use std::{
+    collections::HashMap,
+    fs,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use actix::{AsyncContext, Handler, Message};
+use parking_lot::RwLock;
+use seda_runtime::{HostAdapter, ProgramRegistry};
+use tracing::{error, info};
+
+use crate::{app::App, Result};
+
+/// Checks the files the programs were loaded from for changes and reloads the
+/// changed programs. Runtime jobs that already started finish on the version
+/// they started with, the next jobs run the reloaded version.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct WatchPrograms {
+    pub interval: Duration,
+    /// The last seen modification time of every program file, by program name
+    pub modified: HashMap<String, SystemTime>,
+}
+
+impl WatchPrograms {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            modified: HashMap::new(),
+        }
+    }
+}
+
+impl<HA: HostAdapter> Handler<WatchPrograms> for App<HA> {
+    type Result = ();
+
+    fn handle(&mut self, mut msg: WatchPrograms, ctx: &mut Self::Context) -> Self::Result {
+        let changed: Vec<String> = self
+            .programs
+            .read()
+            .programs()
+            .filter_map(|program| {
+                let modified = fs::metadata(program.path.as_ref()?)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()?;
+                let previous = msg.modified.insert(program.name.clone(), modified)?;
+
+                (previous != modified).then(|| program.name.clone())
+            })
+            .collect();
+
+        for name in changed {
+            let programs = self.programs.clone();
+            actix::spawn(async move {
+                match reload_program(programs, name.clone()).await {
+                    Ok(hash) => info!("Reloaded program `{name}` ({hash})"),
+                    Err(error) => error!("Error reloading program `{name}`: {error}"),
+                }
+            });
+        }
+
+        let interval = msg.interval;
+        ctx.notify_later(msg, interval);
+    }
+}
+
+/// Compiles the current version of the program from its file and swaps it in
+/// for every runtime worker, returns the hash of the new binary. The old
+/// version stays registered when the new one is invalid, and the reload fails
+/// when the program was replaced or removed while it compiled.
+pub async fn reload_program(programs: Arc<RwLock<ProgramRegistry>>, name: String) -> Result<String> {
+    let (source, compiler) = {
+        let programs = programs.read();
+        (programs.source(&name)?, programs.compiler())
+    };
+    // Compiling can take a while, so it runs on the blocking threads without
+    // holding the lock the runtime workers need to start jobs
+    let compile_source = source.clone();
+    let program = tokio::task::spawn_blocking(move || compile_source.compile(&compiler)).await??;
+    let hash = program.hash.clone();
+    programs.write().replace(&source, program)?;
+
+    Ok(hash)
+}
@@ -17,6 +17,10 @@ impl<HA: HostAdapter> Handler<Shutdown> for App<HA> {
             error!("Some error happened while closing RPC: {}", error);
         }
 
+        if let Err(error) = self.shared_memory.write().snapshot() {
+            error!("Some error happened while persisting the shared memory: {}", error);
+        }
+
         // Close actix system
         System::current().stop();
     }
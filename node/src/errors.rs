@@ -14,6 +14,10 @@ pub enum NodeError {
     RuqliteError(#[from] rusqlite::Error),
     #[error("Chain Adapter Error: {0}")]
     ChainAdapterError(#[from] seda_chains::ChainAdapterError),
+    #[error(transparent)]
+    RuntimeError(#[from] seda_runtime::RuntimeError),
+    #[error("Task Error: {0}")]
+    JoinError(#[from] tokio::task::JoinError),
     #[error("Missing app actor address in host adapter, was the node booted?")]
     MissingAppActorAddress,
 }
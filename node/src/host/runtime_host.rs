@@ -1,47 +1,44 @@
 use actix::prelude::*;
 use seda_chains::{AnotherChain, ChainAdapterTrait, Client, NearChain};
 use seda_config::{ChainConfigs, NodeConfig};
-use seda_runtime::HostAdapter;
+use seda_runtime::{Adapter, ChainInteractor, ComposedAdapter, DatabaseAdapter, EventSink, HttpAdapter};
 use seda_runtime_sdk::{events::Event, Chain};
 
 use crate::{ChainCall, ChainView, DatabaseGet, DatabaseSet, Host, HttpFetch, NodeError, Result, TriggerEvent};
 
-/// A communication layer between Actix and the runtime
-pub struct RuntimeAdapter {
-    pub chains_config:  ChainConfigs,
-    pub another_client: Client,
-    pub near_client:    Client,
-}
+/// The components of the node, each one a communication layer between Actix
+/// and the runtime
+#[derive(Adapter)]
+#[adapter(
+    database = HostDatabase,
+    http = HostHttp,
+    chain_interactor = HostChainInteractor,
+    event_sink = HostEventSink
+)]
+pub struct RuntimeAdapterTypes;
+
+pub type RuntimeAdapter = ComposedAdapter<RuntimeAdapterTypes>;
+
+pub struct HostDatabase;
 
 #[async_trait::async_trait]
-impl HostAdapter for RuntimeAdapter {
+impl DatabaseAdapter for HostDatabase {
     type Error = NodeError;
 
-    async fn new(config: ChainConfigs) -> Result<Self> {
-        Ok(Self {
-            another_client: Client::Another(AnotherChain::new_client(&config.another)?),
-            near_client:    Client::Near(NearChain::new_client(&config.near)?),
-            chains_config:  config,
-        })
+    async fn new(_config: &ChainConfigs) -> Result<Self> {
+        Ok(Self)
     }
 
-    fn select_client_from_chain(&self, chain: Chain) -> Client {
-        match chain {
-            Chain::Another => self.another_client.clone(),
-            Chain::Near => self.near_client.clone(),
-        }
-    }
-
-    async fn db_get(&self, key: &str) -> Result<Option<String>> {
-        let host_actor = Host::<Self>::from_registry();
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        let host_actor = Host::<RuntimeAdapter>::from_registry();
 
         let result = host_actor.send(DatabaseGet { key: key.to_string() }).await??;
 
         Ok(result)
     }
 
-    async fn db_set(&self, key: &str, value: &str) -> Result<()> {
-        let host_actor = Host::<Self>::from_registry();
+    async fn set(&self, key: &str, value: &str) -> Result<()> {
+        let host_actor = Host::<RuntimeAdapter>::from_registry();
 
         host_actor
             .send(DatabaseSet {
@@ -52,16 +49,53 @@ impl HostAdapter for RuntimeAdapter {
 
         Ok(())
     }
+}
 
-    async fn http_fetch(&self, url: &str) -> Result<String> {
-        let host_actor = Host::<Self>::from_registry();
+pub struct HostHttp;
+
+#[async_trait::async_trait]
+impl HttpAdapter for HostHttp {
+    type Error = NodeError;
+
+    async fn new(_config: &ChainConfigs) -> Result<Self> {
+        Ok(Self)
+    }
+
+    async fn fetch(&self, url: &str) -> Result<String> {
+        let host_actor = Host::<RuntimeAdapter>::from_registry();
 
         let result = host_actor.send(HttpFetch { url: url.to_string() }).await?;
 
         Ok(result)
     }
+}
 
-    async fn chain_call(
+pub struct HostChainInteractor {
+    pub chains_config:  ChainConfigs,
+    pub another_client: Client,
+    pub near_client:    Client,
+}
+
+#[async_trait::async_trait]
+impl ChainInteractor for HostChainInteractor {
+    type Error = NodeError;
+
+    async fn new(config: &ChainConfigs) -> Result<Self> {
+        Ok(Self {
+            another_client: Client::Another(AnotherChain::new_client(&config.another)?),
+            near_client:    Client::Near(NearChain::new_client(&config.near)?),
+            chains_config:  config.clone(),
+        })
+    }
+
+    fn select_client_from_chain(&self, chain: Chain) -> Client {
+        match chain {
+            Chain::Another => self.another_client.clone(),
+            Chain::Near => self.near_client.clone(),
+        }
+    }
+
+    async fn call(
         &self,
         chain: Chain,
         contract_id: &str,
@@ -70,7 +104,7 @@ impl HostAdapter for RuntimeAdapter {
         deposit: u128,
         node_config: NodeConfig,
     ) -> Result<Vec<u8>> {
-        let host_actor = Host::<Self>::from_registry();
+        let host_actor = Host::<RuntimeAdapter>::from_registry();
         let client = self.select_client_from_chain(chain);
         let result = host_actor
             .send(ChainCall {
@@ -88,8 +122,8 @@ impl HostAdapter for RuntimeAdapter {
         Ok(result)
     }
 
-    async fn chain_view(&self, chain: Chain, contract_id: &str, method_name: &str, args: Vec<u8>) -> Result<Vec<u8>> {
-        let host_actor = Host::<Self>::from_registry();
+    async fn view(&self, chain: Chain, contract_id: &str, method_name: &str, args: Vec<u8>) -> Result<Vec<u8>> {
+        let host_actor = Host::<RuntimeAdapter>::from_registry();
         let client = self.select_client_from_chain(chain);
         let result = host_actor
             .send(ChainView {
@@ -103,9 +137,20 @@ impl HostAdapter for RuntimeAdapter {
 
         Ok(result)
     }
+}
+
+pub struct HostEventSink;
+
+#[async_trait::async_trait]
+impl EventSink for HostEventSink {
+    type Error = NodeError;
+
+    async fn new(_config: &ChainConfigs) -> Result<Self> {
+        Ok(Self)
+    }
 
-    async fn trigger_event(&self, event: Event) -> Result<()> {
-        let host_actor = Host::<Self>::from_registry();
+    async fn trigger(&self, event: Event) -> Result<()> {
+        let host_actor = Host::<RuntimeAdapter>::from_registry();
         host_actor.send(TriggerEvent { event }).await??;
 
         Ok(())
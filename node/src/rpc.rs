@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{str::FromStr, sync::Arc};
 
 use actix::prelude::*;
 use jsonrpsee::{
@@ -6,25 +6,39 @@ use jsonrpsee::{
     proc_macros::rpc,
     server::{ServerBuilder, ServerHandle},
 };
+use parking_lot::RwLock;
 use seda_p2p::{
     libp2p::{Multiaddr, PeerId},
     DiscoveryStatus,
 };
-use seda_runtime::HostAdapter;
+use seda_runtime::{DryRun, HostAdapter, ProgramManifest, ProgramRegistry, SimulatedChainCall};
 use seda_runtime_sdk::{
     events::{Event, EventData},
     p2p::{AddPeerCommand, P2PCommand, RemovePeerCommand},
 };
-use serde_json::Value;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use tokio::sync::mpsc::Sender;
 use tracing::debug;
 
-use crate::runtime_job::{RuntimeJob, RuntimeWorker};
+use crate::{
+    app::reload_program,
+    runtime_job::{RuntimeJob, RuntimeWorker},
+};
+
+/// The output of a `cli` call
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CliOutput {
+    pub stderr:      Vec<String>,
+    /// The `chain_call` transactions a dry run would have sent
+    #[serde(default)]
+    pub chain_calls: Vec<SimulatedChainCall>,
+}
 
 #[rpc(server)]
 pub trait Rpc {
     #[method(name = "cli")]
-    async fn cli(&self, args: Vec<String>) -> Result<Vec<String>, Error>;
+    async fn cli(&self, args: Vec<String>, dry_run: Option<DryRun>) -> Result<CliOutput, Error>;
 
     #[method(name = "add_peer")]
     async fn add_peer(&self, multi_addr: String) -> Result<(), Error>;
@@ -37,17 +51,32 @@ pub trait Rpc {
 
     #[method(name = "discover_peers")]
     async fn discover_peers(&self) -> Result<(), Error>;
+
+    #[method(name = "register_program")]
+    async fn register_program(
+        &self,
+        name: String,
+        wasm_binary_hex: String,
+        manifest_toml: Option<String>,
+    ) -> Result<String, Error>;
+
+    #[method(name = "reload_program")]
+    async fn reload_program(&self, name: String) -> Result<String, Error>;
+
+    #[method(name = "list_programs")]
+    async fn list_programs(&self) -> Result<Value, Error>;
 }
 
 pub struct CliServer<HA: HostAdapter> {
     runtime_worker:             Addr<RuntimeWorker<HA>>,
+    programs:                   Arc<RwLock<ProgramRegistry>>,
     p2p_command_sender_channel: Sender<P2PCommand>,
     discovery_status:           DiscoveryStatus,
 }
 
 #[async_trait]
 impl<HA: HostAdapter> RpcServer for CliServer<HA> {
-    async fn cli(&self, args: Vec<String>) -> Result<Vec<String>, Error> {
+    async fn cli(&self, args: Vec<String>, dry_run: Option<DryRun>) -> Result<CliOutput, Error> {
         debug!("{:?}", &args);
 
         let result = self
@@ -57,11 +86,16 @@ impl<HA: HostAdapter> RpcServer for CliServer<HA> {
                     id:   "test".to_string(),
                     data: EventData::CliCall(args),
                 },
+                dry_run,
             })
             .await
             .map_err(|err| Error::Custom(err.to_string()))?;
+        let vm_result = result.map_err(|err| Error::Custom(err.to_string()))?.vm_result;
 
-        Ok(result.map_err(|err| Error::Custom(err.to_string()))?.vm_result.stderr)
+        Ok(CliOutput {
+            stderr:      vm_result.stderr,
+            chain_calls: vm_result.chain_calls,
+        })
     }
 
     async fn add_peer(&self, multi_addr: String) -> Result<(), Error> {
@@ -106,6 +140,68 @@ impl<HA: HostAdapter> RpcServer for CliServer<HA> {
 
         Ok(())
     }
+
+    async fn register_program(
+        &self,
+        name: String,
+        wasm_binary_hex: String,
+        manifest_toml: Option<String>,
+    ) -> Result<String, Error> {
+        self.programs
+            .read()
+            .check_new_name(&name)
+            .map_err(|err| Error::Custom(err.to_string()))?;
+
+        let wasm_binary = hex::decode(wasm_binary_hex).map_err(|err| Error::Custom(err.to_string()))?;
+        let manifest = match manifest_toml {
+            Some(manifest_toml) => ProgramManifest::from_toml(&manifest_toml),
+            None => ProgramManifest::from_custom_section(&wasm_binary)
+                .map(|manifest| manifest.unwrap_or_else(ProgramManifest::limited)),
+        }
+        .map_err(|err| Error::Custom(err.to_string()))?;
+        // Anyone who can reach the RPC can register a program, so it gets no more
+        // than a program that does not declare a manifest
+        if !manifest.is_within(&ProgramManifest::limited()) {
+            return Err(Error::Custom(
+                "Programs registered over RPC can not be granted more than the limited manifest".to_string(),
+            ));
+        }
+        let compiler = self.programs.read().compiler();
+        let program_name = name.clone();
+
+        // Compiling can take a while, so it should not block the RPC server or
+        // hold the lock on the registry
+        let program = tokio::task::spawn_blocking(move || compiler.compile(&program_name, &wasm_binary, manifest))
+            .await
+            .map_err(|err| Error::Custom(err.to_string()))?
+            .map_err(|err| Error::Custom(err.to_string()))?;
+        let hash = program.hash.clone();
+
+        // The name may have been registered while the program compiled
+        let mut programs = self.programs.write();
+        programs
+            .check_new_name(&name)
+            .map_err(|err| Error::Custom(err.to_string()))?;
+        programs.insert(program);
+
+        Ok(hash)
+    }
+
+    async fn reload_program(&self, name: String) -> Result<String, Error> {
+        reload_program(self.programs.clone(), name)
+            .await
+            .map_err(|err| Error::Custom(err.to_string()))
+    }
+
+    async fn list_programs(&self) -> Result<Value, Error> {
+        let programs = self.programs.read();
+        let result = programs
+            .programs()
+            .map(|program| (program.name.clone(), json!(program.hash)))
+            .collect();
+
+        Ok(Value::Object(result))
+    }
 }
 pub struct JsonRpcServer {
     handle: ServerHandle,
@@ -114,6 +210,7 @@ pub struct JsonRpcServer {
 impl JsonRpcServer {
     pub async fn start<HA: HostAdapter>(
         runtime_worker: Addr<RuntimeWorker<HA>>,
+        programs: Arc<RwLock<ProgramRegistry>>,
         addrs: &str,
         p2p_command_sender_channel: Sender<P2PCommand>,
         discovery_status: DiscoveryStatus,
@@ -121,6 +218,7 @@ impl JsonRpcServer {
         let server = ServerBuilder::default().build(addrs).await?;
         let rpc = CliServer {
             runtime_worker,
+            programs,
             p2p_command_sender_channel,
             discovery_status,
         };
@@ -1,13 +1,23 @@
-use std::{fs, sync::Arc};
+use std::sync::Arc;
 
 use actix::{prelude::*, Handler, Message};
 use parking_lot::{Mutex, RwLock};
 use seda_config::{ChainConfigs, NodeConfig};
-use seda_runtime::{HostAdapter, InMemory, Result, RunnableRuntime, Runtime, VmConfig, VmResult};
+use seda_runtime::{
+    DryRun,
+    HostAdapter,
+    InMemory,
+    PersistentMemory,
+    ProgramRegistry,
+    Result,
+    RunnableRuntime,
+    Runtime,
+    VmConfig,
+    VmResult,
+};
 use seda_runtime_sdk::{
     events::{Event, EventData},
     p2p::P2PCommand,
-    FromBytes,
 };
 use tokio::sync::mpsc::Sender;
 use tracing::info;
@@ -20,7 +30,9 @@ pub struct RuntimeJobResult {
 #[derive(Message)]
 #[rtype(result = "Result<RuntimeJobResult>")]
 pub struct RuntimeJob {
-    pub event: Event,
+    pub event:   Event,
+    /// Runs the job without sending its `chain_call` transactions
+    pub dry_run: Option<DryRun>,
 }
 
 pub struct RuntimeWorker<HA: HostAdapter> {
@@ -28,7 +40,8 @@ pub struct RuntimeWorker<HA: HostAdapter> {
     pub node_config:                NodeConfig,
     pub chain_configs:              ChainConfigs,
     pub p2p_command_sender_channel: Sender<P2PCommand>,
-    pub shared_memory:              Arc<RwLock<InMemory>>,
+    pub shared_memory:              Arc<RwLock<PersistentMemory>>,
+    pub programs:                   Arc<RwLock<ProgramRegistry>>,
 }
 
 impl<HA: HostAdapter> Actor for RuntimeWorker<HA> {
@@ -38,18 +51,13 @@ impl<HA: HostAdapter> Actor for RuntimeWorker<HA> {
         let node_config = self.node_config.clone();
         let chain_configs = self.chain_configs.clone();
         let shared_memory = self.shared_memory.clone();
-        // TODO: when conditionally loading the consensus binary see if it allows full
-        // or limited features
-        let mut runtime = futures::executor::block_on(async move {
-            Runtime::new(node_config, chain_configs, shared_memory, false)
+        let programs = self.programs.clone();
+        let runtime = futures::executor::block_on(async move {
+            Runtime::new(node_config, chain_configs, shared_memory, programs)
                 .await
                 .expect("TODO")
         });
 
-        runtime
-            .init(fs::read(&self.node_config.consensus_wasm_path).unwrap())
-            .unwrap();
-
         self.runtime = Some(runtime);
     }
 }
@@ -60,21 +68,26 @@ impl<HA: HostAdapter> Handler<RuntimeJob> for RuntimeWorker<HA> {
     fn handle(&mut self, msg: RuntimeJob, _ctx: &mut Self::Context) -> Self::Result {
         let memory_adapter = Arc::new(Mutex::new(InMemory::default()));
 
-        let args: Vec<String> = match msg.event.data {
-            EventData::BatchChainTick => vec!["batch".to_string()],
-            EventData::ChainTick => vec![],
-            EventData::CliCall(args) => args,
-            // TODO: Make args accept bytes only
-            EventData::P2PMessage(message) => {
-                vec!["p2p".to_string(), String::from_bytes_vec(message.data).unwrap()]
-            }
+        let (args, input): (Vec<String>, Vec<u8>) = match &msg.event.data {
+            EventData::BatchChainTick => (vec!["batch".to_string()], vec![]),
+            EventData::ChainTick => (vec![], vec![]),
+            EventData::CliCall(args) => (args.clone(), vec![]),
+            // The message can be any bytes, so it is passed as input instead of an arg
+            EventData::P2PMessage(message) => (vec!["p2p".to_string()], message.data.clone()),
         };
 
         let vm_config = VmConfig {
             args,
-            program_name: "test".to_string(),
+            input,
+            program_name: ProgramRegistry::CONSENSUS.to_string(),
             debug: false,
             start_func: None,
+            gas_limit: None,
+            timeout: None,
+            // Consensus jobs have to compute the same result on every node
+            deterministic: true,
+            event: Some(msg.event),
+            dry_run: msg.dry_run,
         };
 
         let runtime = self.runtime.as_ref().unwrap();
@@ -1,3 +1,7 @@
+use std::time::Duration;
+
+use seda_runtime_sdk::events::Event;
+use serde::{Deserialize, Serialize};
 use wasmer_wasi::{WasiEnv, WasiState};
 
 #[derive(Debug, Clone)]
@@ -11,10 +15,46 @@ pub struct VmConfig {
     /// Arguments to pass to the WASM binary
     pub args: Vec<String>,
 
+    /// Raw bytes the WASM binary can read with the `execution_input` import
+    pub input: Vec<u8>,
+
     pub debug: bool,
+
+    /// The maximum amount of gas the execution may consume, defaults to
+    /// [VmConfig::DEFAULT_GAS_LIMIT]
+    pub gas_limit: Option<u64>,
+
+    /// The maximum wall-clock time of the execution, overrides the runtime job
+    /// timeout of the node config
+    pub timeout: Option<Duration>,
+
+    /// Stubs the WASI clock, seeds the WASI random source, restricts the env
+    /// variables and adds an input hash to the [crate::VmResult]. Consensus
+    /// critical executions should be deterministic so nodes can cross-check
+    /// their results.
+    pub deterministic: bool,
+
+    /// The event that started the execution, the WASM binary can read it
+    /// with the `execution_context` import
+    pub event: Option<Event>,
+
+    /// Captures the `chain_call` promises in the [crate::VmResult] instead of
+    /// signing and sending them. The other side effects, the database and
+    /// shared memory writes, events and P2P broadcasts, are dropped.
+    pub dry_run: Option<DryRun>,
+}
+
+/// How a dry run resolves the `chain_call` promises it captures
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DryRun {
+    /// The result every captured `chain_call` is fulfilled with
+    #[serde(default)]
+    pub chain_call_result: Vec<u8>,
 }
 
 impl VmConfig {
+    pub const DEFAULT_GAS_LIMIT: u64 = 300_000_000_000;
+
     pub fn finalize(self) -> WasiEnv {
         let mut wasi_state = WasiState::new(&self.program_name);
         wasi_state.args(&self.args);
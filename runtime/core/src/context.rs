@@ -1,21 +1,60 @@
 use std::sync::Arc;
 
-use parking_lot::{Mutex, RwLock};
+use parking_lot::Mutex;
 use seda_config::NodeConfig;
-use wasmer::{HostEnvInitError, Instance, LazyInit, Memory, WasmerEnv};
+use seda_runtime_sdk::{ExecutionContext, PromiseAction, PromiseStatus};
+use wasmer::{Global, HostEnvInitError, Instance, LazyInit, Memory, WasmerEnv};
 
 use super::PromiseQueue;
-use crate::InMemory;
+use crate::{
+    vm_result::ExecutionResult,
+    DeterministicRandom,
+    ExecutionDeadline,
+    InMemory,
+    MemoryLimitFlag,
+    ProgramManifest,
+    SharedMemoryAccess,
+    SharedMemoryLog,
+    SharedMemoryTransaction,
+    VmResultStatus,
+};
+
+/// Resolves a promise action while the WASM binary waits for it, see the
+/// `promise_await` import
+pub type AwaitAction = Arc<dyn Fn(&PromiseAction) -> ExecutionResult<PromiseStatus> + Send + Sync>;
 
 #[derive(Clone)]
 pub struct VmContext {
     pub result:                Arc<Mutex<Vec<u8>>>,
     pub memory:                LazyInit<Memory>,
+    pub remaining_points:      LazyInit<Global>,
+    pub points_exhausted:      LazyInit<Global>,
     pub memory_adapter:        Arc<Mutex<InMemory>>,
-    pub shared_memory:         Arc<RwLock<InMemory>>,
+    /// Stages the shared memory writes of the whole job
+    pub shared_memory:         Arc<Mutex<SharedMemoryTransaction>>,
     pub promise_queue:         Arc<Mutex<PromiseQueue>>,
     pub current_promise_queue: Arc<Mutex<PromiseQueue>>,
+    /// The program that is executed, its shared memory writes count against
+    /// its quota
+    pub program_name:          String,
+    /// The capabilities of the executed program
+    pub manifest:              Arc<ProgramManifest>,
+    /// The raw input bytes of the executed function
+    pub input:                 Arc<Vec<u8>>,
+    /// The same for every function the job calls
+    pub execution_context:     Arc<ExecutionContext>,
     pub node_config:           NodeConfig,
+    pub deadline:              ExecutionDeadline,
+    /// Set by a host import that trapped because a limit was exceeded
+    pub limit_exceeded:        Arc<Mutex<Option<VmResultStatus>>>,
+    /// Set when the memory of the instance refused to grow
+    pub memory_limit_exceeded: MemoryLimitFlag,
+    /// Only set when the execution is recorded
+    pub shared_memory_log:     Option<SharedMemoryLog>,
+    /// Only set when the execution is deterministic
+    pub deterministic_random:  Option<Arc<Mutex<DeterministicRandom>>>,
+    /// Only set when the execution can await promises
+    pub await_action:          Option<AwaitAction>,
 }
 
 impl WasmerEnv for VmContext {
@@ -23,26 +62,63 @@ impl WasmerEnv for VmContext {
         let memory: Memory = instance.exports.get_with_generics_weak("memory")?;
         self.memory.initialize(memory);
 
+        let remaining_points: Global = instance
+            .exports
+            .get_with_generics_weak("wasmer_metering_remaining_points")?;
+        self.remaining_points.initialize(remaining_points);
+        let points_exhausted: Global = instance
+            .exports
+            .get_with_generics_weak("wasmer_metering_points_exhausted")?;
+        self.points_exhausted.initialize(points_exhausted);
+
         Ok(())
     }
 }
 
 impl VmContext {
+    #[allow(clippy::too_many_arguments)]
     pub fn create_vm_context(
         memory_adapter: Arc<Mutex<InMemory>>,
-        shared_memory: Arc<RwLock<InMemory>>,
+        shared_memory: Arc<Mutex<SharedMemoryTransaction>>,
         current_promise_queue: Arc<Mutex<PromiseQueue>>,
         promise_queue: Arc<Mutex<PromiseQueue>>,
+        program_name: String,
+        manifest: Arc<ProgramManifest>,
+        input: Vec<u8>,
+        execution_context: Arc<ExecutionContext>,
         node_config: NodeConfig,
+        deadline: ExecutionDeadline,
+        shared_memory_log: Option<SharedMemoryLog>,
+        deterministic_random: Option<Arc<Mutex<DeterministicRandom>>>,
+        await_action: Option<AwaitAction>,
     ) -> VmContext {
         VmContext {
             result: Arc::new(Mutex::new(Vec::new())),
             memory_adapter,
             shared_memory,
             memory: LazyInit::new(),
+            remaining_points: LazyInit::new(),
+            points_exhausted: LazyInit::new(),
             current_promise_queue,
             promise_queue,
+            program_name,
+            manifest,
+            input: Arc::new(input),
+            execution_context,
             node_config,
+            deadline,
+            limit_exceeded: Arc::new(Mutex::new(None)),
+            memory_limit_exceeded: MemoryLimitFlag::default(),
+            shared_memory_log,
+            deterministic_random,
+            await_action,
+        }
+    }
+
+    /// Records the shared memory access when the execution is recorded
+    pub fn log_shared_memory(&self, access: SharedMemoryAccess) {
+        if let Some(shared_memory_log) = &self.shared_memory_log {
+            shared_memory_log.lock().push(access);
         }
     }
 }
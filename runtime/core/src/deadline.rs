@@ -0,0 +1,80 @@
+use std::{
+    future::Future,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use futures::future::{select, Either};
+use futures_timer::Delay;
+
+/// The wall-clock deadline of a single execution. Host promises are raced
+/// against it, host imports refuse to run once it has passed and running WASM
+/// instances are interrupted by a [DeadlineWatchdog], so neither a slow RPC
+/// endpoint nor a guest stuck in a loop can stall a runtime worker.
+#[derive(Clone, Copy, Debug)]
+pub struct ExecutionDeadline {
+    deadline: Instant,
+}
+
+impl ExecutionDeadline {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + timeout,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    /// Calls `on_expired` from another thread once the deadline passes, unless
+    /// the returned watchdog is dropped before that
+    pub fn watch<F: FnOnce() + Send + 'static>(&self, on_expired: F) -> DeadlineWatchdog {
+        let (cancel, cancelled) = mpsc::channel::<()>();
+        let remaining = self.remaining();
+        let thread = thread::spawn(move || {
+            if let Err(RecvTimeoutError::Timeout) = cancelled.recv_timeout(remaining) {
+                on_expired();
+            }
+        });
+
+        DeadlineWatchdog {
+            cancel: Some(cancel),
+            thread: Some(thread),
+        }
+    }
+
+    /// Runs the future until it completes or the deadline passes, in which
+    /// case the future is dropped (cancelled) and `None` is returned.
+    pub async fn run<F: Future>(&self, future: F) -> Option<F::Output> {
+        let timer = Delay::new(self.remaining());
+        futures::pin_mut!(future);
+
+        match select(future, timer).await {
+            Either::Left((output, _)) => Some(output),
+            Either::Right(_) => None,
+        }
+    }
+}
+
+/// Guards a running WASM instance, see [ExecutionDeadline::watch]. Dropping it
+/// stops the watchdog thread.
+pub struct DeadlineWatchdog {
+    cancel: Option<mpsc::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for DeadlineWatchdog {
+    fn drop(&mut self) {
+        // Disconnecting the channel wakes the thread up without calling back
+        self.cancel.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use seda_runtime_sdk::{ExecutionContext, PromiseAction};
+use sha2::{Digest, Sha256};
+use wasmer::wasmparser::{Operator, Parser, Payload};
+
+use crate::{PromiseQueue, Result, SharedMemoryAccess, VmConfig};
+
+/// The only env variables a deterministic execution can see, the others
+/// (like the ones a replay is started with) can differ between nodes
+pub const DETERMINISTIC_ENV_VARS: &[&str] = &["ORACLE_CONTRACT_ID"];
+
+/// Replaces the WASI random source of a deterministic execution. The bytes
+/// are a sha256 stream seeded with the inputs the execution started with, so
+/// every node draws the same numbers.
+#[derive(Debug)]
+pub struct DeterministicRandom {
+    seed:    [u8; 32],
+    counter: u64,
+}
+
+impl DeterministicRandom {
+    pub fn new(seed: &[u8]) -> Self {
+        Self {
+            seed:    Sha256::digest(seed).into(),
+            counter: 0,
+        }
+    }
+
+    pub fn fill(&mut self, buffer: &mut [u8]) {
+        for chunk in buffer.chunks_mut(32) {
+            let block = Sha256::new()
+                .chain_update(self.seed)
+                .chain_update(self.counter.to_le_bytes())
+                .finalize();
+            chunk.copy_from_slice(&block[..chunk.len()]);
+            self.counter += 1;
+        }
+    }
+}
+
+/// The inputs an execution starts with, encoded as JSON. The node keys in the
+/// execution context are left out, they identify the node instead of the
+/// job, and so is the slot, which every node estimates from its own clock.
+pub fn execution_inputs(
+    program_hash: &str,
+    config: &VmConfig,
+    env: &[(String, String)],
+    execution_context: &ExecutionContext,
+) -> Vec<u8> {
+    serde_json::to_vec(&(
+        program_hash,
+        &config.start_func,
+        &config.args,
+        &config.input,
+        env,
+        config.gas_limit,
+        &config.event,
+        &execution_context.contract_id,
+        execution_context.start_timestamp,
+    ))
+    .expect("Execution inputs are always serializable")
+}
+
+/// Hashes everything a deterministic execution received from the host: the
+/// inputs it started with, the results of the promise actions resolved by the
+/// host and the shared memory values and keys it read. Two nodes with the same
+/// input hash have to compute the same result.
+pub fn input_hash(
+    execution_inputs: &[u8],
+    promise_queue_trace: &[PromiseQueue],
+    shared_memory: &[SharedMemoryAccess],
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(execution_inputs);
+
+    let host_promises = promise_queue_trace
+        .iter()
+        .flat_map(|promise_queue| &promise_queue.queue)
+        .filter(|promise| !matches!(promise.action, PromiseAction::CallSelf(_)) && !promise.action.is_combinator());
+    for promise in host_promises {
+        // JSON values are self delimiting, so concatenating them is unambiguous
+        let interaction =
+            serde_json::to_vec(&(&promise.action, &promise.status)).expect("Promises are always serializable");
+        hasher.update(interaction);
+    }
+
+    for access in shared_memory {
+        if !access.is_write() {
+            hasher.update(serde_json::to_vec(access).expect("Shared memory accesses are always serializable"));
+        }
+    }
+
+    hex::encode(hasher.finalize())
+}
+
+/// Whether the WASM binary contains any floating point instruction. NaN bit
+/// patterns are not specified by WASM, so floats can make nodes diverge.
+pub fn uses_float_ops(wasm_binary: &[u8]) -> Result<bool> {
+    for payload in Parser::new(0).parse_all(wasm_binary) {
+        if let Payload::CodeSectionEntry(body) = payload.map_err(|error| error.to_string())? {
+            for operator in body.get_operators_reader().map_err(|error| error.to_string())? {
+                if is_float_op(&operator.map_err(|error| error.to_string())?) {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+/// Whether the instruction reads, writes or produces a float, conversions and
+/// SIMD lanes included
+fn is_float_op(operator: &Operator) -> bool {
+    use Operator::*;
+
+    matches!(
+        operator,
+        F32Load { .. }
+            | F64Load { .. }
+            | F32Store { .. }
+            | F64Store { .. }
+            | F32Const { .. }
+            | F64Const { .. }
+            | F32Eq
+            | F32Ne
+            | F32Lt
+            | F32Gt
+            | F32Le
+            | F32Ge
+            | F64Eq
+            | F64Ne
+            | F64Lt
+            | F64Gt
+            | F64Le
+            | F64Ge
+            | F32Abs
+            | F32Neg
+            | F32Ceil
+            | F32Floor
+            | F32Trunc
+            | F32Nearest
+            | F32Sqrt
+            | F32Add
+            | F32Sub
+            | F32Mul
+            | F32Div
+            | F32Min
+            | F32Max
+            | F32Copysign
+            | F64Abs
+            | F64Neg
+            | F64Ceil
+            | F64Floor
+            | F64Trunc
+            | F64Nearest
+            | F64Sqrt
+            | F64Add
+            | F64Sub
+            | F64Mul
+            | F64Div
+            | F64Min
+            | F64Max
+            | F64Copysign
+            | I32TruncF32S
+            | I32TruncF32U
+            | I32TruncF64S
+            | I32TruncF64U
+            | I64TruncF32S
+            | I64TruncF32U
+            | I64TruncF64S
+            | I64TruncF64U
+            | F32ConvertI32S
+            | F32ConvertI32U
+            | F32ConvertI64S
+            | F32ConvertI64U
+            | F32DemoteF64
+            | F64ConvertI32S
+            | F64ConvertI32U
+            | F64ConvertI64S
+            | F64ConvertI64U
+            | F64PromoteF32
+            | I32ReinterpretF32
+            | I64ReinterpretF64
+            | F32ReinterpretI32
+            | F64ReinterpretI64
+            | I32TruncSatF32S
+            | I32TruncSatF32U
+            | I32TruncSatF64S
+            | I32TruncSatF64U
+            | I64TruncSatF32S
+            | I64TruncSatF32U
+            | I64TruncSatF64S
+            | I64TruncSatF64U
+            | F32x4Splat
+            | F64x2Splat
+            | F32x4ExtractLane { .. }
+            | F32x4ReplaceLane { .. }
+            | F64x2ExtractLane { .. }
+            | F64x2ReplaceLane { .. }
+            | F32x4Eq
+            | F32x4Ne
+            | F32x4Lt
+            | F32x4Gt
+            | F32x4Le
+            | F32x4Ge
+            | F64x2Eq
+            | F64x2Ne
+            | F64x2Lt
+            | F64x2Gt
+            | F64x2Le
+            | F64x2Ge
+            | F32x4Ceil
+            | F32x4Floor
+            | F32x4Trunc
+            | F32x4Nearest
+            | F32x4Abs
+            | F32x4Neg
+            | F32x4Sqrt
+            | F32x4Add
+            | F32x4Sub
+            | F32x4Mul
+            | F32x4Div
+            | F32x4Min
+            | F32x4Max
+            | F32x4PMin
+            | F32x4PMax
+            | F64x2Ceil
+            | F64x2Floor
+            | F64x2Trunc
+            | F64x2Nearest
+            | F64x2Abs
+            | F64x2Neg
+            | F64x2Sqrt
+            | F64x2Add
+            | F64x2Sub
+            | F64x2Mul
+            | F64x2Div
+            | F64x2Min
+            | F64x2Max
+            | F64x2PMin
+            | F64x2PMax
+            | I32x4TruncSatF32x4S
+            | I32x4TruncSatF32x4U
+            | F32x4ConvertI32x4S
+            | F32x4ConvertI32x4U
+            | I32x4TruncSatF64x2SZero
+            | I32x4TruncSatF64x2UZero
+            | F64x2ConvertLowI32x4S
+            | F64x2ConvertLowI32x4U
+            | F32x4DemoteF64x2Zero
+            | F64x2PromoteLowF32x4
+    )
+}
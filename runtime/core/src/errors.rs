@@ -28,6 +28,9 @@ pub enum RuntimeError {
     #[error("VM Host Error: {0}")]
     VmHostError(String),
 
+    #[error("Invalid program `{name}`: {}", .problems.join("; "))]
+    InvalidProgram { name: String, problems: Vec<String> },
+
     #[error("{0}")]
     WasiFsError(#[from] FsError),
 
@@ -54,6 +57,9 @@ pub enum RuntimeError {
     #[error("BN254 Error: {0}")]
     Bn254Error(#[from] bn254::Error),
 
+    #[error("Crypto Error: {0}")]
+    CryptoError(#[from] seda_crypto::CryptoError),
+
     #[error("SDK Error: {0}")]
     SDKError(#[from] SDKError),
 }
@@ -0,0 +1,140 @@
+use std::{
+    io::{self, Read, Seek, SeekFrom, Write},
+    sync::Arc,
+};
+
+use parking_lot::Mutex;
+use seda_runtime_sdk::Level;
+use tracing::{info, warn, Span};
+use wasmer_wasi::{WasiFile, WasiFsError};
+
+use crate::vm_result::{ExecutionResult, VmResultStatus};
+
+#[derive(Clone, Copy, Debug)]
+pub enum GuestStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Default)]
+struct GuestOutputBuffer {
+    /// Everything the guest wrote, up to one byte over the capacity
+    captured: Vec<u8>,
+    /// How much of the captured output has been logged
+    logged:   usize,
+}
+
+/// The stdout or stderr of a guest function. Every line is logged as soon as
+/// the guest writes it, in the span of the promise that is executing, so a
+/// hanging job still shows its output. The output is also kept for the
+/// [crate::VmResult].
+#[derive(Clone, Debug)]
+pub struct GuestOutput {
+    stream:   GuestStream,
+    span:     Span,
+    /// Output beyond the capacity is only counted, it exceeds the log limit
+    /// anyway
+    capacity: usize,
+    buffer:   Arc<Mutex<GuestOutputBuffer>>,
+}
+
+impl GuestOutput {
+    pub fn new(stream: GuestStream, span: Span, capacity: usize) -> Self {
+        Self {
+            stream,
+            span,
+            capacity,
+            buffer: Default::default(),
+        }
+    }
+
+    /// Logs what is left of the last line and returns the whole output, fails
+    /// when it is longer than the limit
+    pub fn finish(&self, limit: usize) -> ExecutionResult<String> {
+        let mut buffer = self.buffer.lock();
+        let captured = std::mem::take(&mut buffer.captured);
+        if buffer.logged < captured.len() {
+            self.log_line(&captured[buffer.logged..]);
+        }
+        buffer.logged = 0;
+
+        if captured.len() > limit {
+            return Err(VmResultStatus::LogLimitExceeded);
+        }
+
+        String::from_utf8(captured).map_err(|_| VmResultStatus::FailedToConvertVMPipeToString)
+    }
+
+    fn log_line(&self, line: &[u8]) {
+        let line = String::from_utf8_lossy(line);
+        match self.stream {
+            GuestStream::Stdout => info!(target: Level::TARGET, parent: &self.span, stream = "stdout", "{line}"),
+            GuestStream::Stderr => warn!(target: Level::TARGET, parent: &self.span, stream = "stderr", "{line}"),
+        }
+    }
+}
+
+impl Write for GuestOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut buffer = self.buffer.lock();
+        let free = (self.capacity + 1).saturating_sub(buffer.captured.len());
+        buffer.captured.extend_from_slice(&buf[..buf.len().min(free)]);
+
+        while let Some(end) = buffer.captured[buffer.logged..].iter().position(|byte| *byte == b'\n') {
+            let start = buffer.logged;
+            self.log_line(&buffer.captured[start..start + end]);
+            buffer.logged = start + end + 1;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Read for GuestOutput {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "Can not read from the guest output",
+        ))
+    }
+}
+
+impl Seek for GuestOutput {
+    fn seek(&mut self, _pos: SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(io::ErrorKind::Other, "Can not seek in the guest output"))
+    }
+}
+
+impl WasiFile for GuestOutput {
+    fn last_accessed(&self) -> u64 {
+        0
+    }
+
+    fn last_modified(&self) -> u64 {
+        0
+    }
+
+    fn created_time(&self) -> u64 {
+        0
+    }
+
+    fn size(&self) -> u64 {
+        self.buffer.lock().captured.len() as u64
+    }
+
+    fn set_len(&mut self, _new_size: u64) -> Result<(), WasiFsError> {
+        Err(WasiFsError::PermissionDenied)
+    }
+
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        Ok(())
+    }
+
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        Ok(0)
+    }
+}
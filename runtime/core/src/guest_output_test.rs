@@ -0,0 +1,22 @@
+use std::io::Write;
+
+use tracing::Span;
+
+use crate::{GuestOutput, GuestStream, VmResultStatus};
+
+#[test]
+fn test_guest_output() {
+    let output = GuestOutput::new(GuestStream::Stdout, Span::none(), 16);
+    // The guest only holds a handle, the host reads through its own
+    let mut guest = output.clone();
+    write!(guest, "first\nsec").unwrap();
+    write!(guest, "ond\n").unwrap();
+    write!(guest, "last").unwrap();
+
+    assert_eq!(output.finish(16).unwrap(), "first\nsecond\nlast");
+    assert_eq!(output.finish(16).unwrap(), "");
+
+    // Output over the capacity is dropped, but still exceeds the limit
+    write!(guest, "{}", "a".repeat(32)).unwrap();
+    assert!(matches!(output.finish(16), Err(VmResultStatus::LogLimitExceeded)));
+}
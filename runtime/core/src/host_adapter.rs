@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{error::Error, fmt::Display};
 
 use seda_chains::Client;
 use seda_config::{ChainConfigs, NodeConfig};
@@ -38,3 +38,187 @@ pub trait HostAdapter: Send + Sync + Unpin + 'static {
 
     async fn trigger_event(&self, event: Event) -> Result<(), Self::Error>;
 }
+
+/// Stores the values of `db_get` and `db_set`
+#[async_trait::async_trait]
+pub trait DatabaseAdapter: Send + Sync + Unpin + 'static {
+    type Error: Error + Send + Sync + 'static;
+
+    async fn new(config: &ChainConfigs) -> Result<Self, Self::Error>
+    where
+        Self: Sized;
+
+    async fn get(&self, key: &str) -> Result<Option<String>, Self::Error>;
+    async fn set(&self, key: &str, value: &str) -> Result<(), Self::Error>;
+}
+
+/// Fetches the URLs of `http` promises
+#[async_trait::async_trait]
+pub trait HttpAdapter: Send + Sync + Unpin + 'static {
+    type Error: Error + Send + Sync + 'static;
+
+    async fn new(config: &ChainConfigs) -> Result<Self, Self::Error>
+    where
+        Self: Sized;
+
+    async fn fetch(&self, url: &str) -> Result<String, Self::Error>;
+}
+
+/// Sends the transactions of `chain_call` and reads the contracts of
+/// `chain_view`
+#[async_trait::async_trait]
+pub trait ChainInteractor: Send + Sync + Unpin + 'static {
+    type Error: Error + Send + Sync + 'static;
+
+    async fn new(config: &ChainConfigs) -> Result<Self, Self::Error>
+    where
+        Self: Sized;
+
+    fn select_client_from_chain(&self, chain: Chain) -> Client;
+
+    async fn call(
+        &self,
+        chain: Chain,
+        contract_id: &str,
+        method_name: &str,
+        args: Vec<u8>,
+        deposit: u128,
+        node_config: NodeConfig,
+    ) -> Result<Vec<u8>, Self::Error>;
+
+    async fn view(
+        &self,
+        chain: Chain,
+        contract_id: &str,
+        method_name: &str,
+        args: Vec<u8>,
+    ) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// Receives the events of `trigger_event`
+#[async_trait::async_trait]
+pub trait EventSink: Send + Sync + Unpin + 'static {
+    type Error: Error + Send + Sync + 'static;
+
+    async fn new(config: &ChainConfigs) -> Result<Self, Self::Error>
+    where
+        Self: Sized;
+
+    async fn trigger(&self, event: Event) -> Result<(), Self::Error>;
+}
+
+/// The components a [ComposedAdapter] is made of, implemented with
+/// `#[derive(Adapter)]`:
+///
+/// ```ignore
+/// #[derive(Adapter)]
+/// #[adapter(
+///     database = NodeDatabase,
+///     http = MockHttp,
+///     chain_interactor = NodeChainInteractor,
+///     event_sink = NodeEventSink
+/// )]
+/// pub struct MockHttpAdapterTypes;
+///
+/// pub type MockHttpAdapter = ComposedAdapter<MockHttpAdapterTypes>;
+/// ```
+pub trait HostAdapterTypes: 'static {
+    type Database: DatabaseAdapter;
+    type Http: HttpAdapter;
+    type ChainInteractor: ChainInteractor;
+    type EventSink: EventSink;
+}
+
+/// The error of a [ComposedAdapter], displayed as the error of the component
+/// that failed, which is also its source
+#[derive(Debug)]
+pub struct ComponentError(Box<dyn Error + Send + Sync>);
+
+impl ComponentError {
+    fn new<E: Error + Send + Sync + 'static>(error: E) -> Self {
+        Self(Box::new(error))
+    }
+}
+
+impl Display for ComponentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Error for ComponentError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.0)
+    }
+}
+
+/// A host adapter that hands every host call to the component responsible for
+/// it, so a single component can be swapped while keeping the others
+pub struct ComposedAdapter<T: HostAdapterTypes> {
+    pub database:         T::Database,
+    pub http:             T::Http,
+    pub chain_interactor: T::ChainInteractor,
+    pub event_sink:       T::EventSink,
+}
+
+#[async_trait::async_trait]
+impl<T: HostAdapterTypes> HostAdapter for ComposedAdapter<T> {
+    type Error = ComponentError;
+
+    async fn new(config: ChainConfigs) -> Result<Self, Self::Error> {
+        Ok(Self {
+            database:         T::Database::new(&config).await.map_err(ComponentError::new)?,
+            http:             T::Http::new(&config).await.map_err(ComponentError::new)?,
+            chain_interactor: T::ChainInteractor::new(&config).await.map_err(ComponentError::new)?,
+            event_sink:       T::EventSink::new(&config).await.map_err(ComponentError::new)?,
+        })
+    }
+
+    fn select_client_from_chain(&self, chain: Chain) -> Client {
+        self.chain_interactor.select_client_from_chain(chain)
+    }
+
+    async fn db_get(&self, key: &str) -> Result<Option<String>, Self::Error> {
+        self.database.get(key).await.map_err(ComponentError::new)
+    }
+
+    async fn db_set(&self, key: &str, value: &str) -> Result<(), Self::Error> {
+        self.database.set(key, value).await.map_err(ComponentError::new)
+    }
+
+    async fn http_fetch(&self, url: &str) -> Result<String, Self::Error> {
+        self.http.fetch(url).await.map_err(ComponentError::new)
+    }
+
+    async fn chain_call(
+        &self,
+        chain: Chain,
+        contract_id: &str,
+        method_name: &str,
+        args: Vec<u8>,
+        deposit: u128,
+        node_config: NodeConfig,
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.chain_interactor
+            .call(chain, contract_id, method_name, args, deposit, node_config)
+            .await
+            .map_err(ComponentError::new)
+    }
+
+    async fn chain_view(
+        &self,
+        chain: Chain,
+        contract_id: &str,
+        method_name: &str,
+        args: Vec<u8>,
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.chain_interactor
+            .view(chain, contract_id, method_name, args)
+            .await
+            .map_err(ComponentError::new)
+    }
+
+    async fn trigger_event(&self, event: Event) -> Result<(), Self::Error> {
+        self.event_sink.trigger(event).await.map_err(ComponentError::new)
+    }
+}
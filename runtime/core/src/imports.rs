@@ -1,18 +1,109 @@
-use seda_runtime_sdk::Level;
-use wasmer::{imports, Array, Function, ImportObject, Memory, Module, Store, WasmPtr};
-use wasmer_wasi::WasiEnv;
+use std::time::Duration;
+
+use blake2::{digest::consts::U32, Blake2b, Digest};
+use borsh::BorshSerialize;
+use ed25519_dalek::{ExpandedSecretKey, PublicKey as Ed25519PublicKey, Signature as Ed25519Signature};
+use secp256k1::{
+    ecdsa::{RecoverableSignature, RecoveryId},
+    Message as Secp256k1Message,
+    Secp256k1,
+};
+use seda_crypto::Vrf;
+use seda_runtime_sdk::{p2p::P2PAuth, Level, Promise, PromiseAction, PromiseStatus};
+use sha2::Sha256;
+use sha3::Keccak256;
+use wasmer::{
+    imports,
+    Array,
+    Function,
+    ImportObject,
+    Memory,
+    Module,
+    RuntimeError as WasmerRuntimeError,
+    Store,
+    WasmPtr,
+};
+use wasmer_wasi::{get_wasi_version, WasiEnv};
 
 use super::{Result, RuntimeError, VmContext};
-use crate::MemoryAdapter;
+use crate::{charge_gas, GasCost, MemoryAdapter, SharedMemoryAccess, VmResultStatus};
+
+/// The WASI errno of a successful call
+const WASI_ESUCCESS: u32 = 0;
+
+/// Blake2b with a 32 byte digest
+type Blake2b256 = Blake2b<U32>;
 
 /// Wrapper around memory.get_ref to implement the RuntimeError
 fn get_memory(env: &VmContext) -> Result<&Memory> {
     Ok(env.memory.get_ref().ok_or("Memory reference could not be retrieved")?)
 }
 
+/// Reads a shared memory key, trapping when it is outside the shared memory
+/// namespaces of the program
+fn get_shared_memory_key(
+    env: &VmContext,
+    memory_ref: &Memory,
+    key: WasmPtr<u8, Array>,
+    key_length: i64,
+) -> Result<String> {
+    let key = key
+        .get_utf8_string(memory_ref, key_length as u32)
+        .ok_or("Error getting promise data")?;
+
+    if !env.manifest.allows_shared_memory_key(&env.program_name, &key) {
+        Err(format!(
+            "Shared memory key `{key}` is not allowed by the manifest of program `{}`",
+            env.program_name
+        ))?;
+    }
+
+    Ok(key)
+}
+
+/// Copies the bytes at the pointer out of the WASM memory
+fn read_bytes(memory_ref: &Memory, ptr: WasmPtr<u8, Array>, length: i64) -> Result<Vec<u8>> {
+    let bytes = ptr.deref(memory_ref, 0, length as u32).ok_or("Invalid pointer")?;
+
+    Ok(bytes.into_iter().map(|wc| wc.get()).collect())
+}
+
+/// Copies the bytes to the pointer in the WASM memory, the WASM binary has to
+/// reserve exactly enough room for them
+fn write_bytes(memory_ref: &Memory, ptr: WasmPtr<u8, Array>, length: i64, bytes: &[u8]) -> Result<()> {
+    if length as usize != bytes.len() {
+        Err(format!(
+            "The result data length `{length}` is not the same length for the value `{}`",
+            bytes.len()
+        ))?;
+    }
+
+    let derefed_ptr = ptr.deref(memory_ref, 0, length as u32).ok_or("Invalid pointer")?;
+    for (index, byte) in bytes.iter().enumerate() {
+        derefed_ptr
+            .get(index)
+            .ok_or("Writing out of bounds to memory")?
+            .set(*byte);
+    }
+
+    Ok(())
+}
+
+/// Called at the start of every host import. Traps when the execution deadline
+/// has passed and charges the gas for the call otherwise.
+fn enter_host_call(env: &VmContext, gas_cost: u64) -> Result<()> {
+    if env.deadline.is_expired() {
+        Err("Execution deadline exceeded")?;
+    }
+
+    charge_gas(env, gas_cost)
+}
+
 /// Adds a new promise to the promises stack
 pub fn promise_then_import_obj(store: &Store, vm_context: VmContext) -> Function {
     fn promise_result_write(env: &VmContext, ptr: WasmPtr<u8, Array>, length: i32) -> Result<()> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, length)?)?;
+
         let memory_ref = get_memory(env)?;
         let mut promises_queue_ref = env.promise_queue.lock();
 
@@ -30,9 +121,64 @@ pub fn promise_then_import_obj(store: &Store, vm_context: VmContext) -> Function
     Function::new_native_with_env(store, vm_context, promise_result_write)
 }
 
-/// Gets the length (stringified) of the promise status
+/// Resolves a promise action while the WASM binary waits for it. The settled
+/// promise is added to the current promise queue, so its status is read like
+/// the status of a queued promise. Returns the index of the promise.
+pub fn promise_await_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn promise_await(env: &VmContext, ptr: WasmPtr<u8, Array>, length: i32) -> Result<i32> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, length)?)?;
+
+        let memory_ref = get_memory(env)?;
+        let action_data_raw = ptr
+            .get_utf8_string(memory_ref, length as u32)
+            .ok_or("Error getting promise data")?;
+        let action: PromiseAction = serde_json::from_str(&action_data_raw)?;
+
+        let await_action = env
+            .await_action
+            .as_ref()
+            .ok_or("Promises can not be awaited by this execution")?;
+        if !action.is_awaitable() {
+            Err(format!("`{action}` can not be awaited, it has to be queued"))?;
+        }
+        charge_gas(env, GasCost::promise_action(&action))?;
+
+        let status = if env.manifest.allows(&action) {
+            match await_action(&action) {
+                Ok(status) => status,
+                Err(status) => {
+                    *env.limit_exceeded.lock() = Some(status);
+                    Err(format!("Awaiting `{action}` was interrupted"))?
+                }
+            }
+        } else {
+            PromiseStatus::Rejected(
+                format!(
+                    "Method `{action}` is not allowed by the manifest of program `{}`",
+                    env.program_name
+                )
+                .into_bytes(),
+            )
+        };
+
+        let mut promises_queue_ref = env.current_promise_queue.lock();
+        promises_queue_ref.add_promise(Promise {
+            action,
+            status,
+            dependencies: vec![],
+        });
+
+        Ok(promises_queue_ref.queue.len() as i32 - 1)
+    }
+
+    Function::new_native_with_env(store, vm_context, promise_await)
+}
+
+/// Gets the length of the borsh encoded promise status
 pub fn promise_status_length_import_obj(store: &Store, vm_context: VmContext) -> Function {
     fn promise_status_length(env: &VmContext, promise_index: i32) -> Result<i64> {
+        enter_host_call(env, GasCost::HOST_CALL)?;
+
         let promises_queue_ref = env.current_promise_queue.lock();
 
         let promise_info = promises_queue_ref
@@ -40,8 +186,8 @@ pub fn promise_status_length_import_obj(store: &Store, vm_context: VmContext) ->
             .get(promise_index as usize)
             .ok_or_else(|| format!("Could not find promise at index: {promise_index}"))?;
 
-        // The length depends on the full status enum + result in JSON
-        let status = serde_json::to_string(&promise_info.status)?;
+        // The length depends on the full status enum + result in borsh
+        let status = promise_info.status.try_to_vec()?;
 
         Ok(status.len() as i64)
     }
@@ -49,7 +195,7 @@ pub fn promise_status_length_import_obj(store: &Store, vm_context: VmContext) ->
     Function::new_native_with_env(store, vm_context, promise_status_length)
 }
 
-/// Writes the status of the promise to the WASM memory
+/// Writes the borsh encoded status of the promise to the WASM memory
 pub fn promise_status_write_import_obj(store: &Store, vm_context: VmContext) -> Function {
     fn promise_status_write(
         env: &VmContext,
@@ -57,6 +203,8 @@ pub fn promise_status_write_import_obj(store: &Store, vm_context: VmContext) ->
         result_data_ptr: WasmPtr<u8, Array>,
         result_data_length: i64,
     ) -> Result<()> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, result_data_length)?)?;
+
         let memory_ref = get_memory(env)?;
         let promises_ref = env.current_promise_queue.lock();
         let promise_info = promises_ref
@@ -64,9 +212,7 @@ pub fn promise_status_write_import_obj(store: &Store, vm_context: VmContext) ->
             .get(promise_index as usize)
             .ok_or_else(|| RuntimeError::VmHostError(format!("Could not find promise at index: {promise_index}")))?;
 
-        let promise_status = serde_json::to_string(&promise_info.status)?;
-
-        let promise_status_bytes = promise_status.as_bytes();
+        let promise_status_bytes = promise_info.status.try_to_vec()?;
         let derefed_ptr = result_data_ptr
             .deref(memory_ref, 0, result_data_length as u32)
             .ok_or("Invalid pointer")?;
@@ -93,6 +239,8 @@ pub fn memory_read_import_obj(store: &Store, vm_context: VmContext) -> Function
         result_data_ptr: WasmPtr<u8, Array>,
         result_data_length: i64,
     ) -> Result<()> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, result_data_length)?)?;
+
         let memory_ref = get_memory(env)?;
         let key = key
             .get_utf8_string(memory_ref, key_length as u32)
@@ -127,6 +275,8 @@ pub fn memory_read_import_obj(store: &Store, vm_context: VmContext) -> Function
 /// WASM.
 pub fn memory_read_length_import_obj(store: &Store, vm_context: VmContext) -> Function {
     fn memory_read_length(env: &VmContext, key: WasmPtr<u8, Array>, key_length: i64) -> Result<i64> {
+        enter_host_call(env, GasCost::HOST_CALL)?;
+
         let memory_ref = get_memory(env)?;
         let key = key
             .get_utf8_string(memory_ref, key_length as u32)
@@ -150,6 +300,8 @@ pub fn memory_write_import_obj(store: &Store, vm_context: VmContext) -> Function
         value: WasmPtr<u8, Array>,
         value_len: i64,
     ) -> Result<()> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, value_len)?)?;
+
         let memory_ref = get_memory(env)?;
         let key = key
             .get_utf8_string(memory_ref, key_length as u32)
@@ -175,13 +327,18 @@ pub fn shared_memory_read_import_obj(store: &Store, vm_context: VmContext) -> Fu
         result_data_ptr: WasmPtr<u8, Array>,
         result_data_length: i64,
     ) -> Result<()> {
-        let memory_ref = get_memory(env)?;
-        let key = key
-            .get_utf8_string(memory_ref, key_length as u32)
-            .ok_or("Error getting promise data")?;
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, result_data_length)?)?;
 
-        let memory_adapter = env.shared_memory.read();
-        let read_value: Vec<u8> = memory_adapter.get(&key)?.unwrap_or_default();
+        let memory_ref = get_memory(env)?;
+        let key = get_shared_memory_key(env, memory_ref, key, key_length)?;
+
+        let memory_adapter = env.shared_memory.lock();
+        let value: Option<Vec<u8>> = memory_adapter.get(&key)?;
+        env.log_shared_memory(SharedMemoryAccess::Read {
+            key,
+            value: value.clone(),
+        });
+        let read_value = value.unwrap_or_default();
         if result_data_length as usize != read_value.len() {
             Err(format!(
                 "The result data length `{result_data_length}` is not the same length for the value `{}`",
@@ -209,13 +366,18 @@ pub fn shared_memory_read_import_obj(store: &Store, vm_context: VmContext) -> Fu
 /// WASM.
 pub fn shared_memory_read_length_import_obj(store: &Store, vm_context: VmContext) -> Function {
     fn shared_memory_read_length(env: &VmContext, key: WasmPtr<u8, Array>, key_length: i64) -> Result<i64> {
+        enter_host_call(env, GasCost::HOST_CALL)?;
+
         let memory_ref = get_memory(env)?;
-        let key = key
-            .get_utf8_string(memory_ref, key_length as u32)
-            .ok_or("Error getting promise data")?;
+        let key = get_shared_memory_key(env, memory_ref, key, key_length)?;
 
-        let memory_adapter = env.shared_memory.read();
-        let read_value: Vec<u8> = memory_adapter.get(&key)?.unwrap_or_default();
+        let memory_adapter = env.shared_memory.lock();
+        let value: Option<Vec<u8>> = memory_adapter.get(&key)?;
+        env.log_shared_memory(SharedMemoryAccess::Read {
+            key,
+            value: value.clone(),
+        });
+        let read_value = value.unwrap_or_default();
 
         Ok(read_value.len() as i64)
     }
@@ -226,13 +388,17 @@ pub fn shared_memory_read_length_import_obj(store: &Store, vm_context: VmContext
 /// Reads the value from memory as byte array and returns a bool if it exists
 pub fn shared_memory_contains_key_import_obj(store: &Store, vm_context: VmContext) -> Function {
     fn shared_memory_contains_key(env: &VmContext, key: WasmPtr<u8, Array>, key_length: i64) -> Result<u8> {
+        enter_host_call(env, GasCost::HOST_CALL)?;
+
         let memory_ref = get_memory(env)?;
-        let key = key
-            .get_utf8_string(memory_ref, key_length as u32)
-            .ok_or("Error getting promise data")?;
+        let key = get_shared_memory_key(env, memory_ref, key, key_length)?;
 
-        let memory_adapter = env.shared_memory.read();
+        let memory_adapter = env.shared_memory.lock();
         let contains = memory_adapter.contains_key(&key);
+        if env.shared_memory_log.is_some() {
+            let value = memory_adapter.get(&key)?;
+            env.log_shared_memory(SharedMemoryAccess::Read { key, value });
+        }
 
         Ok(contains.into())
     }
@@ -249,14 +415,23 @@ pub fn shared_memory_write_import_obj(store: &Store, vm_context: VmContext) -> F
         value: WasmPtr<u8, Array>,
         value_len: i64,
     ) -> Result<()> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, value_len)?)?;
+
         let memory_ref = get_memory(env)?;
-        let key = key
-            .get_utf8_string(memory_ref, key_length as u32)
-            .ok_or("Error getting promise data")?;
+        let key = get_shared_memory_key(env, memory_ref, key, key_length)?;
         let value = value.deref(memory_ref, 0, value_len as u32).ok_or("Invalid pointer")?;
         let value_bytes: Vec<u8> = value.into_iter().map(|wc| wc.get()).collect();
 
-        let mut memory_adapter = env.shared_memory.write();
+        let mut memory_adapter = env.shared_memory.lock();
+        if !memory_adapter.fits_quota(&key, value_bytes.len(), env.node_config.max_shared_memory_bytes) {
+            *env.limit_exceeded.lock() = Some(VmResultStatus::SharedMemoryQuotaExceeded);
+            Err("Shared memory quota exceeded")?;
+        }
+
+        env.log_shared_memory(SharedMemoryAccess::Write {
+            key:   key.clone(),
+            value: value_bytes.clone(),
+        });
         memory_adapter.put(&key, value_bytes);
 
         Ok(())
@@ -265,8 +440,236 @@ pub fn shared_memory_write_import_obj(store: &Store, vm_context: VmContext) -> F
     Function::new_native_with_env(store, vm_context, shared_memory_write)
 }
 
+/// Removes the key from the shared memory, returns a bool if it existed
+pub fn shared_memory_remove_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn shared_memory_remove(env: &VmContext, key: WasmPtr<u8, Array>, key_length: i64) -> Result<u8> {
+        enter_host_call(env, GasCost::HOST_CALL)?;
+
+        let memory_ref = get_memory(env)?;
+        let key = get_shared_memory_key(env, memory_ref, key, key_length)?;
+
+        let mut memory_adapter = env.shared_memory.lock();
+        let removed = memory_adapter.remove(&key);
+        let existed = removed.is_some();
+        // Whether the key existed is an input of the execution as well
+        env.log_shared_memory(SharedMemoryAccess::Read {
+            key:   key.clone(),
+            value: removed.map(|value| value.to_vec()),
+        });
+        env.log_shared_memory(SharedMemoryAccess::Remove { key });
+
+        Ok(existed.into())
+    }
+
+    Function::new_native_with_env(store, vm_context, shared_memory_remove)
+}
+
+/// Removes the key from the shared memory once the TTL (ms) has passed,
+/// returns a bool if the key exists
+pub fn shared_memory_expire_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn shared_memory_expire(env: &VmContext, key: WasmPtr<u8, Array>, key_length: i64, ttl_ms: i64) -> Result<u8> {
+        enter_host_call(env, GasCost::HOST_CALL)?;
+
+        let memory_ref = get_memory(env)?;
+        let key = get_shared_memory_key(env, memory_ref, key, key_length)?;
+        let ttl = u64::try_from(ttl_ms).map_err(|_| format!("Invalid TTL `{ttl_ms}`"))?;
+
+        let mut memory_adapter = env.shared_memory.lock();
+        if env.shared_memory_log.is_some() {
+            let value = memory_adapter.get(&key)?;
+            env.log_shared_memory(SharedMemoryAccess::Read {
+                key: key.clone(),
+                value,
+            });
+        }
+        let exists = memory_adapter.expire(&key, Duration::from_millis(ttl));
+
+        Ok(exists.into())
+    }
+
+    Function::new_native_with_env(store, vm_context, shared_memory_expire)
+}
+
+/// Lists the shared memory keys with the prefix and returns the length of the
+/// borsh encoded list
+pub fn shared_memory_keys_length_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn shared_memory_keys_length(env: &VmContext, prefix: WasmPtr<u8, Array>, prefix_length: i64) -> Result<i64> {
+        enter_host_call(env, GasCost::HOST_CALL)?;
+
+        let memory_ref = get_memory(env)?;
+        let prefix = prefix
+            .get_utf8_string(memory_ref, prefix_length as u32)
+            .ok_or("Error getting promise data")?;
+
+        let memory_adapter = env.shared_memory.lock();
+        let mut keys = memory_adapter.keys_with_prefix(&prefix);
+        keys.retain(|key| env.manifest.allows_shared_memory_key(&env.program_name, key));
+
+        Ok(keys.try_to_vec()?.len() as i64)
+    }
+
+    Function::new_native_with_env(store, vm_context, shared_memory_keys_length)
+}
+
+/// Writes the borsh encoded list of shared memory keys with the prefix to the
+/// wasm result pointer
+pub fn shared_memory_keys_write_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn shared_memory_keys_write(
+        env: &VmContext,
+        prefix: WasmPtr<u8, Array>,
+        prefix_length: i64,
+        result_data_ptr: WasmPtr<u8, Array>,
+        result_data_length: i64,
+    ) -> Result<()> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, result_data_length)?)?;
+
+        let memory_ref = get_memory(env)?;
+        let prefix = prefix
+            .get_utf8_string(memory_ref, prefix_length as u32)
+            .ok_or("Error getting promise data")?;
+
+        let memory_adapter = env.shared_memory.lock();
+        let mut keys = memory_adapter.keys_with_prefix(&prefix);
+        keys.retain(|key| env.manifest.allows_shared_memory_key(&env.program_name, key));
+        let encoded_keys = keys.try_to_vec()?;
+        if result_data_length as usize != encoded_keys.len() {
+            Err(format!(
+                "The result data length `{result_data_length}` is not the same length for the keys `{}`",
+                encoded_keys.len()
+            ))?;
+        }
+        env.log_shared_memory(SharedMemoryAccess::Keys { prefix, keys });
+
+        let derefed_ptr = result_data_ptr
+            .deref(memory_ref, 0, result_data_length as u32)
+            .ok_or("Invalid pointer")?;
+        for (index, byte) in encoded_keys.iter().enumerate() {
+            derefed_ptr
+                .get(index)
+                .ok_or("Writing out of bounds to memory")?
+                .set(*byte);
+        }
+
+        Ok(())
+    }
+
+    Function::new_native_with_env(store, vm_context, shared_memory_keys_write)
+}
+
+/// Starts an explicit transaction, the next shared memory writes are staged
+/// separately until [shared_memory_commit_import_obj] is called and rolled
+/// back when the execution ends before that
+pub fn shared_memory_begin_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn shared_memory_begin(env: &VmContext) -> Result<()> {
+        enter_host_call(env, GasCost::HOST_CALL)?;
+
+        env.shared_memory.lock().begin();
+
+        Ok(())
+    }
+
+    Function::new_native_with_env(store, vm_context, shared_memory_begin)
+}
+
+/// Commits the shared memory writes staged so far (those of an explicit
+/// transaction included), they are kept even when the execution fails later on
+pub fn shared_memory_commit_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn shared_memory_commit(env: &VmContext) -> Result<()> {
+        enter_host_call(env, GasCost::HOST_CALL)?;
+
+        env.shared_memory.lock().commit();
+
+        Ok(())
+    }
+
+    Function::new_native_with_env(store, vm_context, shared_memory_commit)
+}
+
+/// Sends the number of raw input bytes of the execution to WASM
+pub fn execution_input_length_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn execution_input_length(env: &VmContext) -> Result<i64> {
+        enter_host_call(env, GasCost::HOST_CALL)?;
+
+        Ok(env.input.len() as i64)
+    }
+
+    Function::new_native_with_env(store, vm_context, execution_input_length)
+}
+
+/// Writes the raw input bytes of the execution to the wasm result pointer
+pub fn execution_input_write_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn execution_input_write(
+        env: &VmContext,
+        result_data_ptr: WasmPtr<u8, Array>,
+        result_data_length: i64,
+    ) -> Result<()> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, result_data_length)?)?;
+
+        if result_data_length as usize != env.input.len() {
+            Err(format!(
+                "The result data length `{result_data_length}` is not the same length for the input `{}`",
+                env.input.len()
+            ))?;
+        }
+
+        let memory_ref = get_memory(env)?;
+        let derefed_ptr = result_data_ptr
+            .deref(memory_ref, 0, result_data_length as u32)
+            .ok_or("Invalid pointer")?;
+        for (index, byte) in env.input.iter().enumerate() {
+            derefed_ptr
+                .get(index)
+                .ok_or("Writing out of bounds to memory")?
+                .set(*byte);
+        }
+
+        Ok(())
+    }
+
+    Function::new_native_with_env(store, vm_context, execution_input_write)
+}
+
+/// Sends the length of the borsh encoded execution context to WASM
+pub fn execution_context_length_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn execution_context_length(env: &VmContext) -> Result<i64> {
+        enter_host_call(env, GasCost::HOST_CALL)?;
+
+        Ok(env.execution_context.try_to_vec()?.len() as i64)
+    }
+
+    Function::new_native_with_env(store, vm_context, execution_context_length)
+}
+
+/// Writes the borsh encoded execution context to the wasm result pointer
+pub fn execution_context_write_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn execution_context_write(
+        env: &VmContext,
+        result_data_ptr: WasmPtr<u8, Array>,
+        result_data_length: i64,
+    ) -> Result<()> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, result_data_length)?)?;
+
+        let execution_context = env.execution_context.try_to_vec()?;
+        write_bytes(
+            get_memory(env)?,
+            result_data_ptr,
+            result_data_length,
+            &execution_context,
+        )
+    }
+
+    Function::new_native_with_env(store, vm_context, execution_context_write)
+}
+
 fn execution_result_import_obj(store: &Store, vm_context: VmContext) -> Function {
     fn execution_result(env: &VmContext, result_ptr: WasmPtr<u8, Array>, result_length: i32) -> Result<()> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, result_length)?)?;
+
+        if result_length as usize > env.node_config.max_result_bytes {
+            *env.limit_exceeded.lock() = Some(VmResultStatus::ResultLimitExceeded);
+            Err("Execution result exceeds the maximum size")?;
+        }
+
         let memory_ref = get_memory(env)?;
 
         let result = result_ptr
@@ -294,6 +697,8 @@ pub fn log_import_obj(store: &Store, vm_context: VmContext) -> Function {
         line_info: WasmPtr<u8, Array>,
         line_info_len: i64,
     ) -> Result<()> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, msg_len)?)?;
+
         let memory_ref = get_memory(env)?;
 
         let promise_data_raw = level
@@ -336,6 +741,8 @@ pub fn bn254_verify_import_obj(store: &Store, vm_context: VmContext) -> Function
         public_key: WasmPtr<u8, Array>,
         public_key_length: i64,
     ) -> Result<u8> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, message_length)?)?;
+
         // Fetch function arguments as Vec<u8>
         let memory_ref = get_memory(env)?;
         let message = message
@@ -382,6 +789,8 @@ pub fn bn254_sign_import_obj(store: &Store, vm_context: VmContext) -> Function {
         result_data_ptr: WasmPtr<u8, Array>,
         result_data_length: i64,
     ) -> Result<()> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, message_length)?)?;
+
         // Fetch function arguments as Vec<u8>
         let memory_ref = get_memory(env)?;
         let message = message
@@ -416,16 +825,340 @@ pub fn bn254_sign_import_obj(store: &Store, vm_context: VmContext) -> Function {
     Function::new_native_with_env(store, vm_context, bn254_sign)
 }
 
+/// Hashes the data with sha256 and writes the 32 byte digest to the wasm
+/// result pointer
+pub fn sha256_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn sha256(
+        env: &VmContext,
+        data: WasmPtr<u8, Array>,
+        data_length: i64,
+        result_data_ptr: WasmPtr<u8, Array>,
+        result_data_length: i64,
+    ) -> Result<()> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, data_length)?)?;
+
+        let memory_ref = get_memory(env)?;
+        let data = read_bytes(memory_ref, data, data_length)?;
+
+        write_bytes(memory_ref, result_data_ptr, result_data_length, &Sha256::digest(data))
+    }
+
+    Function::new_native_with_env(store, vm_context, sha256)
+}
+
+/// Hashes the data with keccak256 (as used by Ethereum) and writes the 32 byte
+/// digest to the wasm result pointer
+pub fn keccak256_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn keccak256(
+        env: &VmContext,
+        data: WasmPtr<u8, Array>,
+        data_length: i64,
+        result_data_ptr: WasmPtr<u8, Array>,
+        result_data_length: i64,
+    ) -> Result<()> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, data_length)?)?;
+
+        let memory_ref = get_memory(env)?;
+        let data = read_bytes(memory_ref, data, data_length)?;
+
+        write_bytes(
+            memory_ref,
+            result_data_ptr,
+            result_data_length,
+            &Keccak256::digest(data),
+        )
+    }
+
+    Function::new_native_with_env(store, vm_context, keccak256)
+}
+
+/// Hashes the data with blake2b and writes the 32 byte digest to the wasm
+/// result pointer
+pub fn blake2b_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn blake2b(
+        env: &VmContext,
+        data: WasmPtr<u8, Array>,
+        data_length: i64,
+        result_data_ptr: WasmPtr<u8, Array>,
+        result_data_length: i64,
+    ) -> Result<()> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, data_length)?)?;
+
+        let memory_ref = get_memory(env)?;
+        let data = read_bytes(memory_ref, data, data_length)?;
+
+        write_bytes(
+            memory_ref,
+            result_data_ptr,
+            result_data_length,
+            &Blake2b256::digest(data),
+        )
+    }
+
+    Function::new_native_with_env(store, vm_context, blake2b)
+}
+
+/// Signs a [P2PAuth] of the program and the payload with the Ed25519 key of
+/// the node and writes the 64 byte signature to the wasm result pointer. The
+/// key also signs the transactions of the node, so programs can not have it
+/// sign anything else.
+pub fn p2p_auth_sign_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn p2p_auth_sign(
+        env: &VmContext,
+        payload: WasmPtr<u8, Array>,
+        payload_length: i64,
+        result_data_ptr: WasmPtr<u8, Array>,
+        result_data_length: i64,
+    ) -> Result<()> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::SIGNATURE, payload_length)?)?;
+
+        let memory_ref = get_memory(env)?;
+        let auth = P2PAuth {
+            program: env.program_name.clone(),
+            payload: read_bytes(memory_ref, payload, payload_length)?,
+        };
+
+        let keypair = &env.node_config.keypair_ed25519;
+        let signature = ExpandedSecretKey::from(&keypair.private_key).sign(&auth.signing_bytes(), &keypair.public_key);
+
+        write_bytes(memory_ref, result_data_ptr, result_data_length, &signature.to_bytes())
+    }
+
+    Function::new_native_with_env(store, vm_context, p2p_auth_sign)
+}
+
+/// Verifies an Ed25519 signature, returns a bool if it is valid. Malformed
+/// signatures and public keys are not valid.
+pub fn ed25519_verify_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn ed25519_verify(
+        env: &VmContext,
+        message: WasmPtr<u8, Array>,
+        message_length: i64,
+        signature: WasmPtr<u8, Array>,
+        signature_length: i64,
+        public_key: WasmPtr<u8, Array>,
+        public_key_length: i64,
+    ) -> Result<u8> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::SIGNATURE, message_length)?)?;
+
+        let memory_ref = get_memory(env)?;
+        let message = read_bytes(memory_ref, message, message_length)?;
+        let signature = read_bytes(memory_ref, signature, signature_length)?;
+        let public_key = read_bytes(memory_ref, public_key, public_key_length)?;
+
+        let valid = match (
+            Ed25519PublicKey::from_bytes(&public_key),
+            Ed25519Signature::try_from(signature.as_slice()),
+        ) {
+            (Ok(public_key), Ok(signature)) => public_key.verify_strict(&message, &signature).is_ok(),
+            _ => false,
+        };
+
+        Ok(valid.into())
+    }
+
+    Function::new_native_with_env(store, vm_context, ed25519_verify)
+}
+
+fn recover_secp256k1_public_key(
+    message_hash: &[u8],
+    signature: &[u8],
+) -> core::result::Result<secp256k1::PublicKey, secp256k1::Error> {
+    let recovery_id = match signature[64] {
+        v @ 27..=28 => v - 27,
+        v => v,
+    };
+    let signature = RecoverableSignature::from_compact(&signature[..64], RecoveryId::from_i32(recovery_id.into())?)?;
+    let message = Secp256k1Message::from_slice(message_hash)?;
+
+    Secp256k1::verification_only().recover_ecdsa(&message, &signature)
+}
+
+/// Recovers the secp256k1 public key that signed the 32 byte message hash,
+/// like `ecrecover` on Ethereum. The signature is 65 bytes: `r`, `s` and the
+/// recovery id `v` (0, 1, 27 or 28). Writes the 64 byte uncompressed public key
+/// (without the 0x04 prefix) to the wasm result pointer and returns a bool if
+/// the recovery succeeded.
+pub fn secp256k1_recover_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn secp256k1_recover(
+        env: &VmContext,
+        message_hash: WasmPtr<u8, Array>,
+        message_hash_length: i64,
+        signature: WasmPtr<u8, Array>,
+        signature_length: i64,
+        result_data_ptr: WasmPtr<u8, Array>,
+        result_data_length: i64,
+    ) -> Result<u8> {
+        enter_host_call(env, GasCost::SIGNATURE)?;
+
+        let memory_ref = get_memory(env)?;
+        let message_hash = read_bytes(memory_ref, message_hash, message_hash_length)?;
+        let signature = read_bytes(memory_ref, signature, signature_length)?;
+        if signature.len() != 65 {
+            Err(format!("Invalid secp256k1 signature length `{signature_length}`"))?;
+        }
+
+        match recover_secp256k1_public_key(&message_hash, &signature) {
+            Ok(public_key) => {
+                write_bytes(
+                    memory_ref,
+                    result_data_ptr,
+                    result_data_length,
+                    &public_key.serialize_uncompressed()[1..],
+                )?;
+
+                Ok(1)
+            }
+            Err(_) => Ok(0),
+        }
+    }
+
+    Function::new_native_with_env(store, vm_context, secp256k1_recover)
+}
+
+/// Proves the input with the VRF of the bn254 key of the node. Writes the 32
+/// byte random output and the proof to the wasm result pointers.
+pub fn vrf_prove_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn vrf_prove(
+        env: &VmContext,
+        input: WasmPtr<u8, Array>,
+        input_length: i64,
+        output_ptr: WasmPtr<u8, Array>,
+        output_length: i64,
+        proof_ptr: WasmPtr<u8, Array>,
+        proof_length: i64,
+    ) -> Result<()> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::SIGNATURE, input_length)?)?;
+
+        let memory_ref = get_memory(env)?;
+        let input = read_bytes(memory_ref, input, input_length)?;
+
+        let (output, proof) = Vrf::prove(&env.node_config.keypair_bn254.private_key, &input)?;
+        write_bytes(memory_ref, output_ptr, output_length, &output)?;
+        write_bytes(memory_ref, proof_ptr, proof_length, &proof)
+    }
+
+    Function::new_native_with_env(store, vm_context, vrf_prove)
+}
+
+/// Verifies the VRF proof of the input against the bn254 public key, writes
+/// the 32 byte random output to the wasm result pointer and returns a bool if
+/// the proof is valid. Malformed proofs and public keys are not valid.
+pub fn vrf_verify_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    #[allow(clippy::too_many_arguments)]
+    fn vrf_verify(
+        env: &VmContext,
+        input: WasmPtr<u8, Array>,
+        input_length: i64,
+        proof: WasmPtr<u8, Array>,
+        proof_length: i64,
+        public_key: WasmPtr<u8, Array>,
+        public_key_length: i64,
+        output_ptr: WasmPtr<u8, Array>,
+        output_length: i64,
+    ) -> Result<u8> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::SIGNATURE, input_length)?)?;
+
+        let memory_ref = get_memory(env)?;
+        let input = read_bytes(memory_ref, input, input_length)?;
+        let proof = read_bytes(memory_ref, proof, proof_length)?;
+        let public_key = read_bytes(memory_ref, public_key, public_key_length)?;
+
+        let output = bn254::PublicKey::from_uncompressed(public_key)
+            .ok()
+            .and_then(|public_key| Vrf::verify(&public_key, &input, &proof).ok());
+        match output {
+            Some(output) => {
+                write_bytes(memory_ref, output_ptr, output_length, &output)?;
+
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+
+    Function::new_native_with_env(store, vm_context, vrf_verify)
+}
+
 // Creates the WASM function imports with the stringed names.
+/// Every clock of a deterministic execution reads as the Unix epoch
+fn clock_time_get_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn clock_time_get(env: &VmContext, _clock_id: u32, _precision: u64, time: WasmPtr<u64>) -> Result<u32> {
+        enter_host_call(env, GasCost::HOST_CALL)?;
+
+        let memory_ref = get_memory(env)?;
+        time.deref(memory_ref).ok_or("Invalid pointer")?.set(0);
+
+        Ok(WASI_ESUCCESS)
+    }
+
+    Function::new_native_with_env(store, vm_context, clock_time_get)
+}
+
+/// Fills the buffer from the seeded random source of a deterministic execution
+fn random_get_import_obj(store: &Store, vm_context: VmContext) -> Function {
+    fn random_get(env: &VmContext, buffer: WasmPtr<u8, Array>, buffer_length: u32) -> Result<u32> {
+        enter_host_call(env, GasCost::with_bytes(GasCost::HOST_CALL, buffer_length)?)?;
+
+        let deterministic_random = env
+            .deterministic_random
+            .as_ref()
+            .ok_or("Random source is only replaced in deterministic executions")?;
+        let mut random_bytes = vec![0; buffer_length as usize];
+        deterministic_random.lock().fill(&mut random_bytes);
+
+        let memory_ref = get_memory(env)?;
+        let derefed_ptr = buffer.deref(memory_ref, 0, buffer_length).ok_or("Invalid pointer")?;
+        for (cell, byte) in derefed_ptr.iter().zip(random_bytes) {
+            cell.set(byte);
+        }
+
+        Ok(WASI_ESUCCESS)
+    }
+
+    Function::new_native_with_env(store, vm_context, random_get)
+}
+
+/// Links the host function when the manifest of the program grants it. Else a
+/// function with the same signature that traps is linked in its place, so
+/// programs that never call it still link.
+fn gated_import(store: &Store, vm_context: &VmContext, name: &str, function: Function) -> Function {
+    if vm_context.manifest.allows_host_function(name) {
+        return function;
+    }
+
+    let message = format!(
+        "Host function `{name}` is not allowed by the manifest of program `{}`",
+        vm_context.program_name
+    );
+    Function::new(store, function.ty(), move |_| Err(WasmerRuntimeError::new(&message)))
+}
+
 pub fn create_wasm_imports(
     store: &Store,
     vm_context: VmContext,
     wasi_env: &mut WasiEnv,
     wasm_module: &Module,
 ) -> Result<ImportObject> {
+    let mut wasi_import_obj = wasi_env.import_object(wasm_module)?;
+
+    // Deterministic executions can not observe the host clock or randomness
+    if vm_context.deterministic_random.is_some() {
+        let namespace = get_wasi_version(wasm_module, false)
+            .ok_or("VM could not get WASI version")?
+            .get_namespace_str();
+        let mut wasi_exports = wasi_import_obj
+            .get_namespace_exports(namespace)
+            .ok_or("VM could not get WASI namespace")?;
+        wasi_exports.insert("clock_time_get", clock_time_get_import_obj(store, vm_context.clone()));
+        wasi_exports.insert("random_get", random_get_import_obj(store, vm_context.clone()));
+        wasi_import_obj.register(namespace, wasi_exports);
+    }
+
     let host_import_obj = imports! {
         "env" => {
             "promise_then" => promise_then_import_obj(store, vm_context.clone()),
+            "promise_await" => promise_await_import_obj(store, vm_context.clone()),
             "promise_status_length" => promise_status_length_import_obj(store, vm_context.clone()),
             "promise_status_write" => promise_status_write_import_obj(store, vm_context.clone()),
             "memory_read" => memory_read_import_obj(store, vm_context.clone()),
@@ -435,15 +1168,47 @@ pub fn create_wasm_imports(
             "shared_memory_read" => shared_memory_read_import_obj(store, vm_context.clone()),
             "shared_memory_read_length" => shared_memory_read_length_import_obj(store, vm_context.clone()),
             "shared_memory_write" => shared_memory_write_import_obj(store, vm_context.clone()),
+            "shared_memory_remove" => shared_memory_remove_import_obj(store, vm_context.clone()),
+            "shared_memory_expire" => shared_memory_expire_import_obj(store, vm_context.clone()),
+            "shared_memory_begin" => shared_memory_begin_import_obj(store, vm_context.clone()),
+            "shared_memory_commit" => shared_memory_commit_import_obj(store, vm_context.clone()),
+            "shared_memory_keys_length" => shared_memory_keys_length_import_obj(store, vm_context.clone()),
+            "shared_memory_keys_write" => shared_memory_keys_write_import_obj(store, vm_context.clone()),
+            "execution_input_length" => execution_input_length_import_obj(store, vm_context.clone()),
+            "execution_input_write" => execution_input_write_import_obj(store, vm_context.clone()),
+            "execution_context_length" => execution_context_length_import_obj(store, vm_context.clone()),
+            "execution_context_write" => execution_context_write_import_obj(store, vm_context.clone()),
             "execution_result" => execution_result_import_obj(store, vm_context.clone()),
             "_log" => log_import_obj(store, vm_context.clone()),
             "bn254_verify" => bn254_verify_import_obj(store, vm_context.clone()),
-            "bn254_sign" => bn254_sign_import_obj(store, vm_context)
+            "bn254_sign" => gated_import(
+                store,
+                &vm_context,
+                "bn254_sign",
+                bn254_sign_import_obj(store, vm_context.clone())
+            ),
+            "sha256" => sha256_import_obj(store, vm_context.clone()),
+            "keccak256" => keccak256_import_obj(store, vm_context.clone()),
+            "blake2b" => blake2b_import_obj(store, vm_context.clone()),
+            "p2p_auth_sign" => gated_import(
+                store,
+                &vm_context,
+                "p2p_auth_sign",
+                p2p_auth_sign_import_obj(store, vm_context.clone())
+            ),
+            "ed25519_verify" => ed25519_verify_import_obj(store, vm_context.clone()),
+            "secp256k1_recover" => secp256k1_recover_import_obj(store, vm_context.clone()),
+            "vrf_prove" => gated_import(
+                store,
+                &vm_context,
+                "vrf_prove",
+                vrf_prove_import_obj(store, vm_context.clone())
+            ),
+            "vrf_verify" => vrf_verify_import_obj(store, vm_context)
         }
     };
 
     // Combining the WASI exports with our custom (host) imports
-    let mut wasi_import_obj = wasi_env.import_object(wasm_module)?;
     let host_exports = host_import_obj
         .get_namespace_exports("env")
         .ok_or("VM could not get env namespace")?;
@@ -1,19 +1,50 @@
 //! WASI compatible WASM VM
 
+// Lets `#[derive(Adapter)]` name the runtime the same way inside and outside it
+extern crate self as seda_runtime;
+
 mod config;
 pub use config::*;
 
 mod context;
 pub use context::*;
 
+mod deadline;
+pub use deadline::*;
+
+mod determinism;
+pub use determinism::*;
+
 mod errors;
 pub use errors::*;
 
+mod guest_output;
+pub use guest_output::*;
+
 mod host_adapter;
 pub use host_adapter::*;
+pub use seda_runtime_macros::Adapter;
 
 pub(crate) mod imports;
 
+mod manifest;
+pub use manifest::*;
+
+mod metering;
+pub use metering::*;
+
+mod module_cache;
+pub use module_cache::*;
+
+mod program_registry;
+pub use program_registry::*;
+
+mod recording;
+pub use recording::*;
+
+mod replay;
+pub use replay::*;
+
 mod promise;
 pub(crate) use promise::*;
 
@@ -23,6 +54,12 @@ pub use runtime::*;
 mod storage;
 pub use storage::*;
 
+mod tunables;
+pub use tunables::*;
+
+mod validation;
+pub use validation::*;
+
 mod vm_result;
 pub use vm_result::*;
 
@@ -32,5 +69,8 @@ mod test {
     mod test_host;
     pub(crate) use test_host::*;
 
+    mod guest_output_test;
+    mod manifest_test;
+    mod metering_test;
     mod runtime_test;
 }
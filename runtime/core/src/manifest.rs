@@ -0,0 +1,258 @@
+use std::{fs, path::Path};
+
+use seda_runtime_sdk::{Chain, PromiseAction, ToUrl};
+use serde::{Deserialize, Serialize};
+
+use crate::{custom_section, Result};
+
+/// A chain and the contract on it a program may reach
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContractPermission {
+    pub chain:       Chain,
+    /// `*` allows every contract on the chain
+    pub contract_id: String,
+}
+
+/// The capabilities a program needs, enforced by the runtime on every promise
+/// and shared memory access. A manifest is read from a TOML file next to the
+/// WASM binary or from the `seda_manifest` custom section of the binary.
+///
+/// ```toml
+/// actions = ["http", "chain_view"]
+/// http_domains = ["api.example.com"]
+/// shared_memory_namespaces = ["price_feed/"]
+/// host_functions = ["p2p_auth_sign"]
+///
+/// [[contracts]]
+/// chain = "Near"
+/// contract_id = "oracle.near"
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProgramManifest {
+    /// The promise actions the program may queue, by name. `call_self` and the
+    /// combinators are always allowed.
+    #[serde(default)]
+    pub actions:                  Vec<String>,
+    /// The domains (and their subdomains) `http` may fetch from, every domain
+    /// when not set. A program without a manifest may not fetch from any.
+    #[serde(default)]
+    pub http_domains:             Option<Vec<String>>,
+    /// The contracts `chain_view` may read from
+    #[serde(default)]
+    pub contracts:                Vec<ContractPermission>,
+    /// The key prefixes of the shared memory the program may access, `*`
+    /// grants every key. When not set the program can only access the keys
+    /// under its own name, `<program name>/`.
+    #[serde(default)]
+    pub shared_memory_namespaces: Option<Vec<String>>,
+    /// The host functions that sign with the keys of the node the program may
+    /// call, see [ProgramManifest::GATED_HOST_FUNCTIONS]
+    #[serde(default)]
+    pub host_functions:           Vec<String>,
+    /// Only granted by the node itself, manifest files can not set it
+    #[serde(default)]
+    unrestricted:                 bool,
+}
+
+impl ProgramManifest {
+    pub const CUSTOM_SECTION: &str = "seda_manifest";
+    /// The actions a manifest can declare. `chain_call` signs with the key of
+    /// the node, so only the node itself can grant it.
+    pub const DECLARABLE_ACTIONS: &[&str] = &[
+        "call_program",
+        "db_get",
+        "db_set",
+        "http",
+        "chain_view",
+        "trigger_event",
+        "p2p_broadcast",
+    ];
+    /// The host functions a manifest can declare
+    pub const DECLARABLE_HOST_FUNCTIONS: &[&str] = &["p2p_auth_sign"];
+    /// The shared memory namespace that grants every key
+    pub const EVERY_KEY: &str = "*";
+    pub const EXTENSION: &str = "toml";
+    /// The host functions only programs whose manifest grants them can call,
+    /// the others trap. `bn254_sign` and `vrf_prove` use the key the node signs
+    /// batches with, so only the node itself can grant them.
+    pub const GATED_HOST_FUNCTIONS: &[&str] = &["bn254_sign", "p2p_auth_sign", "vrf_prove"];
+
+    /// Everything is allowed, used for the consensus program
+    pub fn unrestricted() -> Self {
+        Self {
+            unrestricted: true,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the node granted the program everything
+    pub fn is_unrestricted(&self) -> bool {
+        self.unrestricted
+    }
+
+    /// The manifest of a program that does not declare one: it can queue
+    /// `http` promises, but they are rejected until a manifest declares the
+    /// domains, so it can not reach internal hosts of the node. It can only
+    /// call itself and access its own shared memory.
+    pub fn limited() -> Self {
+        Self {
+            actions: vec!["http".to_string()],
+            http_domains: Some(vec![]),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        let manifest: Self = toml::from_str(toml).map_err(|error| format!("Invalid program manifest: {error}"))?;
+        if manifest.unrestricted {
+            Err("Program manifests can not be unrestricted")?;
+        }
+
+        if let Some(action) = manifest
+            .actions
+            .iter()
+            .find(|action| !Self::DECLARABLE_ACTIONS.contains(&action.as_str()))
+        {
+            Err(format!("Program manifests can not declare the `{action}` action"))?;
+        }
+
+        if let Some(host_function) = manifest
+            .host_functions
+            .iter()
+            .find(|host_function| !Self::DECLARABLE_HOST_FUNCTIONS.contains(&host_function.as_str()))
+        {
+            Err(format!(
+                "Program manifests can not declare the `{host_function}` host function"
+            ))?;
+        }
+
+        Ok(manifest)
+    }
+
+    /// Reads the manifest from the `seda_manifest` custom section of the WASM
+    /// binary, if it has one
+    pub fn from_custom_section(wasm_binary: &[u8]) -> Result<Option<Self>> {
+        custom_section(wasm_binary, Self::CUSTOM_SECTION)?
+            .map(|section| Self::from_toml(std::str::from_utf8(section).map_err(|error| error.to_string())?))
+            .transpose()
+    }
+
+    /// The manifest of a WASM binary on disk: the TOML file with the same name
+    /// next to it, then the custom section of the binary, then
+    /// [ProgramManifest::limited]
+    pub fn for_program<P: AsRef<Path>>(wasm_path: P, wasm_binary: &[u8]) -> Result<Self> {
+        let manifest_path = wasm_path.as_ref().with_extension(Self::EXTENSION);
+        if manifest_path.is_file() {
+            return Self::from_toml(&fs::read_to_string(manifest_path)?);
+        }
+
+        Ok(Self::from_custom_section(wasm_binary)?.unwrap_or_else(Self::limited))
+    }
+
+    /// Whether the program may queue the action
+    pub fn allows(&self, action: &PromiseAction) -> bool {
+        if self.unrestricted {
+            return true;
+        }
+
+        let declared = || self.actions.contains(&action.to_string());
+        match action {
+            PromiseAction::CallSelf(_) | PromiseAction::All | PromiseAction::Any => true,
+            PromiseAction::ChainCall(_) => false,
+            PromiseAction::Http(http_action) => declared() && self.allows_url(&http_action.url),
+            PromiseAction::ChainView(chain_view_action) => {
+                declared() && self.allows_contract(chain_view_action.chain, &chain_view_action.contract_id)
+            }
+            _ => declared(),
+        }
+    }
+
+    /// Whether the program may call the host function, only
+    /// [ProgramManifest::GATED_HOST_FUNCTIONS] have to be granted
+    pub fn allows_host_function(&self, name: &str) -> bool {
+        self.unrestricted
+            || !Self::GATED_HOST_FUNCTIONS.contains(&name)
+            || self.host_functions.iter().any(|host_function| host_function == name)
+    }
+
+    /// Whether the manifest grants nothing `other` does not grant
+    pub fn is_within(&self, other: &Self) -> bool {
+        if other.unrestricted {
+            return true;
+        }
+
+        let domains_within = match (&self.http_domains, &other.http_domains) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(domains), Some(other_domains)) => domains.iter().all(|domain| {
+                other_domains
+                    .iter()
+                    .any(|other| domain == other || domain.ends_with(&format!(".{other}")))
+            }),
+        };
+        // Without namespaces a program only accesses its own, which is within
+        // anything but other namespaces that do not grant every key
+        let namespaces_within = match (&self.shared_memory_namespaces, &other.shared_memory_namespaces) {
+            (None, None) => true,
+            (Some(_), None) => false,
+            (None, Some(other_namespaces)) => other_namespaces.iter().any(|other| other == Self::EVERY_KEY),
+            (Some(namespaces), Some(other_namespaces)) => namespaces.iter().all(|namespace| {
+                other_namespaces.iter().any(|other| {
+                    other == Self::EVERY_KEY || (namespace != Self::EVERY_KEY && namespace.starts_with(other))
+                })
+            }),
+        };
+
+        !self.unrestricted
+            && self.actions.iter().all(|action| other.actions.contains(action))
+            && self
+                .host_functions
+                .iter()
+                .all(|host_function| other.allows_host_function(host_function))
+            && self
+                .contracts
+                .iter()
+                .all(|permission| other.allows_contract(permission.chain, &permission.contract_id))
+            && domains_within
+            && namespaces_within
+    }
+
+    /// Whether the program may read or write the shared memory key
+    pub fn allows_shared_memory_key(&self, program_name: &str, key: &str) -> bool {
+        if self.unrestricted {
+            return true;
+        }
+
+        match &self.shared_memory_namespaces {
+            Some(namespaces) => namespaces
+                .iter()
+                .any(|namespace| namespace == Self::EVERY_KEY || key.starts_with(namespace)),
+            None => key
+                .strip_prefix(program_name)
+                .map_or(false, |rest| rest.starts_with('/')),
+        }
+    }
+
+    fn allows_url(&self, url: &str) -> bool {
+        let domains = match &self.http_domains {
+            Some(domains) => domains,
+            None => return true,
+        };
+
+        let host = match url.to_url().ok().and_then(|url| url.host_str().map(str::to_string)) {
+            Some(host) => host,
+            None => return false,
+        };
+        domains
+            .iter()
+            .any(|domain| host == *domain || host.ends_with(&format!(".{domain}")))
+    }
+
+    fn allows_contract(&self, chain: Chain, contract_id: &str) -> bool {
+        self.contracts.iter().any(|permission| {
+            permission.chain == chain && (permission.contract_id == "*" || permission.contract_id == contract_id)
+        })
+    }
+}
@@ -0,0 +1,171 @@
+use seda_runtime_sdk::{Chain, ChainCallAction, ChainViewAction, DatabaseSetAction, HttpAction, PromiseAction};
+
+use crate::ProgramManifest;
+
+const MANIFEST: &str = r#"
+actions = ["http", "chain_view"]
+http_domains = ["example.com"]
+shared_memory_namespaces = ["prices/"]
+
+[[contracts]]
+chain = "Near"
+contract_id = "oracle.near"
+"#;
+
+fn http(url: &str) -> PromiseAction {
+    PromiseAction::Http(HttpAction { url: url.to_string() })
+}
+
+fn chain_view(chain: Chain, contract_id: &str) -> PromiseAction {
+    PromiseAction::ChainView(ChainViewAction {
+        chain,
+        contract_id: contract_id.to_string(),
+        method_name: "get".to_string(),
+        args: vec![],
+    })
+}
+
+#[test]
+fn test_manifest_allows_declared_capabilities() {
+    let manifest = ProgramManifest::from_toml(MANIFEST).unwrap();
+
+    assert!(manifest.allows(&http("https://example.com/price")));
+    assert!(manifest.allows(&http("https://api.example.com/price")));
+    assert!(!manifest.allows(&http("https://example.org/price")));
+    assert!(!manifest.allows(&http("https://badexample.com/price")));
+
+    assert!(manifest.allows(&chain_view(Chain::Near, "oracle.near")));
+    assert!(!manifest.allows(&chain_view(Chain::Near, "other.near")));
+    assert!(!manifest.allows(&chain_view(Chain::Another, "oracle.near")));
+
+    assert!(!manifest.allows(&PromiseAction::DatabaseSet(DatabaseSetAction {
+        key:   "key".to_string(),
+        value: vec![],
+    })));
+    assert!(manifest.allows(&PromiseAction::All));
+
+    assert!(manifest.allows_shared_memory_key("program", "prices/btc"));
+    assert!(!manifest.allows_shared_memory_key("program", "batch_signature_store"));
+    assert!(!manifest.allows_shared_memory_key("program", "program/key"));
+}
+
+#[test]
+fn test_manifest_shared_memory_defaults_to_program_namespace() {
+    let limited = ProgramManifest::limited();
+    assert!(limited.allows_shared_memory_key("program", "program/key"));
+    assert!(!limited.allows_shared_memory_key("program", "program_other/key"));
+    assert!(!limited.allows_shared_memory_key("program", "other/key"));
+    assert!(!limited.allows_shared_memory_key("program", "batch_signature_store"));
+
+    // Every key has to be granted explicitly
+    let every_key = ProgramManifest::from_toml(r#"shared_memory_namespaces = ["*"]"#).unwrap();
+    assert!(every_key.allows_shared_memory_key("program", "batch_signature_store"));
+    assert!(ProgramManifest::unrestricted().allows_shared_memory_key("program", "batch_signature_store"));
+}
+
+#[test]
+fn test_manifest_can_not_grant_chain_call() {
+    assert!(ProgramManifest::from_toml(r#"actions = ["chain_call"]"#).is_err());
+    assert!(ProgramManifest::from_toml("unrestricted = true").is_err());
+    assert!(ProgramManifest::from_toml(r#"actions = ["unknown"]"#).is_err());
+
+    let chain_call = PromiseAction::ChainCall(ChainCallAction {
+        chain:       Chain::Near,
+        contract_id: "oracle.near".to_string(),
+        method_name: "post".to_string(),
+        args:        vec![],
+        deposit:     0,
+    });
+    assert!(!ProgramManifest::from_toml(MANIFEST).unwrap().allows(&chain_call));
+    assert!(!ProgramManifest::limited().allows(&chain_call));
+    assert!(ProgramManifest::unrestricted().allows(&chain_call));
+}
+
+#[test]
+fn test_limited_manifest_fetches_nothing() {
+    // Without declared domains a program can not reach the node or its network
+    let limited = ProgramManifest::limited();
+    assert!(!limited.allows(&http("https://example.com/price")));
+    assert!(!limited.allows(&http("http://localhost:12345/")));
+    assert!(!limited.allows(&http("http://169.254.169.254/latest/meta-data/")));
+}
+
+#[test]
+fn test_manifest_is_within() {
+    let manifest = ProgramManifest::from_toml(MANIFEST).unwrap();
+    let limited = ProgramManifest::limited();
+
+    assert!(limited.is_within(&limited));
+    assert!(manifest.is_within(&manifest));
+    assert!(manifest.is_within(&ProgramManifest::unrestricted()));
+    assert!(!ProgramManifest::unrestricted().is_within(&limited));
+    assert!(!manifest.is_within(&limited));
+
+    let narrower = ProgramManifest::from_toml(
+        r#"
+actions = ["http"]
+http_domains = ["api.example.com"]
+shared_memory_namespaces = ["prices/btc/"]
+"#,
+    )
+    .unwrap();
+    assert!(narrower.is_within(&manifest));
+    assert!(!limited.is_within(&narrower));
+    // Only programs without namespaces stay within their own
+    assert!(!narrower.is_within(&limited));
+    let narrower = ProgramManifest::from_toml(
+        r#"
+actions = ["http"]
+http_domains = ["api.example.com"]
+"#,
+    )
+    .unwrap();
+    assert!(!narrower.is_within(&limited));
+    let no_domains = ProgramManifest::from_toml(
+        r#"
+actions = ["http"]
+http_domains = []
+"#,
+    )
+    .unwrap();
+    assert!(no_domains.is_within(&limited));
+
+    let every_key = ProgramManifest::from_toml(
+        r#"
+actions = ["http"]
+shared_memory_namespaces = ["*"]
+"#,
+    )
+    .unwrap();
+    assert!(!every_key.is_within(&manifest));
+    assert!(limited.is_within(&every_key));
+
+    let other_contract = ProgramManifest::from_toml(
+        r#"
+actions = ["chain_view"]
+
+[[contracts]]
+chain = "Near"
+contract_id = "other.near"
+"#,
+    )
+    .unwrap();
+    assert!(!other_contract.is_within(&manifest));
+}
+
+#[test]
+fn test_manifest_host_functions() {
+    assert!(ProgramManifest::from_toml(r#"host_functions = ["bn254_sign"]"#).is_err());
+    assert!(ProgramManifest::from_toml(r#"host_functions = ["vrf_prove"]"#).is_err());
+    assert!(!ProgramManifest::limited().allows_host_function("bn254_sign"));
+    assert!(!ProgramManifest::limited().allows_host_function("vrf_prove"));
+
+    let manifest = ProgramManifest::from_toml(r#"host_functions = ["p2p_auth_sign"]"#).unwrap();
+    assert!(manifest.allows_host_function("p2p_auth_sign"));
+    assert!(!ProgramManifest::limited().allows_host_function("p2p_auth_sign"));
+    assert!(ProgramManifest::unrestricted().allows_host_function("p2p_auth_sign"));
+    // Host functions that do not sign with the keys of the node are not gated
+    assert!(ProgramManifest::limited().allows_host_function("sha256"));
+
+    assert!(!manifest.is_within(&ProgramManifest::limited()));
+}
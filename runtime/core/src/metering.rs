@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use seda_config::WasmCompiler;
+use seda_runtime_sdk::PromiseAction;
+#[cfg(feature = "cranelift")]
+use wasmer::Cranelift;
+#[cfg(feature = "singlepass")]
+use wasmer::Singlepass;
+#[cfg(feature = "llvm")]
+use wasmer::LLVM;
+use wasmer::{wasmparser::Operator, BaseTunables, CompilerConfig, Engine, Pages, Store, Universal, Val};
+use wasmer_middlewares::Metering;
+
+use crate::{LimitingTunables, Result, VmContext};
+
+/// The gas prices of the operations a WASM execution can perform
+pub struct GasCost;
+
+impl GasCost {
+    /// Charged for every call from WASM into a host import
+    pub const HOST_CALL: u64 = 1_000;
+    /// Charged for every byte copied between the host and the WASM memory
+    pub const HOST_CALL_PER_BYTE: u64 = 1;
+    /// Charged for every WASM instruction
+    pub const INSTRUCTION: u64 = 1;
+    /// Charged for every signature that is created, verified or recovered by
+    /// a host import
+    pub const SIGNATURE: u64 = 50_000;
+
+    /// The price of a call that copies `length` bytes chosen by the WASM
+    /// binary, which can not be negative
+    pub fn with_bytes<L: Into<i64>>(base: u64, length: L) -> Result<u64> {
+        let length = length.into();
+        let length = u64::try_from(length).map_err(|_| format!("Invalid length `{length}`"))?;
+
+        Ok(base.saturating_add(Self::HOST_CALL_PER_BYTE.saturating_mul(length)))
+    }
+
+    /// The price of resolving a promise action, charged before the action is
+    /// executed.
+    pub fn promise_action(action: &PromiseAction) -> u64 {
+        match action {
+            PromiseAction::CallSelf(_) => 10_000,
+            PromiseAction::CallProgram(_) => 100_000,
+            PromiseAction::DatabaseSet(_) => 50_000,
+            PromiseAction::DatabaseGet(_) => 50_000,
+            PromiseAction::Http(_) => 1_000_000,
+            PromiseAction::ChainView(_) => 1_000_000,
+            PromiseAction::ChainCall(_) => 5_000_000,
+            PromiseAction::TriggerEvent(_) => 100_000,
+            PromiseAction::P2PBroadcast(_) => 500_000,
+            PromiseAction::All | PromiseAction::Any => Self::HOST_CALL,
+        }
+    }
+}
+
+fn cost_function(_operator: &Operator) -> u64 {
+    GasCost::INSTRUCTION
+}
+
+/// Creates a store which compiles with the given compiler and injects gas
+/// metering into every module compiled with it. The remaining gas is set per
+/// instance before execution, so the initial limit given to the middleware is
+/// never used. The linear memory of every instance is capped to
+/// `max_memory_pages`.
+///
+/// Fails when `seda-runtime` was built without the feature of the compiler.
+pub fn create_metered_store(compiler: WasmCompiler, max_memory_pages: u32) -> Result<Store> {
+    let mut compiler_config: Box<dyn CompilerConfig> = match compiler {
+        #[cfg(feature = "cranelift")]
+        WasmCompiler::Cranelift => Box::new(Cranelift::default()),
+        #[cfg(feature = "singlepass")]
+        WasmCompiler::Singlepass => Box::new(Singlepass::default()),
+        #[cfg(feature = "llvm")]
+        WasmCompiler::Llvm => Box::new(LLVM::default()),
+        #[allow(unreachable_patterns)]
+        compiler => Err(format!(
+            "The `{compiler}` compiler is not available, build seda-runtime with the `{compiler}` feature"
+        ))?,
+    };
+    let metering = Arc::new(Metering::new(0, cost_function));
+    compiler_config.push_middleware(metering);
+
+    let engine = Universal::new(compiler_config).engine();
+    let tunables = LimitingTunables::new(BaseTunables::for_target(engine.target()), Pages(max_memory_pages));
+
+    Ok(Store::new_with_tunables(&engine, tunables))
+}
+
+/// Keeps track of the gas consumed during a single execution (all queues and
+/// instances included)
+#[derive(Clone, Debug)]
+pub struct GasMeter {
+    limit: u64,
+    used:  u64,
+}
+
+impl GasMeter {
+    pub fn new(limit: u64) -> Self {
+        Self { limit, used: 0 }
+    }
+
+    pub fn used(&self) -> u64 {
+        self.used
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.limit.saturating_sub(self.used)
+    }
+
+    /// Consumes the given amount of gas, returns false (and uses all the gas
+    /// that was left) when there is not enough gas remaining
+    pub fn consume(&mut self, amount: u64) -> bool {
+        if amount > self.remaining() {
+            self.used = self.limit;
+            return false;
+        }
+
+        self.used += amount;
+        true
+    }
+
+    /// Uses all the remaining gas
+    pub fn exhaust(&mut self) {
+        self.used = self.limit;
+    }
+}
+
+/// Charges gas from inside a host import by updating the metering globals of
+/// the running instance. Returns an error (which traps the instance) when
+/// there is not enough gas left.
+pub fn charge_gas(env: &VmContext, amount: u64) -> Result<()> {
+    let remaining_points = env
+        .remaining_points
+        .get_ref()
+        .ok_or("Metering globals could not be retrieved")?;
+    let points_exhausted = env
+        .points_exhausted
+        .get_ref()
+        .ok_or("Metering globals could not be retrieved")?;
+
+    let remaining: u64 = remaining_points
+        .get()
+        .i64()
+        .ok_or("Remaining points global is not an i64")? as u64;
+
+    if amount > remaining {
+        remaining_points.set(Val::I64(0)).map_err(|e| e.message())?;
+        points_exhausted.set(Val::I32(1)).map_err(|e| e.message())?;
+        Err("Out of gas")?;
+    }
+
+    remaining_points
+        .set(Val::I64((remaining - amount) as i64))
+        .map_err(|e| e.message())?;
+
+    Ok(())
+}
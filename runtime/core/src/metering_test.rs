@@ -0,0 +1,17 @@
+use crate::GasCost;
+
+#[test]
+fn test_gas_cost_with_bytes() {
+    assert_eq!(
+        GasCost::with_bytes(GasCost::HOST_CALL, 10i32).unwrap(),
+        GasCost::HOST_CALL + 10 * GasCost::HOST_CALL_PER_BYTE
+    );
+    assert_eq!(
+        GasCost::with_bytes(GasCost::HOST_CALL, i64::MAX).unwrap(),
+        GasCost::HOST_CALL + i64::MAX as u64
+    );
+    assert_eq!(GasCost::with_bytes(u64::MAX, 1u32).unwrap(), u64::MAX);
+
+    assert!(GasCost::with_bytes(GasCost::HOST_CALL, -1i32).is_err());
+    assert!(GasCost::with_bytes(GasCost::SIGNATURE, i64::MIN).is_err());
+}
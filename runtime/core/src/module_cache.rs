@@ -0,0 +1,160 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use seda_config::WasmCompiler;
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+use wasmer::{Engine, Module, Store};
+
+use crate::Result;
+
+/// Used to give every temporary cache file written by this process a unique
+/// name
+static TMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// An on-disk cache of compiled WASM modules, shared by all runtime workers
+/// and kept across restarts.
+///
+/// Artifacts are keyed by the hash of the WASM binary and everything that
+/// changes the compiled output: the wasmer version, the compiler, the engine
+/// target, the memory limit and [ModuleCache::VERSION]. Every artifact is
+/// stored with a checksum, corrupt or unreadable artifacts are removed and
+/// recompiled.
+#[derive(Clone, Debug)]
+pub struct ModuleCache {
+    path:     PathBuf,
+    compiler: WasmCompiler,
+}
+
+impl ModuleCache {
+    const CHECKSUM_LEN: usize = 32;
+    const EXTENSION: &str = "module";
+    /// Has to be bumped whenever the metering or tunables change in a way that
+    /// makes previously compiled modules invalid
+    pub const VERSION: u32 = 1;
+
+    /// The store modules are compiled and loaded with has to be created with
+    /// the same compiler
+    pub fn new<P: Into<PathBuf>>(path: P, compiler: WasmCompiler) -> Self {
+        Self {
+            path: path.into(),
+            compiler,
+        }
+    }
+
+    /// Loads the compiled module from the cache, or compiles it with the given
+    /// store and writes it to the cache when there is no valid artifact yet.
+    /// Failing to write the cache is not fatal.
+    pub fn load_or_compile(&self, store: &Store, wasm_binary: &[u8], max_memory_pages: u32) -> Result<Module> {
+        let (key, artifact_path) = self.artifact_path(store, wasm_binary, max_memory_pages);
+
+        if let Some(module) = Self::load(store, &artifact_path) {
+            debug!("Loaded compiled module {key} from cache");
+            return Ok(module);
+        }
+
+        let module = Module::new(store, wasm_binary)?;
+        if let Err(error) = self.write(&artifact_path, &module) {
+            warn!("Failed to write compiled module {key} to cache: {error}");
+        }
+
+        Ok(module)
+    }
+
+    /// Compiles the module ahead of time and writes it to the cache, even when
+    /// it is already cached. A node with the same compiler, memory limit and
+    /// target that uses this cache then loads it without compiling. Nothing is
+    /// written when `validate` rejects the module. Returns the path of the
+    /// artifact.
+    pub fn precompile<V>(
+        &self,
+        store: &Store,
+        wasm_binary: &[u8],
+        max_memory_pages: u32,
+        validate: V,
+    ) -> Result<(Module, PathBuf)>
+    where
+        V: FnOnce(&Module) -> Result<()>,
+    {
+        let (_, artifact_path) = self.artifact_path(store, wasm_binary, max_memory_pages);
+
+        let module = Module::new(store, wasm_binary)?;
+        validate(&module)?;
+        self.write(&artifact_path, &module)?;
+
+        Ok((module, artifact_path))
+    }
+
+    fn artifact_path(&self, store: &Store, wasm_binary: &[u8], max_memory_pages: u32) -> (String, PathBuf) {
+        let key = self.key(store, wasm_binary, max_memory_pages);
+        let artifact_path = self.path.join(format!("{key}.{}", Self::EXTENSION));
+
+        (key, artifact_path)
+    }
+
+    fn key(&self, store: &Store, wasm_binary: &[u8], max_memory_pages: u32) -> String {
+        let target = store.engine().target();
+
+        let mut hasher = Sha256::new();
+        hasher.update(wasm_binary);
+        hasher.update(wasmer::VERSION.as_bytes());
+        hasher.update(Self::VERSION.to_le_bytes());
+        hasher.update(self.compiler.to_string().as_bytes());
+        hasher.update(target.triple().to_string().as_bytes());
+        hasher.update(format!("{:?}", target.cpu_features()).as_bytes());
+        hasher.update(max_memory_pages.to_le_bytes());
+
+        hex::encode(hasher.finalize())
+    }
+
+    fn load(store: &Store, artifact_path: &Path) -> Option<Module> {
+        let bytes = fs::read(artifact_path).ok()?;
+
+        let valid = bytes.len() > Self::CHECKSUM_LEN
+            && Sha256::digest(&bytes[Self::CHECKSUM_LEN..])[..] == bytes[..Self::CHECKSUM_LEN];
+        if !valid {
+            warn!("Removing corrupt compiled module {}", artifact_path.display());
+            fs::remove_file(artifact_path).ok();
+            return None;
+        }
+
+        // SAFETY: The artifact was serialized by `write` with the same wasmer version
+        // and engine target (both part of the key) and its checksum matches.
+        match unsafe { Module::deserialize(store, &bytes[Self::CHECKSUM_LEN..]) } {
+            Ok(module) => Some(module),
+            Err(error) => {
+                warn!(
+                    "Removing compiled module {} that failed to deserialize: {error}",
+                    artifact_path.display()
+                );
+                fs::remove_file(artifact_path).ok();
+                None
+            }
+        }
+    }
+
+    /// Writes to a temporary file first and then renames it, so other workers
+    /// never read a partially written artifact
+    fn write(&self, artifact_path: &Path, module: &Module) -> Result<()> {
+        let artifact = module.serialize().map_err(|error| error.to_string())?;
+        let mut bytes = Sha256::digest(&artifact).to_vec();
+        bytes.extend(artifact);
+
+        fs::create_dir_all(&self.path)?;
+        let tmp_path = artifact_path.with_extension(format!(
+            "{}.{}.tmp",
+            std::process::id(),
+            TMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&tmp_path, bytes)?;
+        fs::rename(&tmp_path, artifact_path).map_err(|error| {
+            fs::remove_file(&tmp_path).ok();
+            error
+        })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use seda_config::NodeConfig;
+use sha2::{Digest, Sha256};
+use tracing::info;
+use wasmer::{Module, Store};
+
+use crate::{create_metered_store, uses_float_ops, validate_program, ModuleCache, ProgramManifest, Result};
+
+/// A compiled WASM binary that can be executed by the runtime
+#[derive(Clone)]
+pub struct Program {
+    pub name:           String,
+    /// Hex encoded sha256 hash of the WASM binary
+    pub hash:           String,
+    pub module:         Module,
+    /// Whether the binary contains float instructions, which deterministic
+    /// executions can be configured to reject
+    pub uses_float_ops: bool,
+    /// The capabilities the runtime grants the program
+    pub manifest:       Arc<ProgramManifest>,
+    /// The file the program was loaded from, programs registered from memory
+    /// can not be reloaded
+    pub path:           Option<PathBuf>,
+}
+
+/// Compiles and validates WASM binaries for a [ProgramRegistry]. It is cheap
+/// to clone, so programs can be compiled without holding a lock on the
+/// registry.
+#[derive(Clone)]
+pub struct ProgramCompiler {
+    store:            Store,
+    cache:            ModuleCache,
+    max_memory_pages: u32,
+    node_config:      NodeConfig,
+}
+
+impl ProgramCompiler {
+    /// Fails when the configured compiler is not available
+    pub fn new(node_config: &NodeConfig) -> Result<Self> {
+        Ok(Self {
+            store:            create_metered_store(node_config.wasm_compiler, node_config.max_memory_pages)?,
+            cache:            ModuleCache::new(&node_config.wasm_cache_path, node_config.wasm_compiler),
+            max_memory_pages: node_config.max_memory_pages,
+            node_config:      node_config.clone(),
+        })
+    }
+
+    /// Compiles the WASM binary (or loads it from the module cache) and
+    /// validates it without registering it
+    pub fn compile(&self, name: &str, wasm_binary: &[u8], manifest: ProgramManifest) -> Result<Program> {
+        let module = self
+            .cache
+            .load_or_compile(&self.store, wasm_binary, self.max_memory_pages)?;
+        validate_program(name, &self.store, &module, wasm_binary, &self.node_config)?;
+
+        Ok(Program {
+            name: name.to_string(),
+            hash: hex::encode(Sha256::digest(wasm_binary)),
+            module,
+            uses_float_ops: uses_float_ops(wasm_binary)?,
+            manifest: Arc::new(manifest),
+            path: None,
+        })
+    }
+
+    /// Compiles the WASM binary on disk like [ProgramCompiler::compile]. The
+    /// manifest defaults to [ProgramManifest::for_program].
+    pub fn compile_file<P: AsRef<Path>>(
+        &self,
+        name: &str,
+        path: P,
+        manifest: Option<ProgramManifest>,
+    ) -> Result<Program> {
+        let path = path.as_ref();
+        let wasm_binary = fs::read(path)?;
+        let manifest = match manifest {
+            Some(manifest) => manifest,
+            None => ProgramManifest::for_program(path, &wasm_binary)?,
+        };
+
+        Ok(Program {
+            path: Some(path.to_path_buf()),
+            ..self.compile(name, &wasm_binary, manifest)?
+        })
+    }
+}
+
+/// Where the current version of a registered program is compiled from
+#[derive(Clone, Debug)]
+pub struct ProgramSource {
+    pub name:     String,
+    pub path:     PathBuf,
+    /// The hash of the version that was registered when the source was read
+    pub hash:     String,
+    /// Kept from the registered version when the node granted it everything,
+    /// read again from the file otherwise
+    pub manifest: Option<ProgramManifest>,
+}
+
+impl ProgramSource {
+    pub fn compile(&self, compiler: &ProgramCompiler) -> Result<Program> {
+        compiler.compile_file(&self.name, &self.path, self.manifest.clone())
+    }
+}
+
+/// Holds all the programs the runtime can execute, by name. A single registry
+/// is shared by all runtime workers so programs are only compiled once.
+pub struct ProgramRegistry {
+    programs: HashMap<String, Program>,
+    compiler: ProgramCompiler,
+}
+
+impl ProgramRegistry {
+    pub const CONSENSUS: &str = "consensus";
+    pub const WASM_EXTENSION: &str = "wasm";
+
+    /// Fails when the configured compiler is not available
+    pub fn new(node_config: &NodeConfig) -> Result<Self> {
+        Ok(Self {
+            programs: HashMap::new(),
+            compiler: ProgramCompiler::new(node_config)?,
+        })
+    }
+
+    /// The compiler of the registry, to compile programs without holding a
+    /// lock on it
+    pub fn compiler(&self) -> ProgramCompiler {
+        self.compiler.clone()
+    }
+
+    /// Compiles the WASM binary (or loads it from the module cache) and
+    /// validates it without registering it, see [ProgramCompiler::compile]
+    pub fn compile(&self, name: &str, wasm_binary: &[u8], manifest: ProgramManifest) -> Result<Program> {
+        self.compiler.compile(name, wasm_binary, manifest)
+    }
+
+    /// Compiles the WASM binary on disk without registering it, see
+    /// [ProgramCompiler::compile_file]
+    pub fn compile_file<P: AsRef<Path>>(
+        &self,
+        name: &str,
+        path: P,
+        manifest: Option<ProgramManifest>,
+    ) -> Result<Program> {
+        self.compiler.compile_file(name, path, manifest)
+    }
+
+    /// Where the current version of a registered program is compiled from,
+    /// fails for programs that were not loaded from a file
+    pub fn source(&self, name: &str) -> Result<ProgramSource> {
+        let program = self
+            .programs
+            .get(name)
+            .ok_or_else(|| format!("Program `{name}` is not registered"))?;
+        let path = program
+            .path
+            .clone()
+            .ok_or_else(|| format!("Program `{name}` was not loaded from a file"))?;
+
+        Ok(ProgramSource {
+            name: name.to_string(),
+            path,
+            hash: program.hash.clone(),
+            manifest: program.manifest.is_unrestricted().then(ProgramManifest::unrestricted),
+        })
+    }
+
+    /// Compiles the current version of a registered program from the file it
+    /// was loaded from, without replacing the registered version. A program
+    /// the node granted everything keeps its manifest.
+    pub fn recompile(&self, name: &str) -> Result<Program> {
+        self.source(name)?.compile(&self.compiler)
+    }
+
+    /// Checks that a program registered from outside the node can take the
+    /// name: it may not be empty or contain `/`, which separates the shared
+    /// memory namespace of the program, and may not resolve to a registered
+    /// program, by name or by hash. Programs can only be replaced by the node.
+    pub fn check_new_name(&self, name: &str) -> Result<()> {
+        if name.is_empty() || name.contains('/') {
+            Err(format!(
+                "Invalid program name `{name}`, it can not be empty or contain `/`"
+            ))?;
+        }
+        if name == Self::CONSENSUS || self.get(name).is_some() {
+            Err(format!("Program `{name}` is already registered"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds a compiled program, replacing the program with the same name
+    pub fn insert(&mut self, program: Program) -> Option<Program> {
+        info!("Registered program `{}` ({})", program.name, program.hash);
+        self.programs.insert(program.name.clone(), program)
+    }
+
+    /// Swaps in a program compiled from the source, fails when the registered
+    /// program was replaced or removed since the source was read
+    pub fn replace(&mut self, source: &ProgramSource, program: Program) -> Result<Option<Program>> {
+        let unchanged = self.programs.get(&source.name).map_or(false, |registered| {
+            registered.hash == source.hash && registered.path.as_ref() == Some(&source.path)
+        });
+        if !unchanged {
+            Err(format!("Program `{}` changed while it was reloaded", source.name))?;
+        }
+
+        Ok(self.insert(program))
+    }
+
+    /// Compiles and registers the WASM binary under the given name with the
+    /// manifest in its custom section, returns the hash of the binary
+    pub fn register(&mut self, name: &str, wasm_binary: &[u8]) -> Result<String> {
+        let manifest = ProgramManifest::from_custom_section(wasm_binary)?.unwrap_or_else(ProgramManifest::limited);
+        self.register_with_manifest(name, wasm_binary, manifest)
+    }
+
+    /// Compiles and registers the WASM binary under the given name, returns
+    /// the hash of the binary
+    pub fn register_with_manifest(
+        &mut self,
+        name: &str,
+        wasm_binary: &[u8],
+        manifest: ProgramManifest,
+    ) -> Result<String> {
+        let program = self.compile(name, wasm_binary, manifest)?;
+        let hash = program.hash.clone();
+        self.insert(program);
+
+        Ok(hash)
+    }
+
+    /// Registers every `.wasm` file in the directory, named after the file
+    /// stem. A `.toml` file with the same stem is the manifest of the
+    /// program. Returns the names of the registered programs.
+    pub fn load_dir<P: AsRef<Path>>(&mut self, path: P) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(Self::WASM_EXTENSION) {
+                continue;
+            }
+
+            let name = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .ok_or_else(|| format!("Invalid program file name: {}", path.display()))?
+                .to_string();
+            let program = self.compile_file(&name, &path, None)?;
+            self.insert(program);
+            names.push(name);
+        }
+
+        Ok(names)
+    }
+
+    /// Gets a program by its name, or by the hash of its binary
+    pub fn get(&self, name_or_hash: &str) -> Option<&Program> {
+        self.programs
+            .get(name_or_hash)
+            .or_else(|| self.programs.values().find(|program| program.hash == name_or_hash))
+    }
+
+    /// Replaces the manifest of a registered program, returns false when the
+    /// program is not registered
+    pub fn set_manifest(&mut self, name: &str, manifest: ProgramManifest) -> bool {
+        match self.programs.get_mut(name) {
+            Some(program) => {
+                program.manifest = Arc::new(manifest);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Program> {
+        self.programs.remove(name)
+    }
+
+    pub fn programs(&self) -> impl Iterator<Item = &Program> {
+        self.programs.values()
+    }
+}
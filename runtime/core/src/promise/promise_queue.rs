@@ -1,8 +1,9 @@
 /// Acts as a wrapper around a vector of promises
 /// This allows us to change to batches easily in the future
 use seda_runtime_sdk::Promise;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
 pub struct PromiseQueue {
     /// A list which contains batches of promises
     pub queue: Vec<Promise>,
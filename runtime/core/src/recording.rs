@@ -0,0 +1,247 @@
+use std::{
+    collections::HashSet,
+    fmt::Debug,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use parking_lot::Mutex;
+use seda_runtime_sdk::{events::Event, ExecutionContext};
+use serde::{Deserialize, Serialize};
+
+use crate::{DryRun, MemoryAdapter, PersistentMemory, ProgramManifest, PromiseQueue, Result, VmConfig, VmResult};
+
+/// Used to give every recording written by this process a unique name
+static RECORDING_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// The shared memory accesses of an execution, in the order they happened
+pub type SharedMemoryLog = Arc<Mutex<Vec<SharedMemoryAccess>>>;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum SharedMemoryAccess {
+    Read { key: String, value: Option<Vec<u8>> },
+    Write { key: String, value: Vec<u8> },
+    Remove { key: String },
+    Keys { prefix: String, keys: Vec<String> },
+}
+
+impl SharedMemoryAccess {
+    /// Whether the access changed the shared memory
+    pub fn is_write(&self) -> bool {
+        matches!(self, Self::Write { .. } | Self::Remove { .. })
+    }
+}
+
+/// Every interaction of a runtime job with the host: the promise actions and
+/// their results (in the promise queue trace), the shared memory accesses and
+/// the env and args the WASM binary was started with. A recording can be
+/// replayed offline with [crate::replay()].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Recording {
+    pub program_name:        String,
+    /// Hex encoded sha256 hash of the WASM binary that was executed
+    pub program_hash:        String,
+    pub start_func:          Option<String>,
+    pub args:                Vec<String>,
+    #[serde(default)]
+    pub input:               Vec<u8>,
+    pub gas_limit:           Option<u64>,
+    /// The manifest the program was executed with, only missing when the
+    /// program was not found
+    #[serde(default)]
+    pub manifest:            Option<ProgramManifest>,
+    #[serde(default)]
+    pub deterministic:       bool,
+    pub env:                 Vec<(String, String)>,
+    #[serde(default)]
+    pub event:               Option<Event>,
+    /// Only missing in recordings made before the context existed
+    #[serde(default)]
+    pub execution_context:   Option<ExecutionContext>,
+    #[serde(default)]
+    pub dry_run:             Option<DryRun>,
+    pub shared_memory:       Vec<SharedMemoryAccess>,
+    pub promise_queue_trace: Vec<PromiseQueue>,
+    pub vm_result:           VmResult,
+}
+
+impl Recording {
+    const EXTENSION: &str = "json";
+
+    pub fn read_from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// Writes the recording to a new file in the directory, returns the path
+    /// of the file
+    pub fn write_to_dir<P: AsRef<Path>>(&self, dir: P) -> Result<PathBuf> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|error| error.to_string())?
+            .as_millis();
+        let path = dir.as_ref().join(format!(
+            "{timestamp}-{}-{}-{}.{}",
+            self.program_name,
+            std::process::id(),
+            RECORDING_COUNTER.fetch_add(1, Ordering::Relaxed),
+            Self::EXTENSION
+        ));
+
+        fs::create_dir_all(dir)?;
+        fs::write(&path, serde_json::to_vec_pretty(self)?)?;
+
+        Ok(path)
+    }
+
+    /// The config the recorded job was started with. Replays are not bound to
+    /// the recorded timeout.
+    pub fn vm_config(&self) -> VmConfig {
+        VmConfig {
+            program_name:  self.program_name.clone(),
+            start_func:    self.start_func.clone(),
+            args:          self.args.clone(),
+            input:         self.input.clone(),
+            debug:         false,
+            gas_limit:     self.gas_limit,
+            timeout:       None,
+            deterministic: self.deterministic,
+            event:         self.event.clone(),
+            dry_run:       self.dry_run.clone(),
+        }
+    }
+
+    /// The shared memory as it was seen by the job: every key that was read
+    /// before the job wrote to it, with the value it read. Keys that were only
+    /// listed get an empty value.
+    pub fn initial_shared_memory(&self) -> PersistentMemory {
+        let mut memory = PersistentMemory::default();
+        let mut seen = HashSet::new();
+
+        for access in &self.shared_memory {
+            match access {
+                SharedMemoryAccess::Read { key, value } => {
+                    if seen.insert(key.clone()) {
+                        match value {
+                            Some(value) => memory.put(key, value.clone()),
+                            None => memory.remove(key),
+                        };
+                    }
+                }
+                SharedMemoryAccess::Write { key, .. } | SharedMemoryAccess::Remove { key } => {
+                    seen.insert(key.clone());
+                }
+                SharedMemoryAccess::Keys { keys, .. } => {
+                    for key in keys {
+                        if !seen.contains(key) && !memory.contains_key(key) {
+                            memory.put(key, Vec::new());
+                        }
+                    }
+                }
+            }
+        }
+
+        memory
+    }
+
+    /// Describes every way the other recording differs from this one, empty
+    /// when they match
+    pub fn diff(&self, other: &Recording) -> Vec<String> {
+        let mut differences = Vec::new();
+
+        compare(
+            &mut differences,
+            "program hash",
+            &self.program_hash,
+            &other.program_hash,
+        );
+        compare(
+            &mut differences,
+            "exit info",
+            &self.vm_result.exit_info,
+            &other.vm_result.exit_info,
+        );
+        compare(
+            &mut differences,
+            "result",
+            &self.vm_result.result,
+            &other.vm_result.result,
+        );
+        compare(
+            &mut differences,
+            "stdout",
+            &self.vm_result.stdout,
+            &other.vm_result.stdout,
+        );
+        compare(
+            &mut differences,
+            "stderr",
+            &self.vm_result.stderr,
+            &other.vm_result.stderr,
+        );
+        compare(
+            &mut differences,
+            "gas used",
+            &self.vm_result.gas_used,
+            &other.vm_result.gas_used,
+        );
+
+        let writes = |recording: &Recording| -> Vec<SharedMemoryAccess> {
+            recording
+                .shared_memory
+                .iter()
+                .filter(|access| access.is_write())
+                .cloned()
+                .collect()
+        };
+        compare(&mut differences, "shared memory writes", &writes(self), &writes(other));
+
+        compare(
+            &mut differences,
+            "promise queue count",
+            &self.promise_queue_trace.len(),
+            &other.promise_queue_trace.len(),
+        );
+        for (queue_index, (queue, other_queue)) in self
+            .promise_queue_trace
+            .iter()
+            .zip(&other.promise_queue_trace)
+            .enumerate()
+        {
+            compare(
+                &mut differences,
+                &format!("promise count of queue {queue_index}"),
+                &queue.queue.len(),
+                &other_queue.queue.len(),
+            );
+            for (index, (promise, other_promise)) in queue.queue.iter().zip(&other_queue.queue).enumerate() {
+                compare(
+                    &mut differences,
+                    &format!("action of promise {index} in queue {queue_index}"),
+                    &promise.action,
+                    &other_promise.action,
+                );
+                compare(
+                    &mut differences,
+                    &format!("status of promise {index} in queue {queue_index}"),
+                    &promise.status,
+                    &other_promise.status,
+                );
+            }
+        }
+
+        differences
+    }
+}
+
+/// Compares the JSON encoding of both values, most of the recorded types have
+/// no `PartialEq`
+fn compare<T: Serialize + Debug>(differences: &mut Vec<String>, name: &str, recorded: &T, replayed: &T) {
+    if serde_json::to_string(recorded).ok() != serde_json::to_string(replayed).ok() {
+        differences.push(format!("{name}: recorded {recorded:?}, replayed {replayed:?}"));
+    }
+}
@@ -0,0 +1,216 @@
+use std::sync::Arc;
+
+use parking_lot::{Mutex, RwLock};
+use seda_chains::{AnotherChain, ChainAdapterError, ChainAdapterTrait, Client, NearChain};
+use seda_config::{ChainConfigs, NodeConfig};
+use seda_runtime_sdk::{
+    events::Event,
+    p2p::P2PCommand,
+    Chain,
+    ChainCallAction,
+    ChainViewAction,
+    DatabaseGetAction,
+    DatabaseSetAction,
+    FromBytes,
+    HttpAction,
+    PromiseAction,
+    PromiseStatus,
+    SDKError,
+    TriggerEventAction,
+};
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+use crate::{HostAdapter, InMemory, ProgramRegistry, Recording, Result, Runtime, RuntimeError};
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("No recorded result for `{0}`")]
+    NotRecorded(String),
+
+    /// The recorded rejection, displayed as is so the replayed promise is
+    /// rejected with the same message
+    #[error("{0}")]
+    Rejected(String),
+
+    #[error(transparent)]
+    SDKError(#[from] SDKError),
+
+    #[error(transparent)]
+    ChainAdapterError(#[from] ChainAdapterError),
+}
+
+/// A host adapter that answers every promise action with its recorded result
+/// instead of touching the network or database
+pub struct ReplayAdapter {
+    another_client: Client,
+    near_client:    Client,
+    /// Recorded actions that were not replayed yet, with their results
+    recorded:       Mutex<Vec<(PromiseAction, PromiseStatus)>>,
+}
+
+impl ReplayAdapter {
+    pub fn from_recording(config: ChainConfigs, recording: &Recording) -> Result<Self, ReplayError> {
+        let adapter = Self::from_chain_configs(config)?;
+        *adapter.recorded.lock() = recording
+            .promise_queue_trace
+            .iter()
+            .flat_map(|promise_queue| &promise_queue.queue)
+            .map(|promise| (promise.action.clone(), promise.status.clone()))
+            .collect();
+
+        Ok(adapter)
+    }
+
+    fn from_chain_configs(config: ChainConfigs) -> Result<Self, ReplayError> {
+        Ok(Self {
+            another_client: Client::Another(AnotherChain::new_client(&config.another)?),
+            near_client:    Client::Near(NearChain::new_client(&config.near)?),
+            recorded:       Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Takes the first recorded result of the action. Actions are matched by
+    /// their JSON encoding, concurrently resolved actions may be replayed in
+    /// a different order than they were recorded.
+    fn replay(&self, action: PromiseAction) -> Result<Option<Vec<u8>>, ReplayError> {
+        let encoded = serde_json::to_string(&action).ok();
+        let mut recorded = self.recorded.lock();
+        let position = recorded
+            .iter()
+            .position(|(recorded_action, _)| serde_json::to_string(recorded_action).ok() == encoded)
+            .ok_or_else(|| ReplayError::NotRecorded(action.to_string()))?;
+
+        match recorded.remove(position).1 {
+            PromiseStatus::Fulfilled(data) => Ok(data),
+            PromiseStatus::Rejected(data) => Err(ReplayError::Rejected(String::from_utf8_lossy(&data).into_owned())),
+            _ => Err(ReplayError::NotRecorded(action.to_string())),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl HostAdapter for ReplayAdapter {
+    type Error = ReplayError;
+
+    async fn new(config: ChainConfigs) -> Result<Self, Self::Error> {
+        Self::from_chain_configs(config)
+    }
+
+    fn select_client_from_chain(&self, chain: Chain) -> Client {
+        match chain {
+            Chain::Another => self.another_client.clone(),
+            Chain::Near => self.near_client.clone(),
+        }
+    }
+
+    async fn db_get(&self, key: &str) -> Result<Option<String>, Self::Error> {
+        let value = self.replay(PromiseAction::DatabaseGet(DatabaseGetAction { key: key.to_string() }))?;
+
+        Ok(value.map(String::from_bytes_vec).transpose()?)
+    }
+
+    async fn db_set(&self, key: &str, value: &str) -> Result<(), Self::Error> {
+        self.replay(PromiseAction::DatabaseSet(DatabaseSetAction {
+            key:   key.to_string(),
+            value: value.as_bytes().to_vec(),
+        }))?;
+
+        Ok(())
+    }
+
+    async fn http_fetch(&self, url: &str) -> Result<String, Self::Error> {
+        let body = self.replay(PromiseAction::Http(HttpAction { url: url.to_string() }))?;
+
+        Ok(String::from_bytes_vec(body.unwrap_or_default())?)
+    }
+
+    async fn chain_call(
+        &self,
+        chain: Chain,
+        contract_id: &str,
+        method_name: &str,
+        args: Vec<u8>,
+        deposit: u128,
+        _node_config: NodeConfig,
+    ) -> Result<Vec<u8>, Self::Error> {
+        let result = self.replay(PromiseAction::ChainCall(ChainCallAction {
+            chain,
+            contract_id: contract_id.to_string(),
+            method_name: method_name.to_string(),
+            args,
+            deposit,
+        }))?;
+
+        Ok(result.unwrap_or_default())
+    }
+
+    async fn chain_view(
+        &self,
+        chain: Chain,
+        contract_id: &str,
+        method_name: &str,
+        args: Vec<u8>,
+    ) -> Result<Vec<u8>, Self::Error> {
+        let result = self.replay(PromiseAction::ChainView(ChainViewAction {
+            chain,
+            contract_id: contract_id.to_string(),
+            method_name: method_name.to_string(),
+            args,
+        }))?;
+
+        Ok(result.unwrap_or_default())
+    }
+
+    async fn trigger_event(&self, event: Event) -> Result<(), Self::Error> {
+        self.replay(PromiseAction::TriggerEvent(TriggerEventAction { event }))?;
+
+        Ok(())
+    }
+}
+
+/// The outcome of replaying a recording
+pub struct Replay {
+    /// The recording of the replayed execution
+    pub recording:   Recording,
+    /// Every way the replay differs from the original recording
+    pub differences: Vec<String>,
+}
+
+/// Re-executes a recorded runtime job offline against its recording and
+/// compares the outcome. The recorded program has to be registered in
+/// `programs`, it is executed with the recorded manifest.
+pub async fn replay(
+    recording: &Recording,
+    node_config: NodeConfig,
+    chains_config: ChainConfigs,
+    programs: Arc<RwLock<ProgramRegistry>>,
+) -> Result<Replay> {
+    let host_adapter =
+        ReplayAdapter::from_recording(chains_config, recording).map_err(|e| RuntimeError::NodeError(e.to_string()))?;
+    let shared_memory = Arc::new(RwLock::new(recording.initial_shared_memory()));
+    if let Some(manifest) = &recording.manifest {
+        programs.write().set_manifest(&recording.program_name, manifest.clone());
+    }
+
+    let mut runtime =
+        Runtime::from_host_adapter(host_adapter, node_config, shared_memory, programs).with_env(recording.env.clone());
+    if let Some(execution_context) = &recording.execution_context {
+        runtime = runtime.with_execution_context(execution_context.clone());
+    }
+
+    // Broadcasts are not sent anywhere during a replay
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let (_, replayed) = runtime
+        .record_runtime(
+            recording.vm_config(),
+            Arc::new(Mutex::new(InMemory::default())),
+            p2p_command_sender,
+        )
+        .await;
+
+    Ok(Replay {
+        differences: recording.diff(&replayed),
+        recording:   replayed,
+    })
+}
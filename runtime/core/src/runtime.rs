@@ -1,28 +1,103 @@
-use std::{io::Read, sync::Arc};
+use std::{
+    future::Future,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
 
+use borsh::BorshSerialize;
+use futures::{
+    future::{abortable, AbortHandle, Aborted},
+    stream::FuturesUnordered,
+    FutureExt,
+    StreamExt,
+};
 use parking_lot::{Mutex, RwLock};
-use seda_config::{ChainConfigs, NodeConfig};
-use seda_runtime_sdk::{p2p::P2PCommand, CallSelfAction, FromBytes, Promise, PromiseAction, PromiseStatus};
+use seda_config::{ChainConfigs, NodeConfig, NodeConfigInner};
+use seda_runtime_sdk::{
+    p2p::P2PCommand,
+    CallSelfAction,
+    ExecutionContext,
+    FromBytes,
+    Promise,
+    PromiseAction,
+    PromiseStatus,
+};
 use tokio::sync::mpsc::Sender;
-use tracing::info;
-use wasmer::{Instance, Module, Store};
-use wasmer_wasi::{Pipe, WasiState};
+use tracing::{debug, info, info_span, warn};
+use wasmer::{Module, Store};
+use wasmer_middlewares::metering::{get_remaining_points, set_remaining_points, MeteringPoints};
+use wasmer_wasi::WasiState;
 
 use super::{imports::create_wasm_imports, PromiseQueue, Result, VmConfig, VmContext};
 use crate::{
-    vm_result::{ExecutionResult, ExitInfo, VmResult, VmResultStatus},
+    execution_inputs,
+    input_hash,
+    instantiate_with_limit_flag,
+    unix_millis,
+    vm_result::{ExecutionResult, ExitInfo, SimulatedChainCall, VmResult, VmResultStatus},
+    AwaitAction,
+    DeterministicRandom,
+    DryRun,
+    ExecutionDeadline,
+    GasCost,
+    GasMeter,
+    GuestOutput,
+    GuestStream,
     HostAdapter,
     InMemory,
+    PersistentMemory,
+    ProgramManifest,
+    ProgramRegistry,
+    Recording,
     RuntimeError,
+    SharedMemoryLog,
+    SharedMemoryTransaction,
+    DETERMINISTIC_ENV_VARS,
 };
 
+/// How deep `call_program` promises can be nested
+const MAX_CALL_DEPTH: usize = 8;
+
+/// The program a promise queue is executed for. A `call_program` promise
+/// executes the queues of the called program with its own.
+pub struct QueueProgram<'a> {
+    pub module:         &'a Module,
+    pub name:           &'a str,
+    pub manifest:       Arc<ProgramManifest>,
+    /// How many `call_program` promises the queue is nested in
+    pub call_depth:     usize,
+    pub memory_adapter: Arc<Mutex<InMemory>>,
+}
+
+/// The state of a job that every promise queue it executes shares, the queues
+/// of the programs it calls included
+pub struct JobState {
+    pub execution_context:          Arc<ExecutionContext>,
+    pub shared_memory:              Arc<Mutex<SharedMemoryTransaction>>,
+    pub stdout:                     Vec<String>,
+    pub stderr:                     Vec<String>,
+    /// The results of all the executed promise queues, the last one holds the
+    /// result of the job (for JSON RPC). Can also be used to debug the queues.
+    pub promise_queue_trace:        Vec<PromiseQueue>,
+    pub p2p_command_sender_channel: Sender<P2PCommand>,
+    pub gas_meter:                  GasMeter,
+    pub deadline:                   ExecutionDeadline,
+    pub dry_run:                    Option<DryRun>,
+    pub shared_memory_log:          Option<SharedMemoryLog>,
+    pub deterministic_random:       Option<Arc<Mutex<DeterministicRandom>>>,
+}
+
 #[derive(Clone)]
 pub struct Runtime<HA: HostAdapter> {
-    wasm_module:       Option<Module>,
-    limited:           bool,
-    pub host_adapter:  HA,
+    programs:          Arc<RwLock<ProgramRegistry>>,
+    /// The env variables the WASM binary is started with
+    env:               Vec<(String, String)>,
+    /// Replaces the context of every execution when set
+    execution_context: Option<ExecutionContext>,
+    pub host_adapter:  Arc<HA>,
     pub node_config:   NodeConfig,
-    pub shared_memory: Arc<RwLock<InMemory>>,
+    pub shared_memory: Arc<RwLock<PersistentMemory>>,
 }
 
 #[async_trait::async_trait]
@@ -30,26 +105,18 @@ pub trait RunnableRuntime {
     async fn new(
         node_config: NodeConfig,
         chains_config: ChainConfigs,
-        shared_memory: Arc<RwLock<InMemory>>,
-        limited: bool,
+        shared_memory: Arc<RwLock<PersistentMemory>>,
+        programs: Arc<RwLock<ProgramRegistry>>,
     ) -> Result<Self>
     where
         Self: Sized;
     fn init(&mut self, wasm_binary: Vec<u8>) -> Result<()>;
 
-    #[allow(clippy::too_many_arguments)]
     async fn execute_promise_queue(
         &self,
-        wasm_module: &Module,
-        memory_adapter: Arc<Mutex<InMemory>>,
+        program: &QueueProgram<'_>,
         promise_queue: PromiseQueue,
-        stdout: &mut Vec<String>,
-        stderr: &mut Vec<String>,
-        // Getting the results of all the promise queues
-        // Used to get the result of the last execution (for JSON RPC)
-        // Can also be used to debug the queue
-        promise_queue_trace: &mut Vec<PromiseQueue>,
-        p2p_command_sender_channel: Sender<P2PCommand>,
+        job: &mut JobState,
     ) -> ExecutionResult;
 
     async fn start_runtime(
@@ -65,41 +132,42 @@ impl<HA: HostAdapter> RunnableRuntime for Runtime<HA> {
     async fn new(
         node_config: NodeConfig,
         chains_config: ChainConfigs,
-        shared_memory: Arc<RwLock<InMemory>>,
-        limited: bool,
+        shared_memory: Arc<RwLock<PersistentMemory>>,
+        programs: Arc<RwLock<ProgramRegistry>>,
     ) -> Result<Self> {
-        Ok(Self {
-            wasm_module: None,
-            limited,
-            host_adapter: HA::new(chains_config)
-                .await
-                .map_err(|e| RuntimeError::NodeError(e.to_string()))?,
+        let host_adapter = HA::new(chains_config)
+            .await
+            .map_err(|e| RuntimeError::NodeError(e.to_string()))?;
+
+        Ok(Self::from_host_adapter(
+            host_adapter,
             node_config,
             shared_memory,
-        })
+            programs,
+        ))
     }
 
-    /// Initializes the runtime, this speeds up VM execution by caching WASM
-    /// binary parsing
+    /// Initializes the runtime by registering the WASM binary as the consensus
+    /// program. This speeds up VM execution by caching WASM binary parsing.
+    /// Fails when the binary is not a valid program, see
+    /// [crate::validate_program].
     fn init(&mut self, wasm_binary: Vec<u8>) -> Result<()> {
-        let wasm_store = Store::default();
-        let wasm_module = Module::new(&wasm_store, wasm_binary)?;
-
-        self.wasm_module = Some(wasm_module);
+        self.programs.write().register_with_manifest(
+            ProgramRegistry::CONSENSUS,
+            &wasm_binary,
+            ProgramManifest::unrestricted(),
+        )?;
 
         Ok(())
     }
 
     async fn execute_promise_queue(
         &self,
-        wasm_module: &Module,
-        memory_adapter: Arc<Mutex<InMemory>>,
+        program: &QueueProgram<'_>,
         promise_queue: PromiseQueue,
-        stdout: &mut Vec<String>,
-        stderr: &mut Vec<String>,
-        promise_queue_trace: &mut Vec<PromiseQueue>,
-        p2p_command_sender_channel: Sender<P2PCommand>,
+        job: &mut JobState,
     ) -> ExecutionResult {
+        let deadline = job.deadline;
         let mut next_promise_queue = PromiseQueue::new();
         let mut promise_queue_mut = promise_queue.clone();
 
@@ -111,176 +179,375 @@ impl<HA: HostAdapter> RunnableRuntime for Runtime<HA> {
                 return VmResultStatus::EmptyQueue.into();
             }
 
-            for index in 0..promise_queue.queue.len() {
-                promise_queue_mut.queue[index].status = PromiseStatus::Pending;
+            for (index, promise) in promise_queue.queue.iter().enumerate() {
+                // Only allowing dependencies on earlier promises makes cycles impossible
+                promise_queue_mut.queue[index].status =
+                    if promise.dependencies.iter().any(|dependency| *dependency >= index) {
+                        PromiseStatus::Rejected("Promises can only depend on promises queued before them".into())
+                    } else {
+                        PromiseStatus::Unfulfilled
+                    };
+            }
+
+            // Independent actions are resolved concurrently, their results are still
+            // written back by index so `Promise::result` keeps working
+            let mut in_flight = FuturesUnordered::new();
+            let mut abort_handles: Vec<Option<AbortHandle>> = vec![None; promise_queue.queue.len()];
 
-                match &promise_queue.queue[index].action {
-                    action if self.limited && action.is_limited_action() => {
-                        promise_queue_mut.queue[index].status = PromiseStatus::Rejected(
-                            format!("Method `{action}` not allowed in limited runtime").into_bytes(),
-                        )
+            while !promise_queue_mut
+                .queue
+                .iter()
+                .all(|promise| promise.status.is_settled())
+            {
+                for index in 0..promise_queue.queue.len() {
+                    if !matches!(promise_queue_mut.queue[index].status, PromiseStatus::Unfulfilled)
+                        || !self.is_ready(&promise_queue_mut.queue, index)
+                    {
+                        continue;
                     }
-                    // TODO need an ok_or type situation here. if its ok continue otherwise reject
-                    // promise? or maybe it should return a VMResult. Might hold off on this till the VMResult changes.
-                    PromiseAction::CallSelf(call_action) => {
-                        let wasm_store = Store::default();
-
-                        let stdout_pipe = Pipe::new();
-                        let stderr_pipe = Pipe::new();
-
-                        // TODO: For some reason a second run does not include any env variables
-                        let mut wasi_env = WasiState::new(&call_action.function_name)
-                            .env("ORACLE_CONTRACT_ID", &self.node_config.contract_account_id)
-                            .env(
-                                "ED25519_PUBLIC_KEY",
-                                hex::encode(self.node_config.keypair_ed25519.public_key.to_bytes()),
+
+                    promise_queue_mut.queue[index].status = PromiseStatus::Pending;
+
+                    if deadline.is_expired() {
+                        return Err(VmResultStatus::Timeout);
+                    }
+
+                    if !job
+                        .gas_meter
+                        .consume(GasCost::promise_action(&promise_queue.queue[index].action))
+                    {
+                        return Err(VmResultStatus::OutOfGas);
+                    }
+
+                    match &promise_queue.queue[index].action {
+                        action if !program.manifest.allows(action) => {
+                            promise_queue_mut.queue[index].status = PromiseStatus::Rejected(
+                                format!(
+                                    "Method `{action}` is not allowed by the manifest of program `{}`",
+                                    program.name
+                                )
+                                .into_bytes(),
                             )
-                            .env(
-                                "BN254_PUBLIC_KEY",
-                                hex::encode(&self.node_config.keypair_bn254.public_key.to_uncompressed().unwrap()),
+                        }
+                        PromiseAction::DatabaseSet(_)
+                        | PromiseAction::TriggerEvent(_)
+                        | PromiseAction::P2PBroadcast(_)
+                            if job.dry_run.is_some() =>
+                        {
+                            // A dry run leaves the node as it was
+                            promise_queue_mut.queue[index].status = PromiseStatus::Fulfilled(None);
+                        }
+                        // TODO need an ok_or type situation here. if its ok continue otherwise reject
+                        // promise? or maybe it should return a VMResult. Might hold off on this till the VMResult
+                        // changes.
+                        PromiseAction::CallSelf(call_action) => {
+                            let wasm_store = Store::default();
+
+                            let span = info_span!(
+                                "guest",
+                                program = program.name,
+                                event_id = job.execution_context.event_id.as_deref().unwrap_or_default(),
+                                promise_index = index,
+                                function = call_action.function_name.as_str(),
+                            );
+
+                            // stdout and stderr share a single limit across the whole execution
+                            let log_bytes_used: usize =
+                                job.stdout.iter().chain(job.stderr.iter()).map(String::len).sum();
+                            let log_bytes_remaining = self.node_config.max_log_bytes.saturating_sub(log_bytes_used);
+                            let guest_stdout = GuestOutput::new(GuestStream::Stdout, span.clone(), log_bytes_remaining);
+                            let guest_stderr = GuestOutput::new(GuestStream::Stderr, span.clone(), log_bytes_remaining);
+
+                            // Programs should read the node and the event from the execution context,
+                            // deterministic executions do not see most of the env variables
+                            let mut wasi_env = WasiState::new(&call_action.function_name)
+                                .envs(self.execution_env(job.deterministic_random.is_some()))
+                                .args(call_action.args.clone())
+                                .stdout(Box::new(guest_stdout.clone()))
+                                .stderr(Box::new(guest_stderr.clone()))
+                                .finalize()
+                                .map_err(|_| VmResultStatus::WasiEnvInitializeFailure)?;
+
+                            let current_promise_queue = Arc::new(Mutex::new(promise_queue_mut.clone()));
+                            let await_action = self.await_action(job.dry_run.as_ref(), &deadline);
+                            let next_queue = Arc::new(Mutex::new(PromiseQueue::new()));
+
+                            let vm_context = VmContext::create_vm_context(
+                                program.memory_adapter.clone(),
+                                job.shared_memory.clone(),
+                                current_promise_queue.clone(),
+                                next_queue.clone(),
+                                program.name.to_string(),
+                                program.manifest.clone(),
+                                call_action.input.clone(),
+                                job.execution_context.clone(),
+                                self.node_config.clone(),
+                                deadline,
+                                job.shared_memory_log.clone(),
+                                job.deterministic_random.clone(),
+                                Some(await_action),
+                            );
+
+                            let imports =
+                                create_wasm_imports(&wasm_store, vm_context.clone(), &mut wasi_env, program.module)
+                                    .map_err(|_| VmResultStatus::FailedToCreateVMImports)?;
+                            let wasmer_instance = instantiate_with_limit_flag(
+                                program.module,
+                                &imports,
+                                &vm_context.memory_limit_exceeded,
                             )
-                            .args(call_action.args.clone())
-                            .stdout(Box::new(stdout_pipe))
-                            .stderr(Box::new(stderr_pipe))
-                            .finalize()
-                            .map_err(|_| VmResultStatus::WasiEnvInitializeFailure)?;
-
-                        let current_promise_queue = Arc::new(Mutex::new(promise_queue_mut.clone()));
-                        let next_queue = Arc::new(Mutex::new(PromiseQueue::new()));
-
-                        let vm_context = VmContext::create_vm_context(
-                            memory_adapter.clone(),
-                            self.shared_memory.clone(),
-                            current_promise_queue,
-                            next_queue.clone(),
-                            self.node_config.clone(),
-                        );
-
-                        let imports = create_wasm_imports(&wasm_store, vm_context.clone(), &mut wasi_env, wasm_module)
-                            .map_err(|_| VmResultStatus::FailedToCreateVMImports)?;
-                        let wasmer_instance = Instance::new(wasm_module, &imports)
                             .map_err(|_| VmResultStatus::FailedToCreateWasmerInstance)?;
-                        let main_func = wasmer_instance
-                            .exports
-                            .get_function(&call_action.function_name)
-                            .map_err(|_| VmResultStatus::FailedToGetWASMFn)?;
-                        let runtime_result = main_func.call(&[]);
-
-                        let mut wasi_state = wasi_env.state();
-                        let wasi_stdout = wasi_state
-                            .fs
-                            .stdout_mut()
-                            .map_err(|_| VmResultStatus::FailedToGetWASMStdout)?
-                            .as_mut()
-                            .unwrap();
-                        let mut stdout_buffer = String::new();
-                        wasi_stdout
-                            .read_to_string(&mut stdout_buffer)
-                            .map_err(|_| VmResultStatus::FailedToConvertVMPipeToString)?;
-                        if !stdout_buffer.is_empty() {
-                            stdout.push(stdout_buffer);
-                        }
+                            let main_func = wasmer_instance
+                                .exports
+                                .get_function(&call_action.function_name)
+                                .map_err(|_| VmResultStatus::FailedToGetWASMFn)?;
+
+                            let gas_before = job.gas_meter.remaining();
+                            set_remaining_points(&wasmer_instance, gas_before);
+                            // Taking the remaining points away makes the metering trap the instance at
+                            // the next basic block, so guest code that never calls the host is stopped
+                            // as well
+                            let watched_instance = wasmer_instance.clone();
+                            let watchdog = deadline.watch(move || set_remaining_points(&watched_instance, 0));
+                            // Logs of the guest are tagged with the fields of the span
+                            let runtime_result = span.in_scope(|| main_func.call(&[]));
+                            drop(watchdog);
+                            // The promises the call awaited are part of the trace, so recordings replay
+                            // them and deterministic executions hash their results
+                            let awaited = current_promise_queue
+                                .lock()
+                                .queue
+                                .split_off(promise_queue_mut.queue.len());
+                            if !awaited.is_empty() {
+                                job.promise_queue_trace.push(PromiseQueue { queue: awaited });
+                            }
+                            let out_of_gas = match get_remaining_points(&wasmer_instance) {
+                                MeteringPoints::Remaining(remaining) => !job.gas_meter.consume(gas_before - remaining),
+                                MeteringPoints::Exhausted => {
+                                    job.gas_meter.exhaust();
+                                    true
+                                }
+                            };
+
+                            let stdout_buffer = guest_stdout.finish(log_bytes_remaining)?;
+                            let log_bytes_remaining = log_bytes_remaining - stdout_buffer.len();
+                            if !stdout_buffer.is_empty() {
+                                job.stdout.push(stdout_buffer);
+                            }
+
+                            let stderr_buffer = guest_stderr.finish(log_bytes_remaining)?;
+                            if !stderr_buffer.is_empty() {
+                                job.stderr.push(stderr_buffer);
+                            }
 
-                        let wasi_stderr = wasi_state
-                            .fs
-                            .stderr_mut()
-                            .map_err(|_| VmResultStatus::FailedToGetWASMStderr)?
-                            .as_mut()
-                            .unwrap();
-                        let mut stderr_buffer = String::new();
-                        wasi_stderr
-                            .read_to_string(&mut stderr_buffer)
-                            .map_err(|_| VmResultStatus::FailedToGetWASMStderr)?;
-                        if !stderr_buffer.is_empty() {
-                            stderr.push(stderr_buffer);
+                            if deadline.is_expired() {
+                                return Err(VmResultStatus::Timeout);
+                            }
+
+                            if out_of_gas {
+                                return Err(VmResultStatus::OutOfGas);
+                            }
+
+                            if let Err(err) = runtime_result {
+                                if let Some(status) = vm_context.limit_exceeded.lock().take() {
+                                    return Err(status);
+                                }
+
+                                if vm_context.memory_limit_exceeded.is_exceeded() {
+                                    return Err(VmResultStatus::MemoryLimitExceeded);
+                                }
+
+                                info!("WASM Error output: {:?}", &job.stderr);
+                                return VmResultStatus::ExecutionError(err.to_string()).into();
+                            }
+
+                            let execution_result = vm_context.result.lock();
+                            next_promise_queue = next_queue.lock().clone();
+                            promise_queue_mut.queue[index].status =
+                                PromiseStatus::Fulfilled(Some(execution_result.clone()));
                         }
 
-                        if let Err(err) = runtime_result {
-                            info!("WASM Error output: {:?}", &stderr);
-                            return VmResultStatus::ExecutionError(err.to_string()).into();
+                        PromiseAction::CallProgram(call_program_action) => {
+                            let callee = self.programs.read().get(&call_program_action.program_name).cloned();
+                            let callee = match callee {
+                                Some(_) if program.call_depth >= MAX_CALL_DEPTH => {
+                                    promise_queue_mut.queue[index].status = PromiseStatus::Rejected(
+                                        format!("Programs can not be called more than {MAX_CALL_DEPTH} levels deep")
+                                            .into_bytes(),
+                                    );
+                                    continue;
+                                }
+                                // Calling a program the node granted everything would grant the caller
+                                // everything as well
+                                Some(callee)
+                                    if callee.manifest.is_unrestricted() && !program.manifest.is_unrestricted() =>
+                                {
+                                    promise_queue_mut.queue[index].status = PromiseStatus::Rejected(
+                                        format!("Program `{}` can only be called by the node", callee.name)
+                                            .into_bytes(),
+                                    );
+                                    continue;
+                                }
+                                Some(callee) => callee,
+                                None => {
+                                    promise_queue_mut.queue[index].status = PromiseStatus::Rejected(
+                                        format!("Program `{}` is not registered", call_program_action.program_name)
+                                            .into_bytes(),
+                                    );
+                                    continue;
+                                }
+                            };
+
+                            let mut program_queue = PromiseQueue::new();
+                            program_queue.add_promise(Promise {
+                                action:       PromiseAction::CallSelf(CallSelfAction {
+                                    function_name: call_program_action.function_name.clone(),
+                                    args:          call_program_action.args.clone(),
+                                    input:         call_program_action.input.clone(),
+                                }),
+                                status:       PromiseStatus::Unfulfilled,
+                                dependencies: vec![],
+                            });
+
+                            // The program shares the limits, the output and the shared memory
+                            // transaction of the job, but has its own memory and manifest. Its queues
+                            // are part of the trace so recordings can replay them. Its shared memory
+                            // writes count against its own quota and are rolled back when it fails.
+                            let callee_program = QueueProgram {
+                                module:         &callee.module,
+                                name:           &callee.name,
+                                manifest:       callee.manifest.clone(),
+                                call_depth:     program.call_depth + 1,
+                                memory_adapter: Arc::new(Mutex::new(InMemory::default())),
+                            };
+                            let savepoint = job.shared_memory.lock().savepoint(&callee.name);
+                            let exit_info: ExitInfo =
+                                match self.execute_promise_queue(&callee_program, program_queue, job).await {
+                                    Ok(status) => status.into(),
+                                    // The limits of the job are exceeded for the caller as well
+                                    Err(status @ (VmResultStatus::Timeout | VmResultStatus::OutOfGas)) => {
+                                        return Err(status);
+                                    }
+                                    // Anything else only fails the callee, the caller handles the rejection
+                                    Err(status) => status.into(),
+                                };
+
+                            if exit_info.exit_code == 0 {
+                                job.shared_memory.lock().release(savepoint);
+                            } else {
+                                job.shared_memory.lock().rollback_to(savepoint);
+                            }
+
+                            promise_queue_mut.queue[index].status = if exit_info.exit_code == 0 {
+                                let last_promise_status = job
+                                    .promise_queue_trace
+                                    .last()
+                                    .and_then(|promise_queue| promise_queue.queue.last())
+                                    .map(|promise| promise.status.clone());
+                                match last_promise_status {
+                                    Some(status @ (PromiseStatus::Fulfilled(_) | PromiseStatus::Rejected(_))) => status,
+                                    _ => PromiseStatus::Fulfilled(None),
+                                }
+                            } else {
+                                PromiseStatus::Rejected(exit_info.exit_message.into_bytes())
+                            };
                         }
 
-                        let execution_result = vm_context.result.lock();
-                        next_promise_queue = next_queue.lock().clone();
-                        promise_queue_mut.queue[index].status =
-                            PromiseStatus::Fulfilled(Some(execution_result.clone()));
-                    }
+                        // Just an example, delete this later
+                        PromiseAction::DatabaseSet(db_action) => {
+                            let res = String::from_bytes(&db_action.value);
+                            promise_queue_mut.queue[index].status = if res.is_err() {
+                                res.into()
+                            } else {
+                                deadline
+                                    .run(self.host_adapter.db_set(&db_action.key, &res.unwrap()))
+                                    .await
+                                    .ok_or(VmResultStatus::Timeout)?
+                                    .into()
+                            };
+                        }
 
-                    // Just an example, delete this later
-                    PromiseAction::DatabaseSet(db_action) => {
-                        let res = String::from_bytes(&db_action.value);
-                        promise_queue_mut.queue[index].status = if res.is_err() {
-                            res.into()
-                        } else {
-                            self.host_adapter.db_set(&db_action.key, &res.unwrap()).await.into()
-                        };
+                        action @ (PromiseAction::DatabaseGet(_)
+                        | PromiseAction::Http(_)
+                        | PromiseAction::ChainView(_)) => {
+                            let (resolve, abort_handle) = abortable(resolve_independent_action(
+                                self.host_adapter.as_ref(),
+                                action,
+                                &deadline,
+                            ));
+                            abort_handles[index] = Some(abort_handle);
+                            in_flight.push(resolve.map(move |status| (index, status)));
+                        }
+                        PromiseAction::All | PromiseAction::Any => {
+                            promise_queue_mut.queue[index].status = resolve_combinator(&promise_queue_mut.queue, index);
+                            cancel_unused_dependencies(&mut promise_queue_mut.queue, index, &abort_handles);
+                        }
+                        PromiseAction::ChainCall(_) if job.dry_run.is_some() => {
+                            // Captured from the trace once the execution is done
+                            promise_queue_mut.queue[index].status = PromiseStatus::Fulfilled(
+                                job.dry_run.as_ref().map(|dry_run| dry_run.chain_call_result.clone()),
+                            );
+                        }
+                        PromiseAction::ChainCall(chain_call_action) => {
+                            promise_queue_mut.queue[index].status = deadline
+                                .run(self.host_adapter.chain_call(
+                                    chain_call_action.chain,
+                                    &chain_call_action.contract_id,
+                                    &chain_call_action.method_name,
+                                    chain_call_action.args.clone(),
+                                    chain_call_action.deposit,
+                                    self.node_config.clone(),
+                                ))
+                                .await
+                                .ok_or(VmResultStatus::Timeout)?
+                                .into();
+                        }
+                        PromiseAction::TriggerEvent(trigger_event_action) => {
+                            promise_queue_mut.queue[index].status = deadline
+                                .run(self.host_adapter.trigger_event(trigger_event_action.event.clone()))
+                                .await
+                                .ok_or(VmResultStatus::Timeout)?
+                                .into();
+                        }
+                        PromiseAction::P2PBroadcast(p2p_broadcast_action) => {
+                            // TODO we need to figure out how to handle success and errors using channels.
+                            deadline
+                                .run(
+                                    job.p2p_command_sender_channel
+                                        .send(P2PCommand::Broadcast(p2p_broadcast_action.data.clone())),
+                                )
+                                .await
+                                .ok_or(VmResultStatus::Timeout)?
+                                .expect("fixed with above TODO");
+                            promise_queue_mut.queue[index].status = PromiseStatus::Fulfilled(None);
+                        }
                     }
+                }
 
-                    PromiseAction::DatabaseGet(db_action) => {
-                        promise_queue_mut.queue[index].status = self.host_adapter.db_get(&db_action.key).await.into();
-                    }
+                if promise_queue_mut
+                    .queue
+                    .iter()
+                    .all(|promise| promise.status.is_settled())
+                {
+                    break;
+                }
 
-                    PromiseAction::Http(http_action) => {
-                        promise_queue_mut.queue[index].status =
-                            self.host_adapter.http_fetch(&http_action.url).await.into();
-                    }
-                    PromiseAction::ChainView(chain_view_action) => {
-                        promise_queue_mut.queue[index].status = self
-                            .host_adapter
-                            .chain_view(
-                                chain_view_action.chain,
-                                &chain_view_action.contract_id,
-                                &chain_view_action.method_name,
-                                chain_view_action.args.clone(),
-                            )
-                            .await
-                            .into();
-                    }
-                    PromiseAction::ChainCall(chain_call_action) => {
-                        promise_queue_mut.queue[index].status = self
-                            .host_adapter
-                            .chain_call(
-                                chain_call_action.chain,
-                                &chain_call_action.contract_id,
-                                &chain_call_action.method_name,
-                                chain_call_action.args.clone(),
-                                chain_call_action.deposit,
-                                self.node_config.clone(),
-                            )
-                            .await
-                            .into();
-                    }
-                    PromiseAction::TriggerEvent(trigger_event_action) => {
-                        promise_queue_mut.queue[index].status = self
-                            .host_adapter
-                            .trigger_event(trigger_event_action.event.clone())
-                            .await
-                            .into();
-                    }
-                    PromiseAction::P2PBroadcast(p2p_broadcast_action) => {
-                        // TODO we need to figure out how to handle success and errors using channels.
-                        p2p_command_sender_channel
-                            .send(P2PCommand::Broadcast(p2p_broadcast_action.data.clone()))
-                            .await
-                            .expect("fixed with above TODO");
+                match in_flight.next().await {
+                    Some((index, Ok(status))) => promise_queue_mut.queue[index].status = status?,
+                    // Cancelled because a combinator settled, the status is already set
+                    Some((_, Err(Aborted))) => {}
+                    None => {
+                        return Err(VmResultStatus::ExecutionError(
+                            "Promise queue can not make any progress".to_string(),
+                        ))
                     }
                 }
             }
         }
 
-        promise_queue_trace.push(promise_queue_mut.clone());
+        job.promise_queue_trace.push(promise_queue_mut.clone());
 
-        let res = self.execute_promise_queue(
-            wasm_module,
-            memory_adapter,
-            next_promise_queue,
-            stdout,
-            stderr,
-            promise_queue_trace,
-            p2p_command_sender_channel,
-        );
-
-        res.await
+        self.execute_promise_queue(program, next_promise_queue, job).await
     }
 
     async fn start_runtime(
@@ -289,65 +556,535 @@ impl<HA: HostAdapter> RunnableRuntime for Runtime<HA> {
         memory_adapter: Arc<Mutex<InMemory>>,
         p2p_command_sender_channel: Sender<P2PCommand>,
     ) -> VmResult {
+        let recordings_path = match &self.node_config.recordings_path {
+            Some(recordings_path) => recordings_path,
+            None => {
+                let execution_context = self.execution_context(&config);
+                return self
+                    .run(
+                        config,
+                        execution_context,
+                        memory_adapter,
+                        p2p_command_sender_channel,
+                        None,
+                    )
+                    .await
+                    .0;
+            }
+        };
+
+        let (vm_result, recording) = self
+            .record_runtime(config, memory_adapter, p2p_command_sender_channel)
+            .await;
+        match recording.write_to_dir(recordings_path) {
+            Ok(path) => debug!("Recorded runtime job to {}", path.display()),
+            Err(error) => warn!("Failed to write the recording of a runtime job: {error}"),
+        }
+
+        vm_result
+    }
+}
+
+impl<HA: HostAdapter> Runtime<HA> {
+    pub fn from_host_adapter(
+        host_adapter: HA,
+        node_config: NodeConfig,
+        shared_memory: Arc<RwLock<PersistentMemory>>,
+        programs: Arc<RwLock<ProgramRegistry>>,
+    ) -> Self {
+        // The public keys of the node are only part of the execution context, so
+        // deterministic and regular executions get them from the same place
+        let env = vec![(
+            "ORACLE_CONTRACT_ID".to_string(),
+            node_config.contract_account_id.clone(),
+        )];
+
+        Self {
+            programs,
+            env,
+            execution_context: None,
+            host_adapter: Arc::new(host_adapter),
+            node_config,
+            shared_memory,
+        }
+    }
+
+    /// Overrides the env variables the WASM binary is started with, used to
+    /// replay a recording with the env of the node that recorded it
+    pub fn with_env(mut self, env: Vec<(String, String)>) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Overrides the context of every execution, used to replay a recording
+    /// with the context it was recorded with
+    pub fn with_execution_context(mut self, execution_context: ExecutionContext) -> Self {
+        self.execution_context = Some(execution_context);
+        self
+    }
+
+    /// The context of an execution started now. Deterministic executions do
+    /// not see the clock, nor the slot it falls in.
+    pub fn execution_context(&self, config: &VmConfig) -> ExecutionContext {
+        if let Some(execution_context) = &self.execution_context {
+            return execution_context.clone();
+        }
+
+        // The slot is estimated from the clock of the node, so nodes that start the
+        // same job around a slot boundary would disagree on it
+        let now = unix_millis();
+        let slot = self
+            .node_config
+            .genesis_timestamp_ms
+            .filter(|_| !config.deterministic)
+            .map(|genesis_timestamp| {
+                now.saturating_sub(genesis_timestamp) / (self.node_config.slot_duration.as_millis() as u64).max(1)
+            });
+
+        ExecutionContext {
+            event_id: config.event.as_ref().map(|event| event.id.clone()),
+            event_type: config.event.as_ref().map(|event| event.data.event_type().to_string()),
+            contract_id: self.node_config.contract_account_id.clone(),
+            ed25519_public_key: self.node_config.keypair_ed25519.public_key.to_bytes().to_vec(),
+            bn254_public_key: self
+                .node_config
+                .keypair_bn254
+                .public_key
+                .to_uncompressed()
+                .expect("The public key of the node is always valid"),
+            start_timestamp: (!config.deterministic).then_some(now),
+            slot,
+            epoch: slot.map(|slot| slot / NodeConfigInner::SLOTS_PER_EPOCH),
+        }
+    }
+
+    /// The env variables of an execution, deterministic executions only get
+    /// the variables that are the same on every node
+    fn execution_env(&self, deterministic: bool) -> Vec<(String, String)> {
+        self.env
+            .iter()
+            .filter(|(name, _)| !deterministic || DETERMINISTIC_ENV_VARS.contains(&name.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// Runs the job like [RunnableRuntime::start_runtime] and records every
+    /// interaction it had with the host
+    pub async fn record_runtime(
+        &self,
+        config: VmConfig,
+        memory_adapter: Arc<Mutex<InMemory>>,
+        p2p_command_sender_channel: Sender<P2PCommand>,
+    ) -> (VmResult, Recording) {
+        let (program_hash, manifest) = self
+            .programs
+            .read()
+            .get(&config.program_name)
+            .map(|program| (program.hash.clone(), Some(program.manifest.as_ref().clone())))
+            .unwrap_or_default();
+        let shared_memory_log = SharedMemoryLog::default();
+        let execution_context = self.execution_context(&config);
+
+        let (vm_result, promise_queue_trace) = self
+            .run(
+                config.clone(),
+                execution_context.clone(),
+                memory_adapter,
+                p2p_command_sender_channel,
+                Some(shared_memory_log.clone()),
+            )
+            .await;
+
+        let recording = Recording {
+            deterministic: config.deterministic,
+            program_name: config.program_name,
+            program_hash,
+            start_func: config.start_func,
+            args: config.args,
+            input: config.input,
+            gas_limit: config.gas_limit,
+            manifest,
+            env: self.env.clone(),
+            event: config.event,
+            execution_context: Some(execution_context),
+            dry_run: config.dry_run,
+            shared_memory: shared_memory_log.lock().clone(),
+            promise_queue_trace,
+            vm_result: vm_result.clone(),
+        };
+
+        (vm_result, recording)
+    }
+
+    /// Runs the job, returns its result and the trace of all executed promise
+    /// queues
+    async fn run(
+        &self,
+        config: VmConfig,
+        execution_context: ExecutionContext,
+        memory_adapter: Arc<Mutex<InMemory>>,
+        p2p_command_sender_channel: Sender<P2PCommand>,
+        shared_memory_log: Option<SharedMemoryLog>,
+    ) -> (VmResult, Vec<PromiseQueue>) {
         let function_name = config.clone().start_func.unwrap_or_else(|| "_start".to_string());
-        let wasm_module = self.wasm_module.as_ref().unwrap();
+        let program = self.programs.read().get(&config.program_name).cloned();
+        let program = match program {
+            Some(program) if config.deterministic && program.uses_float_ops && self.node_config.reject_float_ops => {
+                return (VmResult::from_status(VmResultStatus::FloatOpsNotAllowed), vec![]);
+            }
+            Some(program) => program,
+            None => {
+                return (
+                    VmResult::from_status(VmResultStatus::ProgramNotFound(config.program_name)),
+                    vec![],
+                );
+            }
+        };
+
+        // Deterministic executions always log the shared memory reads, they are part
+        // of the input hash
+        let execution_inputs = execution_inputs(
+            &program.hash,
+            &config,
+            &self.execution_env(config.deterministic),
+            &execution_context,
+        );
+        let shared_memory_log = shared_memory_log.or_else(|| config.deterministic.then(SharedMemoryLog::default));
+        let deterministic_random = config
+            .deterministic
+            .then(|| Arc::new(Mutex::new(DeterministicRandom::new(&execution_inputs))));
 
-        let mut promise_queue_trace: Vec<PromiseQueue> = Vec::new();
         let mut promise_queue = PromiseQueue::new();
 
         promise_queue.add_promise(Promise {
-            action: PromiseAction::CallSelf(CallSelfAction {
+            action:       PromiseAction::CallSelf(CallSelfAction {
                 function_name,
                 args: config.args,
+                input: config.input,
             }),
-            status: PromiseStatus::Unfulfilled,
+            status:       PromiseStatus::Unfulfilled,
+            dependencies: vec![],
         });
 
-        let mut stdout: Vec<String> = vec![];
-        let mut stderr: Vec<String> = vec![];
+        // The program may be run by its hash, its shared memory namespace and quota
+        // still belong to its name
+        let queue_program = QueueProgram {
+            module: &program.module,
+            name: &program.name,
+            manifest: program.manifest.clone(),
+            call_depth: 0,
+            memory_adapter,
+        };
+        let mut job = JobState {
+            execution_context: Arc::new(execution_context),
+            shared_memory: Arc::new(Mutex::new(match config.dry_run {
+                Some(_) => SharedMemoryTransaction::dry_run(self.shared_memory.clone(), &program.name),
+                None => SharedMemoryTransaction::new(self.shared_memory.clone(), &program.name),
+            })),
+            stdout: vec![],
+            stderr: vec![],
+            promise_queue_trace: vec![],
+            p2p_command_sender_channel,
+            gas_meter: GasMeter::new(config.gas_limit.unwrap_or(VmConfig::DEFAULT_GAS_LIMIT)),
+            deadline: ExecutionDeadline::new(config.timeout.unwrap_or(self.node_config.runtime_job_timeout)),
+            dry_run: config.dry_run.clone(),
+            shared_memory_log: shared_memory_log.clone(),
+            deterministic_random,
+        };
 
         let exit_info: ExitInfo = self
-            .execute_promise_queue(
-                wasm_module,
-                memory_adapter,
-                promise_queue,
-                &mut stdout,
-                &mut stderr,
-                &mut promise_queue_trace,
-                p2p_command_sender_channel,
-            )
+            .execute_promise_queue(&queue_program, promise_queue, &mut job)
             .await
             .into();
+        let JobState {
+            shared_memory,
+            stdout,
+            stderr,
+            promise_queue_trace,
+            gas_meter,
+            ..
+        } = job;
+
+        // The shared memory writes only outlive a job that succeeded and was not
+        // a dry run
+        std::mem::take(&mut *shared_memory.lock()).finish(exit_info.exit_code == 0 && config.dry_run.is_none());
 
         // There is always 1 queue with 1 promise in the trace (due to this func adding
         // the entrypoint). Only if we haven't hit exit codes, since we no longer return
         // early.
-        let result = if !promise_queue_trace.is_empty() {
-            let mut last_queue = promise_queue_trace
-                .pop()
-                .ok_or("Failed to get last promise queue")
-                .unwrap();
-            let last_promise_status = last_queue
-                .queue
-                .pop()
-                .ok_or("Failed to get last promise in promise queue")
-                .unwrap()
-                .status;
-
-            match last_promise_status {
-                PromiseStatus::Fulfilled(Some(data)) => Some(data),
-                PromiseStatus::Rejected(data) => Some(data),
-                _ => None,
-            }
-        } else {
-            None
+        let last_promise_status = promise_queue_trace
+            .last()
+            .and_then(|promise_queue| promise_queue.queue.last())
+            .map(|promise| &promise.status);
+        let result = match last_promise_status {
+            Some(PromiseStatus::Fulfilled(Some(data))) => Some(data.clone()),
+            Some(PromiseStatus::Rejected(data)) => Some(data.clone()),
+            _ => None,
+        };
+
+        let input_hash = match (config.deterministic, shared_memory_log) {
+            (true, Some(shared_memory_log)) => Some(input_hash(
+                &execution_inputs,
+                &promise_queue_trace,
+                &shared_memory_log.lock(),
+            )),
+            _ => None,
         };
 
-        VmResult {
+        let chain_calls = match config.dry_run {
+            Some(_) => self.simulated_chain_calls(&promise_queue_trace),
+            None => vec![],
+        };
+
+        let vm_result = VmResult {
             stdout,
             stderr,
             result,
             exit_info,
+            gas_used: gas_meter.used(),
+            input_hash,
+            chain_calls,
+        };
+
+        (vm_result, promise_queue_trace)
+    }
+
+    /// The `chain_call` promises a dry run fulfilled instead of sending them
+    fn simulated_chain_calls(&self, promise_queue_trace: &[PromiseQueue]) -> Vec<SimulatedChainCall> {
+        promise_queue_trace
+            .iter()
+            .flat_map(|promise_queue| promise_queue.queue.iter())
+            .filter(|promise| matches!(promise.status, PromiseStatus::Fulfilled(_)))
+            .filter_map(|promise| match &promise.action {
+                PromiseAction::ChainCall(chain_call_action) => Some(SimulatedChainCall {
+                    chain:       chain_call_action.chain,
+                    contract_id: chain_call_action.contract_id.clone(),
+                    method_name: chain_call_action.method_name.clone(),
+                    args:        String::from_utf8(chain_call_action.args.clone())
+                        .unwrap_or_else(|_| hex::encode(&chain_call_action.args)),
+                    deposit:     chain_call_action.deposit,
+                    gas:         self.node_config.gas,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Barriers (state changing actions and calls back into the WASM binary)
+    /// keep their place in the queue, they only run once everything queued
+    /// before them is settled
+    fn is_barrier(&self, action: &PromiseAction) -> bool {
+        !(action.is_independent_action() || action.is_combinator())
+    }
+
+    /// Whether the promise at `index` can be started, given the current status
+    /// of the queue
+    fn is_ready(&self, promises: &[Promise], index: usize) -> bool {
+        let promise = &promises[index];
+        let mut dependencies = promise
+            .dependencies
+            .iter()
+            .map(|dependency| &promises[*dependency].status);
+
+        let dependencies_ready = match promise.action {
+            PromiseAction::All => {
+                dependencies.clone().all(PromiseStatus::is_settled)
+                    || dependencies.any(|status| matches!(status, PromiseStatus::Rejected(_)))
+            }
+            PromiseAction::Any => {
+                dependencies.clone().all(PromiseStatus::is_settled)
+                    || dependencies.any(|status| matches!(status, PromiseStatus::Fulfilled(_)))
+            }
+            _ => dependencies.all(PromiseStatus::is_settled),
+        };
+
+        let earlier_ready = if self.is_barrier(&promise.action) {
+            promises[..index].iter().all(|earlier| earlier.status.is_settled())
+        } else {
+            promises[..index]
+                .iter()
+                .filter(|earlier| self.is_barrier(&earlier.action))
+                .all(|earlier| earlier.status.is_settled())
+        };
+
+        dependencies_ready && earlier_ready
+    }
+
+    /// Resolves the promise actions a call awaits, the host futures are driven
+    /// while the WASM binary is blocked in the `promise_await` import
+    fn await_action(&self, dry_run: Option<&DryRun>, deadline: &ExecutionDeadline) -> AwaitAction {
+        let host_adapter = self.host_adapter.clone();
+        let node_config = self.node_config.clone();
+        let dry_run = dry_run.cloned();
+        let deadline = *deadline;
+
+        Arc::new(move |action: &PromiseAction| {
+            wait_for_host(resolve_awaited_action(
+                host_adapter.as_ref(),
+                action,
+                &node_config,
+                dry_run.as_ref(),
+                &deadline,
+            ))
+        })
+    }
+}
+
+/// Resolves an action that does not change any state, so it can run
+/// concurrently with other independent actions
+async fn resolve_independent_action<HA: HostAdapter>(
+    host_adapter: &HA,
+    action: &PromiseAction,
+    deadline: &ExecutionDeadline,
+) -> ExecutionResult<PromiseStatus> {
+    let resolve = async {
+        match action {
+            PromiseAction::DatabaseGet(db_action) => host_adapter.db_get(&db_action.key).await.into(),
+            PromiseAction::Http(http_action) => host_adapter.http_fetch(&http_action.url).await.into(),
+            PromiseAction::ChainView(chain_view_action) => host_adapter
+                .chain_view(
+                    chain_view_action.chain,
+                    &chain_view_action.contract_id,
+                    &chain_view_action.method_name,
+                    chain_view_action.args.clone(),
+                )
+                .await
+                .into(),
+            _ => unreachable!("`{action}` is not an independent action"),
+        }
+    };
+
+    deadline.run(resolve).await.ok_or(VmResultStatus::Timeout)
+}
+
+/// Resolves an action a call awaits, see [PromiseAction::is_awaitable]
+async fn resolve_awaited_action<HA: HostAdapter>(
+    host_adapter: &HA,
+    action: &PromiseAction,
+    node_config: &NodeConfig,
+    dry_run: Option<&DryRun>,
+    deadline: &ExecutionDeadline,
+) -> ExecutionResult<PromiseStatus> {
+    if action.is_independent_action() {
+        return resolve_independent_action(host_adapter, action, deadline).await;
+    }
+
+    let resolve = async {
+        match action {
+            PromiseAction::DatabaseSet(_) | PromiseAction::TriggerEvent(_) if dry_run.is_some() => {
+                PromiseStatus::Fulfilled(None)
+            }
+            PromiseAction::DatabaseSet(db_action) => match String::from_bytes(&db_action.value) {
+                Ok(value) => host_adapter.db_set(&db_action.key, &value).await.into(),
+                error => error.into(),
+            },
+            PromiseAction::ChainCall(_) if dry_run.is_some() => {
+                PromiseStatus::Fulfilled(dry_run.map(|dry_run| dry_run.chain_call_result.clone()))
+            }
+            PromiseAction::ChainCall(chain_call_action) => host_adapter
+                .chain_call(
+                    chain_call_action.chain,
+                    &chain_call_action.contract_id,
+                    &chain_call_action.method_name,
+                    chain_call_action.args.clone(),
+                    chain_call_action.deposit,
+                    node_config.clone(),
+                )
+                .await
+                .into(),
+            PromiseAction::TriggerEvent(trigger_event_action) => host_adapter
+                .trigger_event(trigger_event_action.event.clone())
+                .await
+                .into(),
+            _ => unreachable!("`{action}` can not be awaited"),
+        }
+    };
+
+    deadline.run(resolve).await.ok_or(VmResultStatus::Timeout)
+}
+
+/// Wakes the thread that waits for a host future
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Polls a host future to completion on the calling thread, parking it while
+/// the future is pending. Unlike `futures::executor::block_on` this can be
+/// nested in the executor the WASM binary is executed by, and the thread keeps
+/// the actix system and tokio runtime the host adapter may depend on.
+fn wait_for_host<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    futures::pin_mut!(future);
+
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// Settles an `all` or `any` combinator from the status of its dependencies.
+/// `all` is fulfilled with the borsh encoded statuses of its dependencies, or
+/// rejected with the first rejection. `any` takes the status of the first
+/// fulfilled dependency and is rejected when all of them are rejected.
+fn resolve_combinator(promises: &[Promise], index: usize) -> PromiseStatus {
+    let promise = &promises[index];
+    let statuses: Vec<&PromiseStatus> = promise
+        .dependencies
+        .iter()
+        .map(|dependency| &promises[*dependency].status)
+        .collect();
+
+    match promise.action {
+        PromiseAction::All => {
+            match statuses
+                .iter()
+                .copied()
+                .find(|status| matches!(status, PromiseStatus::Rejected(_)))
+            {
+                Some(rejected) => rejected.clone(),
+                None => {
+                    let results: Vec<PromiseStatus> = statuses.into_iter().cloned().collect();
+                    match results.try_to_vec() {
+                        Ok(results) => PromiseStatus::Fulfilled(Some(results)),
+                        Err(error) => PromiseStatus::Rejected(error.to_string().into_bytes()),
+                    }
+                }
+            }
+        }
+        PromiseAction::Any => {
+            match statuses
+                .iter()
+                .copied()
+                .find(|status| matches!(status, PromiseStatus::Fulfilled(_)))
+            {
+                Some(fulfilled) => fulfilled.clone(),
+                None => PromiseStatus::Rejected("All promises were rejected".into()),
+            }
+        }
+        _ => unreachable!("`{}` is not a combinator", promise.action),
+    }
+}
+
+/// Cancels the dependencies of a settled combinator that are still running,
+/// unless another unsettled promise depends on them as well
+fn cancel_unused_dependencies(promises: &mut [Promise], index: usize, abort_handles: &[Option<AbortHandle>]) {
+    for dependency in promises[index].dependencies.clone() {
+        let still_needed = promises.iter().enumerate().any(|(other, promise)| {
+            other != index && !promise.status.is_settled() && promise.dependencies.contains(&dependency)
+        });
+
+        if let Some(abort_handle) = &abort_handles[dependency] {
+            if !promises[dependency].status.is_settled() && !still_needed {
+                abort_handle.abort();
+                promises[dependency].status = PromiseStatus::Rejected("Promise was cancelled".into());
+            }
         }
     }
 }
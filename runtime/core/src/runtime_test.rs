@@ -1,23 +1,56 @@
-use std::{env, fs, path::PathBuf, sync::Arc};
+use std::{
+    env,
+    fs,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use bn254::{PrivateKey, PublicKey, Signature, ECDSA};
 use parking_lot::{Mutex, RwLock};
-use seda_config::{ChainConfigsInner, NodeConfigInner};
-use seda_crypto::MasterKey;
-use seda_runtime_sdk::p2p::P2PCommand;
+use secp256k1::{Message, Secp256k1, SecretKey};
+use seda_config::{ChainConfigsInner, NodeConfigInner, WasmCompiler};
+use seda_crypto::{MasterKey, Vrf};
+use seda_runtime_sdk::{
+    events::{Event, EventData},
+    p2p::{P2PAuth, P2PCommand},
+    Chain,
+    PromiseStatus,
+};
 use serde_json::json;
 use tokio::sync::mpsc;
 
-use crate::{test::RuntimeTestAdapter, HostAdapter, InMemory, MemoryAdapter, RunnableRuntime, Runtime, VmConfig};
+use crate::{
+    create_metered_store,
+    test::{MockHttp, MockHttpTestAdapter, RuntimeTestAdapter},
+    DryRun,
+    HostAdapter,
+    InMemory,
+    MemoryAdapter,
+    ModuleCache,
+    PersistentMemory,
+    ProgramManifest,
+    ProgramRegistry,
+    Recording,
+    RunnableRuntime,
+    Runtime,
+    RuntimeError,
+    SimulatedChainCall,
+    VmConfig,
+};
 
 const TEST_MASTER_KEY: &str = "07bc2bbe42d68a80146c873963db1ac5801c7bd79221033b4ccc23cb70a09b28";
 
-fn read_wasm_target(file: &str) -> Vec<u8> {
+fn wasm_target_path(file: &str) -> PathBuf {
     let mut path_prefix = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path_prefix.push("../../target/wasm32-wasi/debug");
     path_prefix.push(&format!("{file}.wasm"));
 
-    fs::read(path_prefix).unwrap()
+    path_prefix
+}
+
+fn read_wasm_target(file: &str) -> Vec<u8> {
+    fs::read(wasm_target_path(file)).unwrap()
 }
 
 fn set_env_vars() {
@@ -28,8 +61,14 @@ fn memory_adapter() -> Arc<Mutex<InMemory>> {
     Arc::new(Mutex::new(InMemory::default()))
 }
 
-fn shared_memory() -> Arc<RwLock<InMemory>> {
-    Arc::new(RwLock::new(InMemory::default()))
+fn shared_memory() -> Arc<RwLock<PersistentMemory>> {
+    Arc::new(RwLock::new(PersistentMemory::default()))
+}
+
+fn program_registry() -> Arc<RwLock<ProgramRegistry>> {
+    Arc::new(RwLock::new(
+        ProgramRegistry::new(&NodeConfigInner::test_config(None)).unwrap(),
+    ))
 }
 
 fn master_key() -> MasterKey {
@@ -44,19 +83,29 @@ async fn test_promise_queue_multiple_calls_with_external_traits() {
     let node_config = NodeConfigInner::test_config(Some(master_key()));
     let memory_adapter = memory_adapter();
     let shared_memory = shared_memory();
-    let mut runtime =
-        Runtime::<RuntimeTestAdapter>::new(node_config, ChainConfigsInner::test_config(), shared_memory, false)
-            .await
-            .unwrap();
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory,
+        program_registry(),
+    )
+    .await
+    .unwrap();
 
     runtime.init(wasm_binary).unwrap();
 
     let runtime_execution_result = runtime.start_runtime(
         VmConfig {
-            args:         vec!["hello world".to_string()],
-            program_name: "consensus".to_string(),
-            start_func:   None,
-            debug:        true,
+            args:          vec!["hello world".to_string()],
+            input:         vec![],
+            program_name:  "consensus".to_string(),
+            start_func:    None,
+            debug:         true,
+            gas_limit:     None,
+            timeout:       None,
+            deterministic: false,
+            event:         None,
+            dry_run:       None,
         },
         memory_adapter,
         p2p_command_sender,
@@ -76,10 +125,14 @@ async fn test_bad_wasm_file() {
     set_env_vars();
 
     let node_config = NodeConfigInner::test_config(Some(master_key()));
-    let mut runtime =
-        Runtime::<RuntimeTestAdapter>::new(node_config, ChainConfigsInner::test_config(), shared_memory(), false)
-            .await
-            .unwrap();
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory(),
+        program_registry(),
+    )
+    .await
+    .unwrap();
 
     runtime.init(vec![203]).unwrap();
 }
@@ -92,19 +145,29 @@ async fn test_non_existing_function() {
     let node_config = NodeConfigInner::test_config(Some(master_key()));
     let memory_adapter = memory_adapter();
     let shared_memory = shared_memory();
-    let mut runtime =
-        Runtime::<RuntimeTestAdapter>::new(node_config, ChainConfigsInner::test_config(), shared_memory, false)
-            .await
-            .unwrap();
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory,
+        program_registry(),
+    )
+    .await
+    .unwrap();
     runtime.init(wasm_binary).unwrap();
 
     let runtime_execution_result = runtime
         .start_runtime(
             VmConfig {
-                args:         vec!["hello world".to_string()],
-                program_name: "consensus".to_string(),
-                start_func:   Some("non_existing_function".to_string()),
-                debug:        true,
+                args:          vec!["hello world".to_string()],
+                input:         vec![],
+                program_name:  "consensus".to_string(),
+                start_func:    Some("non_existing_function".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
             },
             memory_adapter,
             p2p_command_sender,
@@ -124,19 +187,29 @@ async fn test_promise_queue_http_fetch() {
     let node_config = NodeConfigInner::test_config(Some(master_key()));
     let memory_adapter = memory_adapter();
     let shared_memory = shared_memory();
-    let mut runtime =
-        Runtime::<RuntimeTestAdapter>::new(node_config, ChainConfigsInner::test_config(), shared_memory, false)
-            .await
-            .unwrap();
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory,
+        program_registry(),
+    )
+    .await
+    .unwrap();
     runtime.init(wasm_binary).unwrap();
 
     let runtime_execution_result = runtime
         .start_runtime(
             VmConfig {
-                args:         vec![fetch_url.clone()],
-                program_name: "consensus".to_string(),
-                start_func:   Some("http_fetch_test".to_string()),
-                debug:        true,
+                args:          vec![fetch_url.clone()],
+                input:         vec![],
+                program_name:  "consensus".to_string(),
+                start_func:    Some("http_fetch_test".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
             },
             memory_adapter,
             p2p_command_sender,
@@ -157,6 +230,58 @@ async fn test_promise_queue_http_fetch() {
     assert_eq!(result, expected_result);
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_swap_http_component() {
+    set_env_vars();
+    let fetch_url = "https://example.com/price".to_string();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let node_config = NodeConfigInner::test_config(Some(master_key()));
+    let mut runtime = Runtime::<MockHttpTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory(),
+        program_registry(),
+    )
+    .await
+    .unwrap();
+    runtime.init(wasm_binary).unwrap();
+
+    let (vm_result, recording) = runtime
+        .record_runtime(
+            VmConfig {
+                args:          vec![fetch_url.clone()],
+                input:         vec![],
+                program_name:  "consensus".to_string(),
+                start_func:    Some("http_fetch_test".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
+            },
+            memory_adapter(),
+            p2p_command_sender,
+        )
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+
+    // Only the HTTP component is mocked, the database is still the test one
+    assert_eq!(*runtime.host_adapter.http.fetched_urls.lock().await, vec![fetch_url]);
+    let fetched = recording
+        .promise_queue_trace
+        .iter()
+        .flat_map(|promise_queue| &promise_queue.queue)
+        .find(|promise| promise.action.to_string() == "http")
+        .map(|promise| &promise.status);
+    assert!(
+        matches!(fetched, Some(PromiseStatus::Fulfilled(Some(body))) if body == MockHttp::BODY.as_bytes()),
+        "{fetched:?}"
+    );
+}
+
 #[allow(clippy::await_holding_lock)]
 #[tokio::test(flavor = "multi_thread")]
 async fn test_memory_adapter() {
@@ -165,20 +290,30 @@ async fn test_memory_adapter() {
     let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
     let memory_adapter = memory_adapter();
     let shared_memory = shared_memory();
-    let mut runtime =
-        Runtime::<RuntimeTestAdapter>::new(node_config, ChainConfigsInner::test_config(), shared_memory, false)
-            .await
-            .unwrap();
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory,
+        program_registry(),
+    )
+    .await
+    .unwrap();
     let wasm_binary = read_wasm_target("promise-wasm-bin");
     runtime.init(wasm_binary).unwrap();
 
     let runtime_execution_result = runtime
         .start_runtime(
             VmConfig {
-                args:         vec!["memory adapter".to_string()],
-                program_name: "consensus".to_string(),
-                start_func:   Some("memory_adapter_test_success".to_string()),
-                debug:        true,
+                args:          vec!["memory adapter".to_string()],
+                input:         vec![],
+                program_name:  "consensus".to_string(),
+                start_func:    Some("memory_adapter_test_success".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
             },
             memory_adapter.clone(),
             p2p_command_sender,
@@ -213,10 +348,14 @@ async fn test_cli_demo_view_another_chain() {
     let node_config = NodeConfigInner::test_config(Some(master_key()));
     let memory_adapter = memory_adapter();
     let shared_memory = shared_memory();
-    let mut runtime =
-        Runtime::<RuntimeTestAdapter>::new(node_config, ChainConfigsInner::test_config(), shared_memory, false)
-            .await
-            .unwrap();
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory,
+        program_registry(),
+    )
+    .await
+    .unwrap();
 
     runtime.init(wasm_binary).unwrap();
     let contract_id = "mc.mennat0.testnet".to_string();
@@ -226,16 +365,22 @@ async fn test_cli_demo_view_another_chain() {
     let runtime_execution_result = runtime
         .start_runtime(
             VmConfig {
-                args:         vec![
+                args:          vec![
                     "view".to_string(),
                     "another".to_string(),
                     contract_id,
                     method_name,
                     args,
                 ],
-                program_name: "consensus".to_string(),
-                start_func:   None,
-                debug:        true,
+                input:         vec![],
+                program_name:  "consensus".to_string(),
+                start_func:    None,
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
             },
             memory_adapter.clone(),
             p2p_command_sender,
@@ -255,39 +400,120 @@ async fn test_limited_runtime() {
     let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
     let wasm_binary = read_wasm_target("promise-wasm-bin");
     let node_config = NodeConfigInner::test_config(Some(master_key()));
-    let memory_adapter = memory_adapter();
-    let shared_memory = shared_memory();
-    let mut runtime =
-        Runtime::<RuntimeTestAdapter>::new(node_config, ChainConfigsInner::test_config(), shared_memory, true)
+    let programs = program_registry();
+    // Without a manifest the program only gets `http` without any domains
+    programs.write().register("limited", &wasm_binary).unwrap();
+    programs
+        .write()
+        .register_with_manifest(
+            "manifest",
+            &wasm_binary,
+            ProgramManifest::from_toml(r#"actions = ["db_set"]"#).unwrap(),
+        )
+        .unwrap();
+    let runtime =
+        Runtime::<RuntimeTestAdapter>::new(node_config, ChainConfigsInner::test_config(), shared_memory(), programs)
             .await
             .unwrap();
 
-    runtime.init(wasm_binary).unwrap();
-
-    let runtime_execution_result = runtime.start_runtime(
-        VmConfig {
-            args:         vec![],
-            program_name: "consensus".to_string(),
-            start_func:   Some("test_limited_runtime".to_string()),
-            debug:        true,
-        },
-        memory_adapter,
-        p2p_command_sender,
-    );
-
-    let vm_result = runtime_execution_result.await;
+    let vm_config = |program_name: &str| VmConfig {
+        args:          vec![],
+        input:         vec![],
+        program_name:  program_name.to_string(),
+        start_func:    Some("test_limited_runtime".to_string()),
+        debug:         true,
+        gas_limit:     None,
+        timeout:       None,
+        deterministic: false,
+        event:         None,
+        dry_run:       None,
+    };
+
+    let vm_result = runtime
+        .start_runtime(vm_config("limited"), memory_adapter(), p2p_command_sender.clone())
+        .await;
     assert_eq!(vm_result.exit_info.exit_code, 0);
 
     assert_eq!(vm_result.stdout.len(), 1);
-    assert!(
-        vm_result
-            .stdout
-            .into_iter()
-            .any(|output| output.contains("not allowed in limited runtime"))
-    );
+    assert!(vm_result
+        .stdout
+        .into_iter()
+        .any(|output| output.contains("not allowed by the manifest of program `limited`")));
 
     let value = runtime.host_adapter.db_get("foo").await.unwrap();
     assert!(value.is_none());
+
+    // The manifest grants the action, so the program panics because its promise
+    // was not rejected
+    let vm_result = runtime
+        .start_runtime(vm_config("manifest"), memory_adapter(), p2p_command_sender)
+        .await;
+    assert_ne!(vm_result.exit_info.exit_code, 0);
+
+    let value = runtime.host_adapter.db_get("foo").await.unwrap();
+    assert_eq!(value, Some("bar".to_string()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_limited_program_can_not_sign() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let node_config = NodeConfigInner::test_config(Some(master_key()));
+    let programs = program_registry();
+    programs
+        .write()
+        .register_with_manifest("limited", &wasm_binary, ProgramManifest::limited())
+        .unwrap();
+    let mut runtime =
+        Runtime::<RuntimeTestAdapter>::new(node_config, ChainConfigsInner::test_config(), shared_memory(), programs)
+            .await
+            .unwrap();
+
+    runtime.init(wasm_binary).unwrap();
+
+    let vm_config = |program_name: &str, host_function: &str| VmConfig {
+        args:          vec![],
+        input:         host_function.as_bytes().to_vec(),
+        program_name:  program_name.to_string(),
+        start_func:    Some("signing_test".to_string()),
+        debug:         true,
+        gas_limit:     None,
+        timeout:       None,
+        deterministic: false,
+        event:         None,
+        dry_run:       None,
+    };
+
+    for host_function in ProgramManifest::GATED_HOST_FUNCTIONS {
+        // The program still links, but the node does not sign anything for it
+        let vm_result = runtime
+            .start_runtime(
+                vm_config("limited", host_function),
+                memory_adapter(),
+                p2p_command_sender.clone(),
+            )
+            .await;
+        assert_ne!(vm_result.exit_info.exit_code, 0, "{host_function}");
+        assert!(
+            vm_result.exit_info.exit_message.contains(&format!(
+                "Host function `{host_function}` is not allowed by the manifest of program `limited`"
+            )),
+            "{}",
+            vm_result.exit_info.exit_message
+        );
+        assert_ne!(vm_result.result, Some(b"signed".to_vec()));
+
+        let vm_result = runtime
+            .start_runtime(
+                vm_config("consensus", host_function),
+                memory_adapter(),
+                p2p_command_sender.clone(),
+            )
+            .await;
+        assert_eq!(vm_result.exit_info.exit_code, 0, "{host_function}");
+        assert_eq!(vm_result.result, Some(b"signed".to_vec()));
+    }
 }
 
 // TODO: test with local deployment or mocked RPC
@@ -336,10 +562,14 @@ async fn test_bn254_verify_valid() {
     let node_config = NodeConfigInner::test_config(Some(master_key()));
     let memory_adapter = memory_adapter();
     let shared_memory = shared_memory();
-    let mut runtime =
-        Runtime::<RuntimeTestAdapter>::new(node_config, ChainConfigsInner::test_config(), shared_memory, false)
-            .await
-            .unwrap();
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory,
+        program_registry(),
+    )
+    .await
+    .unwrap();
     runtime.init(wasm_binary).unwrap();
     let sig = hex::encode(
         Signature::from_compressed(
@@ -354,7 +584,7 @@ async fn test_bn254_verify_valid() {
     let runtime_execution_result = runtime
         .start_runtime(
             VmConfig {
-                args:         vec![
+                args:          vec![
                     // Message ("sample" in ASCII)
                     "73616d706c65".to_string(),
                     // Signature (uncompressed G1 point)
@@ -362,9 +592,15 @@ async fn test_bn254_verify_valid() {
                     // Public Key (uncompressed G2 point)
                     pk,
                 ],
-                program_name: "consensus".to_string(),
-                start_func:   Some("bn254_verify_test".to_string()),
-                debug:        true,
+                input:         vec![],
+                program_name:  "consensus".to_string(),
+                start_func:    Some("bn254_verify_test".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
             },
             memory_adapter,
             p2p_command_sender,
@@ -391,10 +627,14 @@ async fn test_bn254_verify_invalid() {
     let node_config = NodeConfigInner::test_config(Some(master_key()));
     let memory_adapter = memory_adapter();
     let shared_memory = shared_memory();
-    let mut runtime =
-        Runtime::<RuntimeTestAdapter>::new(node_config, ChainConfigsInner::test_config(), shared_memory, false)
-            .await
-            .unwrap();
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory,
+        program_registry(),
+    )
+    .await
+    .unwrap();
     runtime.init(wasm_binary).unwrap();
     let sig = hex::encode(
         Signature::from_compressed(
@@ -408,7 +648,7 @@ async fn test_bn254_verify_invalid() {
     let runtime_execution_result = runtime
         .start_runtime(
             VmConfig {
-                args:         vec![
+                args:          vec![
                     // Message ("sample" in ASCII)
                     "73616d706c65".to_string(),
                     // WRONG Signature (compressed G1 point) -> 1 flipped bit!
@@ -416,9 +656,15 @@ async fn test_bn254_verify_invalid() {
                     // Public Key (compressed G2 point)
                     pk,
                 ],
-                program_name: "consensus".to_string(),
-                start_func:   Some("bn254_verify_test".to_string()),
-                debug:        true,
+                input:         vec![],
+                program_name:  "consensus".to_string(),
+                start_func:    Some("bn254_verify_test".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
             },
             memory_adapter,
             p2p_command_sender,
@@ -445,24 +691,34 @@ async fn test_bn254_signature() {
     let node_config = NodeConfigInner::test_config(Some(master_key()));
     let memory_adapter = memory_adapter();
     let shared_memory = shared_memory();
-    let mut runtime =
-        Runtime::<RuntimeTestAdapter>::new(node_config, ChainConfigsInner::test_config(), shared_memory, false)
-            .await
-            .unwrap();
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory,
+        program_registry(),
+    )
+    .await
+    .unwrap();
     runtime.init(wasm_binary).unwrap();
 
     let runtime_execution_result = runtime
         .start_runtime(
             VmConfig {
-                args:         vec![
+                args:          vec![
                     // Message ("sample" in ASCII)
                     "73616d706c65".to_string(),
                     // Private Key
                     "2009da7287c158b126123c113d1c85241b6e3294dd75c643588630a8bc0f934c".to_string(),
                 ],
-                program_name: "consensus".to_string(),
-                start_func:   Some("bn254_sign_test".to_string()),
-                debug:        true,
+                input:         vec![],
+                program_name:  "consensus".to_string(),
+                start_func:    Some("bn254_sign_test".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
             },
             memory_adapter,
             p2p_command_sender,
@@ -496,19 +752,29 @@ async fn test_error_turns_into_rejection() {
     let node_config = NodeConfigInner::test_config(Some(master_key()));
     let memory_adapter = memory_adapter();
     let shared_memory = shared_memory();
-    let mut runtime =
-        Runtime::<RuntimeTestAdapter>::new(node_config, ChainConfigsInner::test_config(), shared_memory, false)
-            .await
-            .unwrap();
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory,
+        program_registry(),
+    )
+    .await
+    .unwrap();
 
     runtime.init(wasm_binary).unwrap();
 
     let runtime_execution_result = runtime.start_runtime(
         VmConfig {
-            args:         vec![],
-            program_name: "consensus".to_string(),
-            start_func:   Some("test_error_turns_into_rejection".to_string()),
-            debug:        true,
+            args:          vec![],
+            input:         vec![],
+            program_name:  "consensus".to_string(),
+            start_func:    Some("test_error_turns_into_rejection".to_string()),
+            debug:         true,
+            gas_limit:     None,
+            timeout:       None,
+            deterministic: false,
+            event:         None,
+            dry_run:       None,
         },
         memory_adapter,
         p2p_command_sender,
@@ -540,7 +806,7 @@ async fn test_shared_memory() {
         node_config.clone(),
         ChainConfigsInner::test_config(),
         shared_memory.clone(),
-        false,
+        program_registry(),
     )
     .await
     .unwrap();
@@ -549,10 +815,16 @@ async fn test_shared_memory() {
 
     let runtime_execution_result = runtime.start_runtime(
         VmConfig {
-            args:         vec![],
-            program_name: "consensus".to_string(),
-            start_func:   Some("shared_memory_test".to_string()),
-            debug:        true,
+            args:          vec![],
+            input:         vec![],
+            program_name:  "consensus".to_string(),
+            start_func:    Some("shared_memory_test".to_string()),
+            debug:         true,
+            gas_limit:     None,
+            timeout:       None,
+            deterministic: false,
+            event:         None,
+            dry_run:       None,
         },
         memory_adapter(),
         p2p_command_sender,
@@ -561,20 +833,30 @@ async fn test_shared_memory() {
     let vm_result = runtime_execution_result.await;
     assert_eq!(vm_result.exit_info.exit_code, 0);
 
-    let mut runtime =
-        Runtime::<RuntimeTestAdapter>::new(node_config, ChainConfigsInner::test_config(), shared_memory, false)
-            .await
-            .unwrap();
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory,
+        program_registry(),
+    )
+    .await
+    .unwrap();
 
     runtime.init(wasm_binary).unwrap();
 
     let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
     let runtime_execution_result = runtime.start_runtime(
         VmConfig {
-            args:         vec![],
-            program_name: "consensus".to_string(),
-            start_func:   Some("shared_memory_success".to_string()),
-            debug:        true,
+            args:          vec![],
+            input:         vec![],
+            program_name:  "consensus".to_string(),
+            start_func:    Some("shared_memory_success".to_string()),
+            debug:         true,
+            gas_limit:     None,
+            timeout:       None,
+            deterministic: false,
+            event:         None,
+            dry_run:       None,
         },
         memory_adapter(),
         p2p_command_sender,
@@ -583,3 +865,1270 @@ async fn test_shared_memory() {
     let vm_result = runtime_execution_result.await;
     assert_eq!(vm_result.exit_info.exit_code, 0);
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_out_of_gas() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let node_config = NodeConfigInner::test_config(Some(master_key()));
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory(),
+        program_registry(),
+    )
+    .await
+    .unwrap();
+
+    runtime.init(wasm_binary).unwrap();
+
+    let vm_result = runtime
+        .start_runtime(
+            VmConfig {
+                args:          vec![],
+                input:         vec![],
+                program_name:  "consensus".to_string(),
+                start_func:    Some("infinite_loop_test".to_string()),
+                debug:         true,
+                gas_limit:     Some(1_000_000),
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
+            },
+            memory_adapter(),
+            p2p_command_sender,
+        )
+        .await;
+
+    assert_eq!(vm_result.exit_info.exit_code, 9);
+    assert_eq!(vm_result.gas_used, 1_000_000);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_timeout() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let node_config = NodeConfigInner::test_config(Some(master_key()));
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory(),
+        program_registry(),
+    )
+    .await
+    .unwrap();
+
+    runtime.init(wasm_binary).unwrap();
+
+    // The guest loops without calling the host, the default gas limit takes far
+    // longer than the timeout to run out
+    let started = Instant::now();
+    let vm_result = runtime
+        .start_runtime(
+            VmConfig {
+                args:          vec![],
+                input:         vec![],
+                program_name:  "consensus".to_string(),
+                start_func:    Some("infinite_loop_test".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       Some(Duration::from_millis(500)),
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
+            },
+            memory_adapter(),
+            p2p_command_sender,
+        )
+        .await;
+
+    assert_eq!(vm_result.exit_info.exit_code, 10);
+    assert!(started.elapsed() >= Duration::from_millis(500));
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_memory_limit_exceeded() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let node_config = NodeConfigInner::test_config(Some(master_key()));
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory(),
+        program_registry(),
+    )
+    .await
+    .unwrap();
+
+    runtime.init(wasm_binary).unwrap();
+
+    let vm_result = runtime
+        .start_runtime(
+            VmConfig {
+                args:          vec![],
+                input:         vec![],
+                program_name:  "consensus".to_string(),
+                start_func:    Some("memory_hog_test".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
+            },
+            memory_adapter(),
+            p2p_command_sender,
+        )
+        .await;
+
+    assert_eq!(vm_result.exit_info.exit_code, 11);
+}
+
+#[test]
+fn test_module_cache() {
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let cache_path = env::temp_dir().join(format!("seda_wasm_cache_test_{}", std::process::id()));
+    let cache = ModuleCache::new(&cache_path, WasmCompiler::default());
+    let store = create_metered_store(WasmCompiler::default(), NodeConfigInner::MAX_MEMORY_PAGES).unwrap();
+
+    let compiled = cache
+        .load_or_compile(&store, &wasm_binary, NodeConfigInner::MAX_MEMORY_PAGES)
+        .unwrap();
+    let artifacts: Vec<PathBuf> = fs::read_dir(&cache_path)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(artifacts.len(), 1);
+
+    let cached = cache
+        .load_or_compile(&store, &wasm_binary, NodeConfigInner::MAX_MEMORY_PAGES)
+        .unwrap();
+    assert_eq!(
+        compiled
+            .exports()
+            .map(|export| export.name().to_string())
+            .collect::<Vec<_>>(),
+        cached
+            .exports()
+            .map(|export| export.name().to_string())
+            .collect::<Vec<_>>()
+    );
+
+    // A corrupt artifact is replaced by a freshly compiled one
+    fs::write(&artifacts[0], b"corrupt").unwrap();
+    cache
+        .load_or_compile(&store, &wasm_binary, NodeConfigInner::MAX_MEMORY_PAGES)
+        .unwrap();
+    assert_ne!(fs::read(&artifacts[0]).unwrap(), b"corrupt");
+
+    // A precompiled artifact is the one the cache loads
+    let (_, precompiled_path) = cache
+        .precompile(&store, &wasm_binary, NodeConfigInner::MAX_MEMORY_PAGES, |_| Ok(()))
+        .unwrap();
+    assert_eq!(precompiled_path, artifacts[0]);
+
+    // A module that fails validation is not cached
+    fs::remove_file(&artifacts[0]).unwrap();
+    assert!(cache
+        .precompile(&store, &wasm_binary, NodeConfigInner::MAX_MEMORY_PAGES, |_| {
+            Err("invalid".into())
+        })
+        .is_err());
+    assert!(!artifacts[0].exists());
+
+    fs::remove_dir_all(cache_path).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_program_registry() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let node_config = NodeConfigInner::test_config(Some(master_key()));
+    let programs = program_registry();
+    let hash = programs.write().register("promise", &wasm_binary).unwrap();
+
+    // Registered programs can not be taken over, by name or by hash
+    assert!(programs.read().check_new_name("new").is_ok());
+    for name in ["", "new/program", "consensus", "promise", &hash] {
+        assert!(programs.read().check_new_name(name).is_err(), "{name}");
+    }
+
+    let runtime =
+        Runtime::<RuntimeTestAdapter>::new(node_config, ChainConfigsInner::test_config(), shared_memory(), programs)
+            .await
+            .unwrap();
+
+    let vm_config = |program_name: &str| VmConfig {
+        args:          vec![],
+        input:         vec![],
+        program_name:  program_name.to_string(),
+        start_func:    Some("memory_adapter_test_success".to_string()),
+        debug:         true,
+        gas_limit:     None,
+        timeout:       None,
+        deterministic: false,
+        event:         None,
+        dry_run:       None,
+    };
+
+    let vm_result = runtime
+        .start_runtime(vm_config("promise"), memory_adapter(), p2p_command_sender.clone())
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+
+    let vm_result = runtime
+        .start_runtime(vm_config(&hash), memory_adapter(), p2p_command_sender.clone())
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+
+    let vm_result = runtime
+        .start_runtime(vm_config("unknown"), memory_adapter(), p2p_command_sender)
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 14);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_concurrent_promises() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let node_config = NodeConfigInner::test_config(Some(master_key()));
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory(),
+        program_registry(),
+    )
+    .await
+    .unwrap();
+
+    runtime.init(wasm_binary).unwrap();
+
+    let vm_result = runtime
+        .start_runtime(
+            VmConfig {
+                args:          vec![],
+                input:         vec![],
+                program_name:  "consensus".to_string(),
+                start_func:    Some("concurrent_db_get_test".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
+            },
+            memory_adapter(),
+            p2p_command_sender,
+        )
+        .await;
+
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+
+    // The concurrently resolved results are still in the order of the queue
+    let db_result = runtime.host_adapter.db_get("concurrent_db_get_result").await.unwrap();
+    assert_eq!(db_result, Some("a,b,none".to_string()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_promise_combinators() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let node_config = NodeConfigInner::test_config(Some(master_key()));
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory(),
+        program_registry(),
+    )
+    .await
+    .unwrap();
+
+    runtime.init(wasm_binary).unwrap();
+
+    let vm_result = runtime
+        .start_runtime(
+            VmConfig {
+                args:          vec![],
+                input:         vec![],
+                program_name:  "consensus".to_string(),
+                start_func:    Some("promise_combinators_test".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
+            },
+            memory_adapter(),
+            p2p_command_sender,
+        )
+        .await;
+
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+
+    // `any` ignores the rejected fetch, `all` is rejected by it
+    let db_result = runtime.host_adapter.db_get("promise_combinators_result").await.unwrap();
+    assert_eq!(db_result, Some("fulfilled,rejected,2".to_string()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_record_and_replay() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let node_config = NodeConfigInner::test_config(Some(master_key()));
+    let programs = program_registry();
+    let shared_memory = shared_memory();
+    shared_memory.write().put("recording_seed", "seed");
+
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config.clone(),
+        ChainConfigsInner::test_config(),
+        shared_memory,
+        programs.clone(),
+    )
+    .await
+    .unwrap();
+
+    runtime.init(wasm_binary).unwrap();
+    runtime.host_adapter.db_set("recording_key", "recorded").await.unwrap();
+
+    let (vm_result, recording) = runtime
+        .record_runtime(
+            VmConfig {
+                args:          vec![],
+                input:         vec![],
+                program_name:  "consensus".to_string(),
+                start_func:    Some("recording_test".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
+            },
+            memory_adapter(),
+            p2p_command_sender,
+        )
+        .await;
+
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    assert_eq!(vm_result.result, Some("recorded".as_bytes().to_vec()));
+
+    let recordings_path = env::temp_dir().join("seda_recordings");
+    let recording_path = recording.write_to_dir(&recordings_path).unwrap();
+    let recording = Recording::read_from_path(&recording_path).unwrap();
+    fs::remove_file(recording_path).unwrap();
+
+    // The replay does not touch the database, so changing it has no effect
+    runtime.host_adapter.db_set("recording_key", "changed").await.unwrap();
+    let replay = crate::replay(
+        &recording,
+        node_config.clone(),
+        ChainConfigsInner::test_config(),
+        programs.clone(),
+    )
+    .await
+    .unwrap();
+    assert!(replay.differences.is_empty(), "{:?}", replay.differences);
+    assert_eq!(replay.recording.vm_result.result, Some("recorded".as_bytes().to_vec()));
+
+    // A recording of a different database value shows up as a difference
+    let mut tampered = recording.clone();
+    tampered.promise_queue_trace[1].queue[0].status = PromiseStatus::Fulfilled(Some("tampered".as_bytes().to_vec()));
+    let replay = crate::replay(&tampered, node_config, ChainConfigsInner::test_config(), programs)
+        .await
+        .unwrap();
+    assert!(!replay.differences.is_empty());
+    assert_eq!(replay.recording.vm_result.result, Some("tampered".as_bytes().to_vec()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_deterministic_execution() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let mut node_config = NodeConfigInner::test_config(Some(master_key()));
+    Arc::get_mut(&mut node_config).unwrap().reject_float_ops = true;
+    let programs = program_registry();
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory(),
+        programs.clone(),
+    )
+    .await
+    .unwrap();
+
+    runtime.init(wasm_binary).unwrap();
+
+    let vm_config = |deterministic: bool| VmConfig {
+        args: vec![],
+        input: vec![],
+        program_name: "consensus".to_string(),
+        start_func: Some("deterministic_test".to_string()),
+        debug: true,
+        gas_limit: None,
+        timeout: None,
+        deterministic,
+        event: None,
+        dry_run: None,
+    };
+
+    // The test binary uses floats, which the node is configured to reject
+    assert!(programs.read().get("consensus").unwrap().uses_float_ops);
+    let vm_result = runtime
+        .start_runtime(vm_config(true), memory_adapter(), p2p_command_sender.clone())
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 15);
+
+    Arc::get_mut(&mut runtime.node_config).unwrap().reject_float_ops = false;
+    let first = runtime
+        .start_runtime(vm_config(true), memory_adapter(), p2p_command_sender.clone())
+        .await;
+    let second = runtime
+        .start_runtime(vm_config(true), memory_adapter(), p2p_command_sender.clone())
+        .await;
+    assert_eq!(first.exit_info.exit_code, 0);
+    assert_eq!(first.result, second.result);
+    assert!(first.input_hash.is_some());
+    assert_eq!(first.input_hash, second.input_hash);
+
+    // The clock is stubbed and the public keys of the node are not in the env
+    let result = String::from_utf8(first.result.unwrap()).unwrap();
+    assert!(result.starts_with("0,"), "{result}");
+    assert!(result.ends_with(",false"), "{result}");
+
+    let vm_result = runtime
+        .start_runtime(vm_config(false), memory_adapter(), p2p_command_sender)
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    assert_eq!(vm_result.input_hash, None);
+    // Regular executions read the public keys from the execution context too
+    let result = String::from_utf8(vm_result.result.unwrap()).unwrap();
+    assert!(result.ends_with(",false"), "{result}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_execution_input() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let node_config = NodeConfigInner::test_config(Some(master_key()));
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory(),
+        program_registry(),
+    )
+    .await
+    .unwrap();
+
+    runtime.init(wasm_binary).unwrap();
+
+    // Not valid UTF-8, so it could not be passed as an arg
+    let vm_result = runtime
+        .start_runtime(
+            VmConfig {
+                args:          vec![],
+                input:         vec![0, 159, 146, 150],
+                program_name:  "consensus".to_string(),
+                start_func:    Some("execution_input_test".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
+            },
+            memory_adapter(),
+            p2p_command_sender,
+        )
+        .await;
+
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    assert_eq!(vm_result.result, Some(vec![0, 159, 146, 150, 255]));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_shared_memory_management() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let mut node_config = NodeConfigInner::test_config(Some(master_key()));
+    Arc::get_mut(&mut node_config).unwrap().max_shared_memory_bytes = 100;
+    let shared_memory = shared_memory();
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory.clone(),
+        program_registry(),
+    )
+    .await
+    .unwrap();
+
+    runtime.init(wasm_binary).unwrap();
+
+    let vm_config = |start_func: &str| VmConfig {
+        args:          vec![],
+        input:         vec![],
+        program_name:  "consensus".to_string(),
+        start_func:    Some(start_func.to_string()),
+        debug:         true,
+        gas_limit:     None,
+        timeout:       None,
+        deterministic: false,
+        event:         None,
+        dry_run:       None,
+    };
+
+    let vm_result = runtime
+        .start_runtime(
+            vm_config("shared_memory_management_test"),
+            memory_adapter(),
+            p2p_command_sender.clone(),
+        )
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    assert_eq!(vm_result.result, Some("true,management_a".as_bytes().to_vec()));
+    assert!(shared_memory.read().usage("consensus") > 0);
+
+    let vm_result = runtime
+        .start_runtime(
+            vm_config("shared_memory_quota_test"),
+            memory_adapter(),
+            p2p_command_sender,
+        )
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 16);
+    assert!(!shared_memory.read().contains_key("quota"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_shared_memory_namespace_by_hash() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let shared_memory = shared_memory();
+    let programs = program_registry();
+    let hash = programs.write().register("owned", &wasm_binary).unwrap();
+    let runtime = Runtime::<RuntimeTestAdapter>::new(
+        NodeConfigInner::test_config(Some(master_key())),
+        ChainConfigsInner::test_config(),
+        shared_memory.clone(),
+        programs,
+    )
+    .await
+    .unwrap();
+
+    let vm_config = |key: String| VmConfig {
+        args:          vec![key],
+        input:         vec![],
+        program_name:  hash.clone(),
+        start_func:    Some("shared_memory_set_test".to_string()),
+        debug:         true,
+        gas_limit:     None,
+        timeout:       None,
+        deterministic: false,
+        event:         None,
+        dry_run:       None,
+    };
+
+    // Run by its hash the program still only has its own namespace and quota
+    let vm_result = runtime
+        .start_runtime(
+            vm_config("owned/key".to_string()),
+            memory_adapter(),
+            p2p_command_sender.clone(),
+        )
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    assert!(shared_memory.read().contains_key("owned/key"));
+    assert!(shared_memory.read().usage("owned") > 0);
+    assert_eq!(shared_memory.read().usage(&hash), 0);
+
+    let vm_result = runtime
+        .start_runtime(vm_config(format!("{hash}/key")), memory_adapter(), p2p_command_sender)
+        .await;
+    assert_ne!(vm_result.exit_info.exit_code, 0);
+    assert!(!shared_memory.read().contains_key(&format!("{hash}/key")));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_shared_memory_rollback() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let node_config = NodeConfigInner::test_config(Some(master_key()));
+    let shared_memory = shared_memory();
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory.clone(),
+        program_registry(),
+    )
+    .await
+    .unwrap();
+
+    runtime.init(wasm_binary).unwrap();
+
+    let vm_config = |start_func: &str| VmConfig {
+        args:          vec![],
+        input:         vec![],
+        program_name:  "consensus".to_string(),
+        start_func:    Some(start_func.to_string()),
+        debug:         true,
+        gas_limit:     None,
+        timeout:       None,
+        deterministic: false,
+        event:         None,
+        dry_run:       None,
+    };
+
+    // The write is discarded because a later promise of the job failed
+    let vm_result = runtime
+        .start_runtime(
+            vm_config("shared_memory_rollback_test"),
+            memory_adapter(),
+            p2p_command_sender.clone(),
+        )
+        .await;
+    assert_ne!(vm_result.exit_info.exit_code, 0);
+    assert!(!shared_memory.read().contains_key("rollback"));
+
+    let vm_result = runtime
+        .start_runtime(
+            vm_config("shared_memory_commit_test"),
+            memory_adapter(),
+            p2p_command_sender.clone(),
+        )
+        .await;
+    assert_ne!(vm_result.exit_info.exit_code, 0);
+    assert!(shared_memory.read().contains_key("commit_a"));
+    assert!(!shared_memory.read().contains_key("commit_b"));
+
+    // An explicit transaction that is never committed is discarded, even when
+    // the job succeeds
+    let vm_result = runtime
+        .start_runtime(
+            vm_config("shared_memory_begin_test"),
+            memory_adapter(),
+            p2p_command_sender,
+        )
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    assert!(shared_memory.read().contains_key("begin_a"));
+    assert!(!shared_memory.read().contains_key("begin_b"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_crypto_host_functions() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let node_config = NodeConfigInner::test_config(Some(master_key()));
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config.clone(),
+        ChainConfigsInner::test_config(),
+        shared_memory(),
+        program_registry(),
+    )
+    .await
+    .unwrap();
+
+    runtime.init(wasm_binary).unwrap();
+
+    let vm_config = |start_func: &str, input: Vec<u8>| VmConfig {
+        args: vec![],
+        input,
+        program_name: "consensus".to_string(),
+        start_func: Some(start_func.to_string()),
+        debug: true,
+        gas_limit: None,
+        timeout: None,
+        deterministic: false,
+        event: None,
+        dry_run: None,
+    };
+
+    let vm_result = runtime
+        .start_runtime(
+            vm_config("hash_test", vec![]),
+            memory_adapter(),
+            p2p_command_sender.clone(),
+        )
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    let digests = String::from_utf8(vm_result.result.unwrap()).unwrap();
+    assert_eq!(
+        digests.split(',').collect::<Vec<_>>(),
+        vec![
+            "a2821a22fdd44495155b3f4ba9dfa376998c81521815d24c02d210ba664b7030",
+            "69e0d869b6045ce832354a531f435250c632e3bce43436089cf8fde4aaa3e223",
+            "e8945bebac9106f89e1959c5a4a3bfd68f43b1d3108e69b9b2eca21006bee8ad",
+        ]
+    );
+
+    let vm_result = runtime
+        .start_runtime(
+            vm_config("ed25519_test", b"consensus".to_vec()),
+            memory_adapter(),
+            p2p_command_sender.clone(),
+        )
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    let result = String::from_utf8(vm_result.result.unwrap()).unwrap();
+    let (verified, signature) = result.rsplit_once(',').unwrap();
+    assert_eq!(verified, "true,false,false");
+    let signature = ed25519_dalek::Signature::try_from(hex::decode(signature).unwrap().as_slice()).unwrap();
+    let auth = P2PAuth {
+        program: "consensus".to_string(),
+        payload: b"seda".to_vec(),
+    };
+    assert!(node_config
+        .keypair_ed25519
+        .public_key
+        .verify_strict(&auth.signing_bytes(), &signature)
+        .is_ok());
+    assert!(node_config
+        .keypair_ed25519
+        .public_key
+        .verify_strict(b"seda", &signature)
+        .is_err());
+
+    // An Ethereum style signature, with 27 added to the recovery id
+    let secp = Secp256k1::new();
+    let secret_key = SecretKey::from_slice(&[7; 32]).unwrap();
+    let message_hash = [1; 32];
+    let (recovery_id, signature) = secp
+        .sign_ecdsa_recoverable(&Message::from_slice(&message_hash).unwrap(), &secret_key)
+        .serialize_compact();
+    let mut input = message_hash.to_vec();
+    input.extend(signature);
+    input.push(recovery_id.to_i32() as u8 + 27);
+
+    let vm_result = runtime
+        .start_runtime(
+            vm_config("secp256k1_recover_test", input),
+            memory_adapter(),
+            p2p_command_sender,
+        )
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    let public_key = secp256k1::PublicKey::from_secret_key(&secp, &secret_key).serialize_uncompressed();
+    assert_eq!(vm_result.result, Some(public_key[1..].to_vec()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_vrf() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let node_config = NodeConfigInner::test_config(Some(master_key()));
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config.clone(),
+        ChainConfigsInner::test_config(),
+        shared_memory(),
+        program_registry(),
+    )
+    .await
+    .unwrap();
+
+    runtime.init(wasm_binary).unwrap();
+
+    let vm_config = VmConfig {
+        args:          vec![],
+        input:         b"committee-1".to_vec(),
+        program_name:  "consensus".to_string(),
+        start_func:    Some("vrf_test".to_string()),
+        debug:         true,
+        gas_limit:     None,
+        timeout:       None,
+        deterministic: false,
+        event:         None,
+        dry_run:       None,
+    };
+
+    let vm_result = runtime
+        .start_runtime(vm_config.clone(), memory_adapter(), p2p_command_sender.clone())
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    let result = vm_result.result.unwrap();
+    let (output, proof) = result.split_at(32);
+    assert_eq!(
+        Vrf::verify(&node_config.keypair_bn254.public_key, b"committee-1", proof).unwrap(),
+        output
+    );
+
+    // The same node key and input always give the same output
+    let vm_result = runtime
+        .start_runtime(vm_config, memory_adapter(), p2p_command_sender)
+        .await;
+    assert_eq!(vm_result.result, Some(result));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_execution_context() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let mut node_config = NodeConfigInner::test_config(Some(master_key()));
+    Arc::get_mut(&mut node_config).unwrap().genesis_timestamp_ms = Some(0);
+    let public_key = hex::encode(node_config.keypair_ed25519.public_key.to_bytes());
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory(),
+        program_registry(),
+    )
+    .await
+    .unwrap();
+
+    runtime.init(wasm_binary).unwrap();
+
+    let vm_config = |deterministic: bool| VmConfig {
+        args: vec![],
+        input: vec![],
+        program_name: "consensus".to_string(),
+        start_func: Some("execution_context_test".to_string()),
+        debug: true,
+        gas_limit: None,
+        timeout: None,
+        deterministic,
+        event: Some(Event::new("event-1", EventData::BatchChainTick)),
+        dry_run: None,
+    };
+
+    // The context reaches the function called through the promise queue as well
+    let vm_result = runtime
+        .start_runtime(vm_config(false), memory_adapter(), p2p_command_sender.clone())
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    assert_eq!(
+        String::from_utf8(vm_result.result.unwrap()).unwrap(),
+        format!("event-1,BatchChainTick,{public_key},true,true")
+    );
+
+    // Deterministic executions still know the node, but not the clock or the
+    // slot estimated from it
+    let vm_result = runtime
+        .start_runtime(vm_config(true), memory_adapter(), p2p_command_sender)
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    assert_eq!(
+        String::from_utf8(vm_result.result.unwrap()).unwrap(),
+        format!("event-1,BatchChainTick,{public_key},false,false")
+    );
+}
+
+#[test]
+fn test_program_validation() {
+    let programs = program_registry();
+    programs
+        .write()
+        .register("promise", &read_wasm_target("promise-wasm-bin"))
+        .unwrap();
+
+    // An empty module has no entrypoint and is not built for WASI
+    match programs.write().register("empty", b"\0asm\x01\0\0\0") {
+        Err(RuntimeError::InvalidProgram { name, problems }) => {
+            assert_eq!(name, "empty");
+            assert!(problems.iter().any(|problem| problem.contains("`_start`")));
+            assert!(problems.iter().any(|problem| problem.contains("WASI")));
+        }
+        result => panic!("Expected the program to be invalid, got {result:?}"),
+    }
+    assert!(programs.read().get("empty").is_none());
+
+    // A WASI module that imports `env::ed25519_sign`, which older SDKs did
+    let removed_import = b"\0asm\x01\0\0\0\x01\x08\x02\x60\0\0\x60\x01\x7f\0\x02\x37\x02\x03env\x0ced25519_sign\0\0\
+                           \x16wasi_snapshot_preview1\x09proc_exit\0\x01";
+    match programs.write().register("removed_import", removed_import) {
+        Err(RuntimeError::InvalidProgram { problems, .. }) => {
+            assert!(problems
+                .iter()
+                .any(|problem| problem.contains("replaced by `p2p_auth_sign`")));
+        }
+        result => panic!("Expected the program to be invalid, got {result:?}"),
+    }
+}
+
+#[test]
+fn test_program_recompile() {
+    let programs = program_registry();
+    let consensus = programs
+        .read()
+        .compile_file(
+            "consensus",
+            wasm_target_path("promise-wasm-bin"),
+            Some(ProgramManifest::unrestricted()),
+        )
+        .unwrap();
+    let hash = consensus.hash.clone();
+    programs.write().insert(consensus);
+    programs
+        .write()
+        .register("in_memory", &read_wasm_target("promise-wasm-bin"))
+        .unwrap();
+
+    // The reloaded program keeps the manifest the node granted it
+    let reloaded = programs.read().recompile("consensus").unwrap();
+    assert_eq!(reloaded.hash, hash);
+    assert!(reloaded.manifest.is_unrestricted());
+
+    assert!(programs.read().recompile("in_memory").is_err());
+    assert!(programs.read().recompile("unknown").is_err());
+
+    // A reload does not overwrite a program that changed while it compiled
+    let source = programs.read().source("consensus").unwrap();
+    let compiler = programs.read().compiler();
+    let reloaded = source.compile(&compiler).unwrap();
+    assert!(programs.write().replace(&source, reloaded).is_ok());
+    let reloaded = source.compile(&compiler).unwrap();
+    programs
+        .write()
+        .register_with_manifest(
+            "consensus",
+            &read_wasm_target("promise-wasm-bin"),
+            ProgramManifest::unrestricted(),
+        )
+        .unwrap();
+    assert!(programs.write().replace(&source, reloaded).is_err());
+    assert!(programs.read().get("consensus").unwrap().path.is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_dry_run() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let node_config = NodeConfigInner::test_config(Some(master_key()));
+    let gas = node_config.gas;
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config,
+        ChainConfigsInner::test_config(),
+        shared_memory(),
+        program_registry(),
+    )
+    .await
+    .unwrap();
+
+    runtime.init(wasm_binary).unwrap();
+
+    let vm_result = runtime
+        .start_runtime(
+            VmConfig {
+                args:          vec![],
+                input:         vec![],
+                program_name:  "consensus".to_string(),
+                start_func:    Some("dry_run_test".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       Some(DryRun {
+                    chain_call_result: b"simulated".to_vec(),
+                }),
+            },
+            memory_adapter(),
+            p2p_command_sender,
+        )
+        .await;
+
+    // The call is captured and resolved with the simulated result
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    assert_eq!(vm_result.result, Some(b"simulated".to_vec()));
+    assert_eq!(
+        vm_result.chain_calls,
+        vec![SimulatedChainCall {
+            chain: Chain::Near,
+            contract_id: "mc.seda.near".to_string(),
+            method_name: "post".to_string(),
+            args: r#"{"value":1}"#.to_string(),
+            deposit: 1,
+            gas,
+        }]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_dry_run_has_no_side_effects() {
+    set_env_vars();
+    let (p2p_command_sender, mut p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let shared_memory = shared_memory();
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        NodeConfigInner::test_config(Some(master_key())),
+        ChainConfigsInner::test_config(),
+        shared_memory.clone(),
+        program_registry(),
+    )
+    .await
+    .unwrap();
+
+    runtime.init(wasm_binary).unwrap();
+
+    let vm_result = runtime
+        .start_runtime(
+            VmConfig {
+                args:          vec![],
+                input:         vec![],
+                program_name:  "consensus".to_string(),
+                start_func:    Some("dry_run_side_effects_test".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       Some(DryRun::default()),
+            },
+            memory_adapter(),
+            p2p_command_sender,
+        )
+        .await;
+
+    // The writes, even the committed ones, the database write and the broadcast
+    // never leave the job
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    assert!(!shared_memory.read().contains_key("dry_run"));
+    assert!(!shared_memory.read().contains_key("dry_run_committed"));
+    assert_eq!(runtime.host_adapter.db_get("dry_run").await.unwrap(), None);
+    assert!(p2p_command_receiver.try_recv().is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_call_program() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let programs = program_registry();
+    programs
+        .write()
+        .register_with_manifest(
+            "caller",
+            &wasm_binary,
+            ProgramManifest::from_toml(r#"actions = ["call_program"]"#).unwrap(),
+        )
+        .unwrap();
+    programs.write().register("callee", &wasm_binary).unwrap();
+    let mut runtime = Runtime::<RuntimeTestAdapter>::new(
+        NodeConfigInner::test_config(Some(master_key())),
+        ChainConfigsInner::test_config(),
+        shared_memory(),
+        programs,
+    )
+    .await
+    .unwrap();
+    runtime.init(wasm_binary).unwrap();
+
+    let vm_config = |callee: &str| VmConfig {
+        args:          vec![callee.to_string()],
+        input:         vec![],
+        program_name:  "caller".to_string(),
+        start_func:    Some("call_program_test".to_string()),
+        debug:         true,
+        gas_limit:     None,
+        timeout:       None,
+        deterministic: false,
+        event:         None,
+        dry_run:       None,
+    };
+
+    let vm_result = runtime
+        .start_runtime(vm_config("callee"), memory_adapter(), p2p_command_sender.clone())
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    assert_eq!(vm_result.result, Some(b"hello from the callee".to_vec()));
+
+    // The consensus program could send transactions on behalf of the caller
+    let vm_result = runtime
+        .start_runtime(vm_config("consensus"), memory_adapter(), p2p_command_sender)
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    assert_eq!(
+        String::from_utf8(vm_result.result.unwrap()).unwrap(),
+        "Program `consensus` can only be called by the node"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_call_program_rejects_missing_function() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let programs = program_registry();
+    programs
+        .write()
+        .register_with_manifest(
+            "caller",
+            &wasm_binary,
+            ProgramManifest::from_toml(r#"actions = ["call_program"]"#).unwrap(),
+        )
+        .unwrap();
+    programs.write().register("callee", &wasm_binary).unwrap();
+    let runtime = Runtime::<RuntimeTestAdapter>::new(
+        NodeConfigInner::test_config(Some(master_key())),
+        ChainConfigsInner::test_config(),
+        shared_memory(),
+        programs,
+    )
+    .await
+    .unwrap();
+
+    let vm_result = runtime
+        .start_runtime(
+            VmConfig {
+                args:          vec!["callee".to_string(), "not_exported".to_string()],
+                input:         vec![],
+                program_name:  "caller".to_string(),
+                start_func:    Some("call_program_function_test".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
+            },
+            memory_adapter(),
+            p2p_command_sender,
+        )
+        .await;
+
+    // The caller settles its promise with the failure of the callee
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    assert_eq!(
+        String::from_utf8(vm_result.result.unwrap()).unwrap(),
+        "Error: Failed to find specified function in WASM binary"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_call_program_rolls_back_failed_callee() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let shared_memory = shared_memory();
+    let programs = program_registry();
+    programs
+        .write()
+        .register_with_manifest(
+            "caller",
+            &wasm_binary,
+            ProgramManifest::from_toml(r#"actions = ["call_program"]"#).unwrap(),
+        )
+        .unwrap();
+    programs.write().register("callee", &wasm_binary).unwrap();
+    let runtime = Runtime::<RuntimeTestAdapter>::new(
+        NodeConfigInner::test_config(Some(master_key())),
+        ChainConfigsInner::test_config(),
+        shared_memory.clone(),
+        programs,
+    )
+    .await
+    .unwrap();
+
+    let vm_result = runtime
+        .start_runtime(
+            VmConfig {
+                args:          vec!["callee".to_string()],
+                input:         vec![],
+                program_name:  "caller".to_string(),
+                start_func:    Some("call_program_rollback_test".to_string()),
+                debug:         true,
+                gas_limit:     None,
+                timeout:       None,
+                deterministic: false,
+                event:         None,
+                dry_run:       None,
+            },
+            memory_adapter(),
+            p2p_command_sender,
+        )
+        .await;
+
+    // The caller handles the failure, only the writes of the callee are gone
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    assert!(String::from_utf8(vm_result.result.unwrap())
+        .unwrap()
+        .starts_with("Execution Error"));
+    assert!(shared_memory.read().contains_key("caller/before"));
+    assert!(shared_memory.read().contains_key("caller/after"));
+    assert!(!shared_memory.read().contains_key("callee/partial"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_await_promise() {
+    set_env_vars();
+    let (p2p_command_sender, _p2p_command_receiver) = mpsc::channel::<P2PCommand>(100);
+    let wasm_binary = read_wasm_target("promise-wasm-bin");
+    let node_config = NodeConfigInner::test_config(Some(master_key()));
+    let programs = program_registry();
+    programs
+        .write()
+        .register_with_manifest(
+            "awaiting",
+            &wasm_binary,
+            ProgramManifest::from_toml(r#"actions = ["db_get", "db_set"]"#).unwrap(),
+        )
+        .unwrap();
+    programs.write().register("limited", &wasm_binary).unwrap();
+    let runtime = Runtime::<RuntimeTestAdapter>::new(
+        node_config.clone(),
+        ChainConfigsInner::test_config(),
+        shared_memory(),
+        programs.clone(),
+    )
+    .await
+    .unwrap();
+
+    let vm_config = |program_name: &str| VmConfig {
+        args:          vec![],
+        input:         vec![],
+        program_name:  program_name.to_string(),
+        start_func:    Some("await_test".to_string()),
+        debug:         true,
+        gas_limit:     None,
+        timeout:       None,
+        deterministic: false,
+        event:         None,
+        dry_run:       None,
+    };
+
+    let (vm_result, recording) = runtime
+        .record_runtime(vm_config("awaiting"), memory_adapter(), p2p_command_sender.clone())
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    assert_eq!(vm_result.result, Some(b"awaited".to_vec()));
+
+    // The awaited promises are recorded, so the execution can be replayed
+    let awaited: Vec<String> = recording
+        .promise_queue_trace
+        .iter()
+        .flat_map(|promise_queue| &promise_queue.queue)
+        .filter(|promise| promise.action.is_awaitable())
+        .map(|promise| promise.action.to_string())
+        .collect();
+    assert_eq!(awaited, vec!["db_set", "db_get"]);
+    let replay = crate::replay(&recording, node_config, ChainConfigsInner::test_config(), programs)
+        .await
+        .unwrap();
+    assert!(replay.differences.is_empty(), "{:?}", replay.differences);
+
+    let vm_result = runtime
+        .start_runtime(vm_config("limited"), memory_adapter(), p2p_command_sender)
+        .await;
+    assert_eq!(vm_result.exit_info.exit_code, 0);
+    assert_eq!(
+        String::from_utf8(vm_result.result.unwrap()).unwrap(),
+        "Method `db_get` is not allowed by the manifest of program `limited`"
+    );
+}
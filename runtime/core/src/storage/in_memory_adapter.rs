@@ -1,11 +1,65 @@
-use std::{collections::HashMap, ops::Deref};
+use std::{collections::HashMap, ops::Deref, time::Duration};
 
-use super::{Bytes, FromBytes, MemoryAdapter, ToBytes};
+use super::{unix_millis, Bytes, FromBytes, MemoryAdapter, ToBytes};
 use crate::Result;
 
+#[derive(Debug)]
+pub(crate) struct MemoryEntry {
+    pub(crate) value:      Bytes,
+    /// Unix timestamp in milliseconds from which the entry no longer exists
+    pub(crate) expires_at: Option<u64>,
+}
+
+impl MemoryEntry {
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.map_or(false, |expires_at| expires_at <= now)
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct InMemory {
-    memory: HashMap<String, Bytes>,
+    memory: HashMap<String, MemoryEntry>,
+}
+
+impl InMemory {
+    fn live_entry(&self, key: &str) -> Option<&MemoryEntry> {
+        self.memory.get(key).filter(|entry| !entry.is_expired(unix_millis()))
+    }
+
+    /// Every entry that has not expired yet
+    pub(crate) fn entries(&self) -> impl Iterator<Item = (&String, &MemoryEntry)> {
+        let now = unix_millis();
+        self.memory.iter().filter(move |(_, entry)| !entry.is_expired(now))
+    }
+
+    /// Sets the moment the key expires, returns false when the key does not
+    /// exist
+    pub(crate) fn set_expiry(&mut self, key: &str, expires_at: u64) -> bool {
+        let now = unix_millis();
+        match self.memory.get_mut(key).filter(|entry| !entry.is_expired(now)) {
+            Some(entry) => {
+                entry.expires_at = Some(expires_at);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drops every expired entry, returns their keys
+    pub fn remove_expired(&mut self) -> Vec<String> {
+        let now = unix_millis();
+        let expired: Vec<String> = self
+            .memory
+            .iter()
+            .filter(|(_, entry)| entry.is_expired(now))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &expired {
+            self.memory.remove(key);
+        }
+
+        expired
+    }
 }
 
 impl MemoryAdapter for InMemory {
@@ -13,17 +67,50 @@ impl MemoryAdapter for InMemory {
     where
         O: FromBytes,
     {
-        Ok(self.memory.get(key).map(|b| O::from_bytes(b.deref())).transpose()?)
+        Ok(self
+            .live_entry(key)
+            .map(|entry| O::from_bytes(entry.value.deref()))
+            .transpose()?)
     }
 
     fn put<V>(&mut self, key: &str, value: V) -> Option<Bytes>
     where
         V: ToBytes,
     {
-        self.memory.insert(key.into(), value.to_bytes())
+        let entry = MemoryEntry {
+            value:      value.to_bytes(),
+            expires_at: None,
+        };
+
+        self.memory
+            .insert(key.into(), entry)
+            .filter(|previous| !previous.is_expired(unix_millis()))
+            .map(|previous| previous.value)
     }
 
     fn contains_key(&self, key: &str) -> bool {
-        self.memory.contains_key(key)
+        self.live_entry(key).is_some()
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Bytes> {
+        self.memory
+            .remove(key)
+            .filter(|entry| !entry.is_expired(unix_millis()))
+            .map(|entry| entry.value)
+    }
+
+    fn keys_with_prefix(&self, prefix: &str) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .entries()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.sort();
+
+        keys
+    }
+
+    fn expire(&mut self, key: &str, ttl: Duration) -> bool {
+        self.set_expiry(key, unix_millis().saturating_add(ttl.as_millis() as u64))
     }
 }
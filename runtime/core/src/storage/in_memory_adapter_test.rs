@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use super::{InMemory, MemoryAdapter, ToBytes};
 
 #[test]
@@ -35,3 +37,46 @@ fn test_in_memory_storage_incorrect_read_type() {
     memory_adapter.put("u32", 245u32);
     let _: Option<u8> = memory_adapter.get("u32").unwrap();
 }
+
+#[test]
+fn test_in_memory_storage_remove() {
+    let mut memory_adapter = InMemory::default();
+
+    memory_adapter.put("u32", 245u32);
+    assert_eq!(memory_adapter.remove("u32"), Some(245u32.to_bytes()));
+    assert!(!memory_adapter.contains_key("u32"));
+    assert_eq!(memory_adapter.remove("u32"), None);
+}
+
+#[test]
+fn test_in_memory_storage_keys_with_prefix() {
+    let mut memory_adapter = InMemory::default();
+
+    memory_adapter.put("batch_2", 2u32);
+    memory_adapter.put("batch_1", 1u32);
+    memory_adapter.put("other", 3u32);
+
+    assert_eq!(memory_adapter.keys_with_prefix("batch_"), vec!["batch_1", "batch_2"]);
+    assert_eq!(memory_adapter.keys_with_prefix("").len(), 3);
+}
+
+#[test]
+fn test_in_memory_storage_expire() {
+    let mut memory_adapter = InMemory::default();
+
+    memory_adapter.put("expired", 1u32);
+    memory_adapter.put("alive", 2u32);
+    assert!(memory_adapter.expire("expired", Duration::ZERO));
+    assert!(memory_adapter.expire("alive", Duration::from_secs(60)));
+    assert!(!memory_adapter.expire("missing", Duration::ZERO));
+
+    assert!(!memory_adapter.contains_key("expired"));
+    assert_eq!(memory_adapter.get::<u32>("expired").unwrap(), None);
+    assert_eq!(memory_adapter.get("alive").unwrap(), Some(2u32));
+    assert_eq!(memory_adapter.keys_with_prefix(""), vec!["alive"]);
+    assert_eq!(memory_adapter.remove_expired(), vec!["expired"]);
+
+    // Writing the key again clears the TTL
+    memory_adapter.put("expired", 3u32);
+    assert_eq!(memory_adapter.get("expired").unwrap(), Some(3u32));
+}
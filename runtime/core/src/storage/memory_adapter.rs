@@ -1,3 +1,5 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use super::{Bytes, FromBytes, ToBytes};
 use crate::Result;
 
@@ -11,4 +13,23 @@ pub trait MemoryAdapter: Default {
         V: ToBytes;
 
     fn contains_key(&self, key: &str) -> bool;
+
+    /// Removes the key, returns its value when it existed
+    fn remove(&mut self, key: &str) -> Option<Bytes>;
+
+    /// The keys that start with the prefix, sorted
+    fn keys_with_prefix(&self, prefix: &str) -> Vec<String>;
+
+    /// Removes the key once the TTL has passed, returns false when the key
+    /// does not exist. Writing the key again clears the TTL.
+    fn expire(&mut self, key: &str, ttl: Duration) -> bool;
+}
+
+/// The current time as a Unix timestamp in milliseconds, expiry times are
+/// stored like this so they survive restarts
+pub(crate) fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
 }
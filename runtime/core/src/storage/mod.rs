@@ -3,7 +3,13 @@ pub use in_memory_adapter::*;
 
 mod memory_adapter;
 pub use memory_adapter::*;
+
+mod persistent_memory_adapter;
+pub use persistent_memory_adapter::*;
+
+mod shared_memory_transaction;
 pub(crate) use seda_runtime_sdk::{Bytes, FromBytes, ToBytes};
+pub use shared_memory_transaction::*;
 
 #[cfg(test)]
 #[path = ""]
@@ -11,4 +17,6 @@ pub mod test {
     use super::*;
 
     mod in_memory_adapter_test;
+    mod persistent_memory_adapter_test;
+    mod shared_memory_transaction_test;
 }
@@ -0,0 +1,321 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use tracing::error;
+
+use super::{unix_millis, Bytes, FromBytes, InMemory, MemoryAdapter, ToBytes};
+use crate::Result;
+
+/// A change to the memory. The log and the snapshot are both a sequence of
+/// borsh encoded entries.
+#[derive(BorshSerialize, BorshDeserialize)]
+enum LogEntry {
+    Put {
+        key:   String,
+        value: Vec<u8>,
+        /// The program the write counts against, if any
+        owner: Option<String>,
+    },
+    Remove {
+        key: String,
+    },
+    Expire {
+        key:        String,
+        expires_at: u64,
+    },
+}
+
+/// Applies every complete entry in the bytes, returns the length of the
+/// complete entries and their count. The last entry is incomplete when the
+/// node stopped while writing it.
+fn read_entries(bytes: &[u8], memory: &mut PersistentMemory) -> (usize, u64) {
+    let mut remaining = bytes;
+    let mut entries = 0;
+    loop {
+        let mut entry_bytes = remaining;
+        match LogEntry::deserialize(&mut entry_bytes) {
+            Ok(entry) => memory.apply(entry),
+            Err(_) => break,
+        };
+        remaining = entry_bytes;
+        entries += 1;
+    }
+
+    (bytes.len() - remaining.len(), entries)
+}
+
+#[derive(Debug)]
+struct Storage {
+    dir:                   PathBuf,
+    log:                   File,
+    snapshot_interval:     u64,
+    writes_since_snapshot: u64,
+}
+
+/// A memory adapter that survives restarts. Every write is appended to a log
+/// in its directory and the log is compacted into a snapshot every
+/// `snapshot_interval` writes. The default adapter is not persisted.
+///
+/// Writes made with [PersistentMemory::put_owned] count against the quota of
+/// the program that made them.
+#[derive(Debug, Default)]
+pub struct PersistentMemory {
+    memory:  InMemory,
+    /// The program and size (key and value bytes) of every owned key
+    owners:  HashMap<String, (String, usize)>,
+    /// The bytes owned by every program
+    usage:   HashMap<String, usize>,
+    storage: Option<Storage>,
+}
+
+impl PersistentMemory {
+    const LOG_FILE: &str = "shared_memory.log";
+    const SNAPSHOT_FILE: &str = "shared_memory.snapshot";
+    pub const SNAPSHOT_INTERVAL: u64 = 1_000;
+
+    /// Loads the memory persisted in the directory, the directory is created
+    /// when it does not exist yet
+    pub fn open<P: AsRef<Path>>(dir: P, snapshot_interval: u64) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut memory = Self::default();
+        let snapshot_path = dir.join(Self::SNAPSHOT_FILE);
+        if snapshot_path.is_file() {
+            read_entries(&fs::read(snapshot_path)?, &mut memory);
+        }
+
+        let log_path = dir.join(Self::LOG_FILE);
+        let mut writes_since_snapshot = 0;
+        if log_path.is_file() {
+            let (complete_length, entries) = read_entries(&fs::read(&log_path)?, &mut memory);
+            writes_since_snapshot = entries;
+
+            // New entries are appended after the last complete one
+            OpenOptions::new()
+                .write(true)
+                .open(&log_path)?
+                .set_len(complete_length as u64)?;
+        }
+
+        let log = OpenOptions::new().create(true).append(true).open(log_path)?;
+        memory.storage = Some(Storage {
+            dir,
+            log,
+            snapshot_interval,
+            writes_since_snapshot,
+        });
+
+        Ok(memory)
+    }
+
+    /// The bytes (keys and values) the program owns
+    pub fn usage(&self, owner: &str) -> usize {
+        self.usage.get(owner).copied().unwrap_or_default()
+    }
+
+    /// The bytes the key takes from the quota of the program, 0 when the
+    /// program does not own the key
+    pub fn owned_bytes(&self, owner: &str, key: &str) -> usize {
+        match self.owners.get(key) {
+            Some((key_owner, size)) if key_owner == owner => *size,
+            _ => 0,
+        }
+    }
+
+    /// Whether the program can write the value to the key without owning more
+    /// than `quota` bytes. Expired keys are dropped when they are in the way.
+    pub fn fits_quota(&mut self, owner: &str, key: &str, value_length: usize, quota: usize) -> bool {
+        let fits =
+            |memory: &Self| memory.usage(owner) - memory.owned_bytes(owner, key) + key.len() + value_length <= quota;
+
+        if fits(self) {
+            return true;
+        }
+
+        self.remove_expired();
+        fits(self)
+    }
+
+    /// Writes the value and counts it against the quota of the program
+    pub fn put_owned(&mut self, owner: &str, key: &str, value: Vec<u8>) -> Option<Bytes> {
+        self.write(LogEntry::Put {
+            key: key.to_string(),
+            value,
+            owner: Some(owner.to_string()),
+        })
+    }
+
+    /// Removes the key at the Unix timestamp (ms), returns false when the key
+    /// does not exist
+    pub fn expire_at(&mut self, key: &str, expires_at: u64) -> bool {
+        if !self.memory.contains_key(key) {
+            return false;
+        }
+
+        self.write(LogEntry::Expire {
+            key: key.to_string(),
+            expires_at,
+        });
+
+        true
+    }
+
+    /// Drops every expired key and frees the bytes it took
+    pub fn remove_expired(&mut self) {
+        for key in self.memory.remove_expired() {
+            self.release(&key);
+        }
+    }
+
+    /// Writes the whole memory to the snapshot and clears the log. Does
+    /// nothing when the memory is not persisted.
+    pub fn snapshot(&mut self) -> Result<()> {
+        self.remove_expired();
+        let storage = match &mut self.storage {
+            Some(storage) => storage,
+            None => return Ok(()),
+        };
+
+        // The snapshot is replaced at once, so a crash never leaves a partial one
+        let temporary_path = storage.dir.join(format!("{}.tmp", Self::SNAPSHOT_FILE));
+        let mut snapshot = BufWriter::new(File::create(&temporary_path)?);
+        for (key, entry) in self.memory.entries() {
+            LogEntry::Put {
+                key:   key.clone(),
+                value: entry.value.to_vec(),
+                owner: self.owners.get(key).map(|(owner, _)| owner.clone()),
+            }
+            .serialize(&mut snapshot)?;
+
+            if let Some(expires_at) = entry.expires_at {
+                LogEntry::Expire {
+                    key: key.clone(),
+                    expires_at,
+                }
+                .serialize(&mut snapshot)?;
+            }
+        }
+        snapshot.into_inner().map_err(|error| error.into_error())?.sync_all()?;
+        fs::rename(&temporary_path, storage.dir.join(Self::SNAPSHOT_FILE))?;
+
+        // Replaying the log on top of the snapshot is harmless, so a crash
+        // before this point does not lose anything
+        storage.log.set_len(0)?;
+        storage.writes_since_snapshot = 0;
+
+        Ok(())
+    }
+
+    /// Frees the bytes the key took from the quota of its owner
+    fn release(&mut self, key: &str) {
+        if let Some((owner, size)) = self.owners.remove(key) {
+            if let Some(usage) = self.usage.get_mut(&owner) {
+                *usage -= size;
+            }
+        }
+    }
+
+    /// Applies the entry to the memory, returns the value it replaced or
+    /// removed
+    fn apply(&mut self, entry: LogEntry) -> Option<Bytes> {
+        match entry {
+            LogEntry::Put { key, value, owner } => {
+                self.release(&key);
+                if let Some(owner) = owner {
+                    let size = key.len() + value.len();
+                    *self.usage.entry(owner.clone()).or_default() += size;
+                    self.owners.insert(key.clone(), (owner, size));
+                }
+
+                self.memory.put(&key, value)
+            }
+            LogEntry::Remove { key } => {
+                self.release(&key);
+                self.memory.remove(&key)
+            }
+            LogEntry::Expire { key, expires_at } => {
+                self.memory.set_expiry(&key, expires_at);
+                None
+            }
+        }
+    }
+
+    /// Applies the entry and appends it to the log. The memory stays usable
+    /// when the disk fails, the write is only lost on a restart.
+    fn write(&mut self, entry: LogEntry) -> Option<Bytes> {
+        let encoded = self.storage.is_some().then(|| entry.try_to_vec());
+        let previous = self.apply(entry);
+
+        if let Some(encoded) = encoded {
+            if let Err(error) = encoded.map_err(Into::into).and_then(|encoded| self.persist(&encoded)) {
+                error!("Could not persist a shared memory write: {error}");
+            }
+        }
+
+        previous
+    }
+
+    fn persist(&mut self, encoded: &[u8]) -> Result<()> {
+        let snapshot_due = match &mut self.storage {
+            Some(storage) => {
+                storage.log.write_all(encoded)?;
+                storage.writes_since_snapshot += 1;
+                storage.writes_since_snapshot >= storage.snapshot_interval
+            }
+            None => false,
+        };
+
+        if snapshot_due {
+            self.snapshot()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl MemoryAdapter for PersistentMemory {
+    fn get<O>(&self, key: &str) -> Result<Option<O>>
+    where
+        O: FromBytes,
+    {
+        self.memory.get(key)
+    }
+
+    fn put<V>(&mut self, key: &str, value: V) -> Option<Bytes>
+    where
+        V: ToBytes,
+    {
+        self.write(LogEntry::Put {
+            key:   key.to_string(),
+            value: value.to_bytes().eject(),
+            owner: None,
+        })
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        self.memory.contains_key(key)
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Bytes> {
+        if !self.memory.contains_key(key) {
+            return None;
+        }
+
+        self.write(LogEntry::Remove { key: key.to_string() })
+    }
+
+    fn keys_with_prefix(&self, prefix: &str) -> Vec<String> {
+        self.memory.keys_with_prefix(prefix)
+    }
+
+    fn expire(&mut self, key: &str, ttl: Duration) -> bool {
+        self.expire_at(key, unix_millis().saturating_add(ttl.as_millis() as u64))
+    }
+}
@@ -0,0 +1,114 @@
+use std::{env, fs, path::PathBuf, time::Duration};
+
+use super::{MemoryAdapter, PersistentMemory};
+
+fn storage_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("seda_shared_memory_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    dir
+}
+
+#[test]
+fn test_persistent_storage_survives_reopen() {
+    let dir = storage_dir("reopen");
+
+    let mut memory_adapter = PersistentMemory::open(&dir, 1_000).unwrap();
+    memory_adapter.put("u32", 245u32);
+    memory_adapter.put("string", "hello".to_string());
+    memory_adapter.put("string", "replaced".to_string());
+    drop(memory_adapter);
+
+    let memory_adapter = PersistentMemory::open(&dir, 1_000).unwrap();
+    assert_eq!(memory_adapter.get("u32").unwrap(), Some(245u32));
+    assert_eq!(memory_adapter.get("string").unwrap(), Some("replaced".to_string()));
+}
+
+#[test]
+fn test_persistent_storage_snapshot() {
+    let dir = storage_dir("snapshot");
+
+    // Every second write compacts the log into a snapshot
+    let mut memory_adapter = PersistentMemory::open(&dir, 2).unwrap();
+    memory_adapter.put("first", 1u32);
+    memory_adapter.put("second", 2u32);
+    memory_adapter.put("third", 3u32);
+    drop(memory_adapter);
+
+    let memory_adapter = PersistentMemory::open(&dir, 2).unwrap();
+    assert_eq!(memory_adapter.get("first").unwrap(), Some(1u32));
+    assert_eq!(memory_adapter.get("second").unwrap(), Some(2u32));
+    assert_eq!(memory_adapter.get("third").unwrap(), Some(3u32));
+}
+
+#[test]
+fn test_persistent_storage_ignores_incomplete_write() {
+    let dir = storage_dir("incomplete");
+
+    let mut memory_adapter = PersistentMemory::open(&dir, 1_000).unwrap();
+    memory_adapter.put("complete", 1u32);
+    memory_adapter.put("incomplete", 2u32);
+    drop(memory_adapter);
+
+    // Cut the last entry in half, like a node that stopped while writing it
+    let log_path = dir.join("shared_memory.log");
+    let log = fs::read(&log_path).unwrap();
+    fs::write(&log_path, &log[..log.len() - 3]).unwrap();
+
+    let mut memory_adapter = PersistentMemory::open(&dir, 1_000).unwrap();
+    assert_eq!(memory_adapter.get("complete").unwrap(), Some(1u32));
+    assert!(!memory_adapter.contains_key("incomplete"));
+
+    memory_adapter.put("after", 3u32);
+    drop(memory_adapter);
+
+    let memory_adapter = PersistentMemory::open(&dir, 1_000).unwrap();
+    assert_eq!(memory_adapter.get("after").unwrap(), Some(3u32));
+}
+
+#[test]
+fn test_persistent_storage_remove_and_expire() {
+    let dir = storage_dir("remove");
+
+    let mut memory_adapter = PersistentMemory::open(&dir, 1_000).unwrap();
+    memory_adapter.put("removed", 1u32);
+    memory_adapter.put("expired", 2u32);
+    memory_adapter.put("alive", 3u32);
+    memory_adapter.remove("removed");
+    memory_adapter.expire("expired", Duration::ZERO);
+    memory_adapter.expire("alive", Duration::from_secs(60));
+    drop(memory_adapter);
+
+    let mut memory_adapter = PersistentMemory::open(&dir, 1_000).unwrap();
+    assert_eq!(memory_adapter.keys_with_prefix(""), vec!["alive"]);
+
+    // The TTL is part of the snapshot as well
+    memory_adapter.snapshot().unwrap();
+    drop(memory_adapter);
+    let memory_adapter = PersistentMemory::open(&dir, 1_000).unwrap();
+    assert_eq!(memory_adapter.get("alive").unwrap(), Some(3u32));
+}
+
+#[test]
+fn test_persistent_storage_quota() {
+    let mut memory_adapter = PersistentMemory::default();
+
+    // 4 bytes of key and 4 bytes of value
+    assert!(memory_adapter.fits_quota("program", "key1", 4, 8));
+    memory_adapter.put_owned("program", "key1", vec![0; 4]);
+    assert_eq!(memory_adapter.usage("program"), 8);
+
+    // Replacing the value only counts the new value
+    assert!(memory_adapter.fits_quota("program", "key1", 4, 8));
+    assert!(!memory_adapter.fits_quota("program", "key2", 1, 8));
+    assert!(memory_adapter.fits_quota("other", "key2", 1, 8));
+
+    memory_adapter.remove("key1");
+    assert_eq!(memory_adapter.usage("program"), 0);
+
+    // Expired keys are dropped to make room
+    memory_adapter.put_owned("program", "key1", vec![0; 4]);
+    memory_adapter.expire("key1", Duration::ZERO);
+    assert!(memory_adapter.fits_quota("program", "key2", 4, 8));
+    assert_eq!(memory_adapter.usage("program"), 0);
+}
@@ -0,0 +1,358 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+
+use parking_lot::RwLock;
+use tracing::warn;
+
+use super::{unix_millis, Bytes, FromBytes, MemoryAdapter, PersistentMemory, ToBytes};
+use crate::Result;
+
+#[derive(Debug)]
+enum StagedWrite {
+    /// The value counts against the quota of the program that wrote it
+    Put {
+        owner: String,
+        value: Vec<u8>,
+    },
+    Remove,
+}
+
+/// The writes staged since the transaction started, or since a savepoint
+#[derive(Debug, Default)]
+struct Staged {
+    writes:   HashMap<String, StagedWrite>,
+    /// Unix timestamps (ms) from which the keys no longer exist
+    expiries: HashMap<String, u64>,
+}
+
+impl Staged {
+    /// Stages the writes of a later savepoint on top of these
+    fn merge(&mut self, later: Staged) {
+        for (key, write) in later.writes {
+            self.expiries.remove(&key);
+            self.writes.insert(key, write);
+        }
+        self.expiries.extend(later.expiries);
+    }
+}
+
+/// The writes of the job, or of a program it calls
+#[derive(Debug, Default)]
+struct Scope {
+    /// The program that stages the writes
+    owner:    String,
+    /// The writes staged outside of an explicit transaction
+    staged:   Staged,
+    /// Set by [SharedMemoryTransaction::begin], the writes of the explicit
+    /// transaction are staged separately until it is committed
+    explicit: Option<Staged>,
+}
+
+impl Scope {
+    /// The staged changes, from the first staged to the last staged
+    fn layers(&self) -> impl DoubleEndedIterator<Item = &Staged> {
+        std::iter::once(&self.staged).chain(self.explicit.as_ref())
+    }
+
+    /// The changes the next writes are staged in
+    fn current_mut(&mut self) -> &mut Staged {
+        match &mut self.explicit {
+            Some(explicit) => explicit,
+            None => &mut self.staged,
+        }
+    }
+
+    fn commit_explicit(&mut self) {
+        if let Some(explicit) = self.explicit.take() {
+            self.staged.merge(explicit);
+        }
+    }
+}
+
+/// Marks where the writes of a called program start, see
+/// [SharedMemoryTransaction::savepoint]
+#[derive(Debug, PartialEq, Eq)]
+pub struct Savepoint(usize);
+
+/// The shared memory as seen by a single execution. Writes are staged and
+/// only reach the shared memory when they are committed, so an execution that
+/// fails halfway does not leave its partial writes behind.
+///
+/// Reads see the staged writes on top of the shared memory.
+#[derive(Debug, Default)]
+pub struct SharedMemoryTransaction {
+    shared_memory: Arc<RwLock<PersistentMemory>>,
+    /// The writes of the job itself
+    job:           Scope,
+    /// The writes of the programs the job calls, the innermost call last
+    calls:         Vec<Scope>,
+    /// Commits only stage the writes, nothing reaches the shared memory
+    dry_run:       bool,
+}
+
+impl SharedMemoryTransaction {
+    pub fn new(shared_memory: Arc<RwLock<PersistentMemory>>, owner: &str) -> Self {
+        Self {
+            shared_memory,
+            job: Scope {
+                owner: owner.to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// A transaction whose writes are visible to the execution but never
+    /// reach the shared memory, not even when they are committed
+    pub fn dry_run(shared_memory: Arc<RwLock<PersistentMemory>>, owner: &str) -> Self {
+        Self {
+            dry_run: true,
+            ..Self::new(shared_memory, owner)
+        }
+    }
+
+    /// Whether the program that is executed can write the value to the key
+    /// without owning more than `quota` bytes, the staged writes included
+    pub fn fits_quota(&self, key: &str, value_length: usize, quota: usize) -> bool {
+        let owner = &self.scope().owner;
+        let fits = |shared_memory: &PersistentMemory| {
+            self.usage(shared_memory, owner) + key.len() + value_length - self.owned_bytes(shared_memory, owner, key)
+                <= quota
+        };
+
+        if fits(&self.shared_memory.read()) {
+            return true;
+        }
+
+        let mut shared_memory = self.shared_memory.write();
+        shared_memory.remove_expired();
+        fits(&shared_memory)
+    }
+
+    /// Starts an explicit transaction: the next writes are staged on top of the
+    /// ones staged so far and wait for [SharedMemoryTransaction::commit]. Does
+    /// nothing when an explicit transaction is already open.
+    pub fn begin(&mut self) {
+        self.scope_mut().explicit.get_or_insert_with(Staged::default);
+    }
+
+    /// Writes every staged change to the shared memory at once, the ones of an
+    /// open explicit transaction included. A dry run keeps them staged, and so
+    /// does a called program: its writes stay within its savepoint.
+    pub fn commit(&mut self) {
+        self.scope_mut().commit_explicit();
+        if self.dry_run || !self.calls.is_empty() {
+            return;
+        }
+
+        let staged = std::mem::take(&mut self.job.staged);
+        let mut shared_memory = self.shared_memory.write();
+        for (key, write) in staged.writes {
+            match write {
+                StagedWrite::Put { owner, value } => shared_memory.put_owned(&owner, &key, value),
+                StagedWrite::Remove => shared_memory.remove(&key),
+            };
+        }
+        for (key, expires_at) in staged.expiries {
+            shared_memory.expire_at(&key, expires_at);
+        }
+    }
+
+    /// Starts staging the writes of a called program separately, so they can
+    /// be rolled back without the ones of its caller. They count against the
+    /// quota of the called program.
+    pub fn savepoint(&mut self, owner: &str) -> Savepoint {
+        self.calls.push(Scope {
+            owner: owner.to_string(),
+            ..Default::default()
+        });
+        Savepoint(self.calls.len())
+    }
+
+    /// Ends a called program that succeeded, its writes are staged for its
+    /// caller. An explicit transaction it never committed is rolled back.
+    pub fn release(&mut self, savepoint: Savepoint) {
+        while self.calls.len() >= savepoint.0 {
+            let call = self.calls.pop().expect("savepoints are only released once");
+            if call.explicit.is_some() {
+                warn!(
+                    "Program `{}` ended without committing its shared memory transaction, it is rolled back",
+                    call.owner
+                );
+            }
+            self.scope_mut().current_mut().merge(call.staged);
+        }
+    }
+
+    /// Ends a called program that failed, every write it staged is discarded
+    pub fn rollback_to(&mut self, savepoint: Savepoint) {
+        self.calls.truncate(savepoint.0 - 1);
+    }
+
+    /// Ends the execution. The staged writes are committed when it succeeded,
+    /// otherwise they are discarded. An explicit transaction that was never
+    /// committed is rolled back either way.
+    pub fn finish(mut self, success: bool) {
+        // Calls are only left open by a job that failed while running them
+        self.calls.clear();
+        if self.job.explicit.take().is_some() {
+            warn!(
+                "Program `{}` ended without committing its shared memory transaction, it is rolled back",
+                self.job.owner
+            );
+        }
+
+        if success {
+            self.commit();
+        }
+    }
+
+    /// The staged changes, from the first staged to the last staged
+    fn layers(&self) -> impl DoubleEndedIterator<Item = &Staged> {
+        self.job.layers().chain(self.calls.iter().flat_map(Scope::layers))
+    }
+
+    /// The scope of the innermost program
+    fn scope(&self) -> &Scope {
+        self.calls.last().unwrap_or(&self.job)
+    }
+
+    /// The scope of the innermost program
+    fn scope_mut(&mut self) -> &mut Scope {
+        self.calls.last_mut().unwrap_or(&mut self.job)
+    }
+
+    /// The changes the next writes are staged in
+    fn current_mut(&mut self) -> &mut Staged {
+        self.scope_mut().current_mut()
+    }
+
+    /// The last staged value of the key, `None` when it was not staged and
+    /// `Some(None)` when it was removed or expired
+    fn staged_value(&self, key: &str) -> Option<Option<&Vec<u8>>> {
+        for staged in self.layers().rev() {
+            if staged
+                .expiries
+                .get(key)
+                .map_or(false, |expires_at| *expires_at <= unix_millis())
+            {
+                return Some(None);
+            }
+
+            match staged.writes.get(key) {
+                Some(StagedWrite::Put { value, .. }) => return Some(Some(value)),
+                Some(StagedWrite::Remove) => return Some(None),
+                None => {}
+            }
+        }
+
+        None
+    }
+
+    /// The last staged write of the key, ignoring expiries
+    fn staged_write(&self, key: &str) -> Option<&StagedWrite> {
+        self.layers().rev().find_map(|staged| staged.writes.get(key))
+    }
+
+    fn read(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match self.staged_value(key) {
+            Some(value) => Ok(value.cloned()),
+            None => self.shared_memory.read().get(key),
+        }
+    }
+
+    /// The bytes the program owns once the staged writes are committed
+    fn usage(&self, shared_memory: &PersistentMemory, owner: &str) -> usize {
+        let keys: BTreeSet<&String> = self.layers().flat_map(|staged| staged.writes.keys()).collect();
+        keys.into_iter().fold(shared_memory.usage(owner), |usage, key| {
+            usage + self.owned_bytes(shared_memory, owner, key) - shared_memory.owned_bytes(owner, key)
+        })
+    }
+
+    /// The bytes the key takes from the quota of the program once the staged
+    /// writes are committed, a key written by another program is owned by it
+    fn owned_bytes(&self, shared_memory: &PersistentMemory, owner: &str, key: &str) -> usize {
+        match self.staged_write(key) {
+            Some(StagedWrite::Put {
+                owner: key_owner,
+                value,
+            }) if key_owner == owner => key.len() + value.len(),
+            Some(_) => 0,
+            None => shared_memory.owned_bytes(owner, key),
+        }
+    }
+}
+
+impl MemoryAdapter for SharedMemoryTransaction {
+    fn get<O>(&self, key: &str) -> Result<Option<O>>
+    where
+        O: FromBytes,
+    {
+        Ok(self.read(key)?.map(O::from_bytes_vec).transpose()?)
+    }
+
+    fn put<V>(&mut self, key: &str, value: V) -> Option<Bytes>
+    where
+        V: ToBytes,
+    {
+        let previous = self.read(key).ok().flatten();
+        let scope = self.scope_mut();
+        let owner = scope.owner.clone();
+        let current = scope.current_mut();
+        current.writes.insert(
+            key.to_string(),
+            StagedWrite::Put {
+                owner,
+                value: value.to_bytes().eject(),
+            },
+        );
+        current.expiries.remove(key);
+
+        previous.map(ToBytes::to_bytes)
+    }
+
+    fn contains_key(&self, key: &str) -> bool {
+        matches!(self.read(key), Ok(Some(_)))
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Bytes> {
+        let previous = self.read(key).ok().flatten()?;
+        let current = self.current_mut();
+        current.writes.insert(key.to_string(), StagedWrite::Remove);
+        current.expiries.remove(key);
+
+        Some(previous.to_bytes())
+    }
+
+    fn keys_with_prefix(&self, prefix: &str) -> Vec<String> {
+        let stored: BTreeSet<String> = self.shared_memory.read().keys_with_prefix(prefix).into_iter().collect();
+        let staged = self
+            .layers()
+            .flat_map(|staged| staged.writes.keys().chain(staged.expiries.keys()))
+            .filter(|key| key.starts_with(prefix))
+            .cloned();
+        let keys: BTreeSet<String> = stored.iter().cloned().chain(staged).collect();
+
+        keys.into_iter()
+            .filter(|key| match self.staged_value(key) {
+                Some(value) => value.is_some(),
+                None => stored.contains(key),
+            })
+            .collect()
+    }
+
+    fn expire(&mut self, key: &str, ttl: Duration) -> bool {
+        if !self.contains_key(key) {
+            return false;
+        }
+
+        self.current_mut()
+            .expiries
+            .insert(key.to_string(), unix_millis().saturating_add(ttl.as_millis() as u64));
+
+        true
+    }
+}
@@ -0,0 +1,174 @@
+use std::{sync::Arc, time::Duration};
+
+use parking_lot::RwLock;
+
+use super::{MemoryAdapter, PersistentMemory, SharedMemoryTransaction};
+
+#[test]
+fn test_transaction_stages_writes() {
+    let shared_memory = Arc::new(RwLock::new(PersistentMemory::default()));
+    shared_memory.write().put("removed", 1u32);
+
+    let mut transaction = SharedMemoryTransaction::new(shared_memory.clone(), "program");
+    transaction.put("staged", 2u32);
+    transaction.remove("removed");
+
+    // The transaction sees its own writes, the shared memory does not
+    assert_eq!(transaction.get("staged").unwrap(), Some(2u32));
+    assert!(!transaction.contains_key("removed"));
+    assert_eq!(transaction.keys_with_prefix(""), vec!["staged"]);
+    assert!(!shared_memory.read().contains_key("staged"));
+    assert!(shared_memory.read().contains_key("removed"));
+
+    transaction.finish(true);
+    assert_eq!(shared_memory.read().keys_with_prefix(""), vec!["staged"]);
+    assert_eq!(shared_memory.read().usage("program"), "staged".len() + 4);
+}
+
+#[test]
+fn test_transaction_discards_writes_on_failure() {
+    let shared_memory = Arc::new(RwLock::new(PersistentMemory::default()));
+
+    let mut transaction = SharedMemoryTransaction::new(shared_memory.clone(), "program");
+    transaction.put("committed", 1u32);
+    transaction.commit();
+    transaction.put("discarded", 2u32);
+    transaction.finish(false);
+    assert!(shared_memory.read().contains_key("committed"));
+    assert!(!shared_memory.read().contains_key("discarded"));
+
+    // An explicit transaction is only committed explicitly
+    let mut transaction = SharedMemoryTransaction::new(shared_memory.clone(), "program");
+    transaction.begin();
+    transaction.put("discarded", 2u32);
+    transaction.expire("committed", Duration::ZERO);
+    transaction.finish(true);
+    assert!(shared_memory.read().contains_key("committed"));
+    assert!(!shared_memory.read().contains_key("discarded"));
+}
+
+#[test]
+fn test_explicit_transaction_is_a_savepoint() {
+    let shared_memory = Arc::new(RwLock::new(PersistentMemory::default()));
+
+    // Beginning a transaction does not commit the writes staged before it
+    let mut transaction = SharedMemoryTransaction::new(shared_memory.clone(), "program");
+    transaction.put("before", 1u32);
+    transaction.put("overwritten", 1u32);
+    transaction.begin();
+    assert!(!shared_memory.read().contains_key("before"));
+
+    transaction.put("overwritten", 2u32);
+    transaction.remove("before");
+    transaction.put("during", 3u32);
+    assert_eq!(transaction.get("overwritten").unwrap(), Some(2u32));
+    assert_eq!(transaction.keys_with_prefix(""), vec!["during", "overwritten"]);
+
+    // The open transaction is rolled back, the writes before it are kept
+    transaction.finish(true);
+    assert_eq!(shared_memory.read().keys_with_prefix(""), vec!["before", "overwritten"]);
+    assert_eq!(shared_memory.read().get("overwritten").unwrap(), Some(1u32));
+
+    // Nothing is kept when the execution fails before the transaction began
+    let mut transaction = SharedMemoryTransaction::new(shared_memory.clone(), "program");
+    transaction.put("failed", 1u32);
+    transaction.begin();
+    transaction.finish(false);
+    assert!(!shared_memory.read().contains_key("failed"));
+
+    // Committing keeps the writes of both
+    let mut transaction = SharedMemoryTransaction::new(shared_memory.clone(), "program");
+    transaction.put("staged", 1u32);
+    transaction.begin();
+    transaction.put("committed", 2u32);
+    transaction.commit();
+    transaction.finish(false);
+    assert!(shared_memory.read().contains_key("staged"));
+    assert_eq!(shared_memory.read().get("committed").unwrap(), Some(2u32));
+}
+
+#[test]
+fn test_call_savepoints() {
+    let shared_memory = Arc::new(RwLock::new(PersistentMemory::default()));
+
+    // A failed call loses its writes, even the ones it committed
+    let mut transaction = SharedMemoryTransaction::new(shared_memory.clone(), "program");
+    transaction.put("caller", 1u32);
+    let savepoint = transaction.savepoint("callee");
+    transaction.put("caller", 2u32);
+    transaction.begin();
+    transaction.put("failed", 3u32);
+    transaction.commit();
+    assert_eq!(transaction.get("failed").unwrap(), Some(3u32));
+    assert!(!shared_memory.read().contains_key("caller"));
+
+    transaction.rollback_to(savepoint);
+    assert_eq!(transaction.get("caller").unwrap(), Some(1u32));
+    assert!(!transaction.contains_key("failed"));
+
+    // A released call stages its writes for the caller
+    let savepoint = transaction.savepoint("callee");
+    transaction.put("released", 4u32);
+    transaction.begin();
+    transaction.put("uncommitted", 5u32);
+    transaction.release(savepoint);
+    assert_eq!(transaction.get("released").unwrap(), Some(4u32));
+    assert!(!transaction.contains_key("uncommitted"));
+
+    transaction.finish(true);
+    assert_eq!(shared_memory.read().keys_with_prefix(""), vec!["caller", "released"]);
+    assert_eq!(shared_memory.read().get("caller").unwrap(), Some(1u32));
+}
+
+#[test]
+fn test_call_quota() {
+    let shared_memory = Arc::new(RwLock::new(PersistentMemory::default()));
+    shared_memory.write().put_owned("callee", "callee/key", vec![0; 6]);
+
+    // The writes of a called program count against its own quota
+    let mut transaction = SharedMemoryTransaction::new(shared_memory.clone(), "caller");
+    transaction.put("caller/key", vec![0; 6]);
+    let savepoint = transaction.savepoint("callee");
+    assert!(!transaction.fits_quota("callee/other", 6, 32));
+    assert!(transaction.fits_quota("callee/key", 6, 32));
+    transaction.put("callee/key", vec![0; 2]);
+    assert!(transaction.fits_quota("callee/other", 2, 32));
+    transaction.release(savepoint);
+
+    transaction.finish(true);
+    assert_eq!(shared_memory.read().usage("caller"), "caller/key".len() + 6);
+    assert_eq!(shared_memory.read().usage("callee"), "callee/key".len() + 2);
+}
+
+#[test]
+fn test_dry_run_transaction_never_writes() {
+    let shared_memory = Arc::new(RwLock::new(PersistentMemory::default()));
+    shared_memory.write().put("removed", 1u32);
+
+    let mut transaction = SharedMemoryTransaction::dry_run(shared_memory.clone(), "program");
+    transaction.begin();
+    transaction.put("committed", 2u32);
+    transaction.remove("removed");
+    transaction.commit();
+
+    // The committed writes stay visible to the execution only
+    assert_eq!(transaction.get("committed").unwrap(), Some(2u32));
+    transaction.finish(true);
+    assert_eq!(shared_memory.read().keys_with_prefix(""), vec!["removed"]);
+}
+
+#[test]
+fn test_transaction_quota() {
+    let shared_memory = Arc::new(RwLock::new(PersistentMemory::default()));
+    shared_memory.write().put_owned("program", "key1", vec![0; 4]);
+
+    // The staged writes count against the quota as well
+    let mut transaction = SharedMemoryTransaction::new(shared_memory, "program");
+    assert!(transaction.fits_quota("key2", 4, 16));
+    transaction.put("key2", vec![0; 4]);
+    assert!(!transaction.fits_quota("key3", 1, 16));
+    assert!(transaction.fits_quota("key2", 4, 16));
+
+    transaction.remove("key1");
+    assert!(transaction.fits_quota("key3", 4, 16));
+}
@@ -6,7 +6,7 @@ use seda_chains::{chain, AnotherChain, ChainAdapterTrait, Client, NearChain};
 use seda_config::{ChainConfigs, NodeConfig};
 use seda_runtime_sdk::{events::Event, Chain};
 
-use crate::{HostAdapter, Result, RuntimeError};
+use crate::{Adapter, ChainInteractor, ComposedAdapter, DatabaseAdapter, EventSink, HttpAdapter, Result, RuntimeError};
 
 lazy_static! {
     #[derive(Clone, Default)]
@@ -14,56 +14,126 @@ lazy_static! {
 
 }
 
-pub struct RuntimeTestAdapter {
-    pub another_client: Client,
-    pub near_client:    Client,
-    pub chain_configs:  ChainConfigs,
-}
+#[derive(Adapter)]
+#[adapter(
+    database = TestDatabase,
+    http = TestHttp,
+    chain_interactor = TestChainInteractor,
+    event_sink = TestEventSink
+)]
+pub struct TestAdapterTypes;
+
+pub type RuntimeTestAdapter = ComposedAdapter<TestAdapterTypes>;
+
+pub struct TestDatabase;
 
 #[async_trait::async_trait]
-impl HostAdapter for RuntimeTestAdapter {
+impl DatabaseAdapter for TestDatabase {
     type Error = RuntimeError;
 
-    async fn new(config: ChainConfigs) -> Result<Self> {
-        Ok(Self {
-            another_client: Client::Another(AnotherChain::new_client(&config.another)?),
-            near_client:    Client::Near(NearChain::new_client(&config.near)?),
-            chain_configs:  config,
-        })
-    }
-
-    fn select_client_from_chain(&self, chain: Chain) -> Client {
-        match chain {
-            Chain::Another => self.another_client.clone(),
-            Chain::Near => self.near_client.clone(),
-        }
+    async fn new(_config: &ChainConfigs) -> Result<Self> {
+        Ok(Self)
     }
 
-    async fn db_get(&self, key: &str) -> Result<Option<String>> {
+    async fn get(&self, key: &str) -> Result<Option<String>> {
         let db = HASHMAP.lock().await;
         let value = db.get(key);
 
         Ok(value.cloned())
     }
 
-    async fn db_set(&self, key: &str, value: &str) -> Result<()> {
+    async fn set(&self, key: &str, value: &str) -> Result<()> {
         let mut db = HASHMAP.lock().await;
         db.insert(key.to_string(), value.to_string());
 
         Ok(())
     }
+}
+
+pub struct TestHttp;
+
+#[async_trait::async_trait]
+impl HttpAdapter for TestHttp {
+    type Error = RuntimeError;
 
-    async fn http_fetch(&self, url: &str) -> Result<String> {
+    async fn new(_config: &ChainConfigs) -> Result<Self> {
+        Ok(Self)
+    }
+
+    async fn fetch(&self, url: &str) -> Result<String> {
         Ok(reqwest::get(url).await?.text().await?)
     }
+}
+
+/// Answers every request with the same body and remembers the URLs, so tests
+/// do not depend on the network
+#[derive(Default)]
+pub struct MockHttp {
+    pub fetched_urls: Mutex<Vec<String>>,
+}
+
+impl MockHttp {
+    pub const BODY: &str = "mocked response";
+}
+
+#[async_trait::async_trait]
+impl HttpAdapter for MockHttp {
+    type Error = RuntimeError;
+
+    async fn new(_config: &ChainConfigs) -> Result<Self> {
+        Ok(Self::default())
+    }
 
-    async fn chain_view(&self, chain: Chain, contract_id: &str, method_name: &str, args: Vec<u8>) -> Result<Vec<u8>> {
+    async fn fetch(&self, url: &str) -> Result<String> {
+        self.fetched_urls.lock().await.push(url.to_string());
+
+        Ok(Self::BODY.to_string())
+    }
+}
+
+#[derive(Adapter)]
+#[adapter(
+    database = TestDatabase,
+    http = MockHttp,
+    chain_interactor = TestChainInteractor,
+    event_sink = TestEventSink
+)]
+pub struct MockHttpAdapterTypes;
+
+pub type MockHttpTestAdapter = ComposedAdapter<MockHttpAdapterTypes>;
+
+pub struct TestChainInteractor {
+    pub another_client: Client,
+    pub near_client:    Client,
+    pub chain_configs:  ChainConfigs,
+}
+
+#[async_trait::async_trait]
+impl ChainInteractor for TestChainInteractor {
+    type Error = RuntimeError;
+
+    async fn new(config: &ChainConfigs) -> Result<Self> {
+        Ok(Self {
+            another_client: Client::Another(AnotherChain::new_client(&config.another)?),
+            near_client:    Client::Near(NearChain::new_client(&config.near)?),
+            chain_configs:  config.clone(),
+        })
+    }
+
+    fn select_client_from_chain(&self, chain: Chain) -> Client {
+        match chain {
+            Chain::Another => self.another_client.clone(),
+            Chain::Near => self.near_client.clone(),
+        }
+    }
+
+    async fn view(&self, chain: Chain, contract_id: &str, method_name: &str, args: Vec<u8>) -> Result<Vec<u8>> {
         let client = self.select_client_from_chain(chain);
 
         Ok(chain::view(chain, client, contract_id, method_name, args).await?)
     }
 
-    async fn chain_call(
+    async fn call(
         &self,
         chain: Chain,
         contract_id: &str,
@@ -94,8 +164,19 @@ impl HostAdapter for RuntimeTestAdapter {
 
         Ok(chain::send_tx(chain, client, &signed_txn).await?)
     }
+}
+
+pub struct TestEventSink;
+
+#[async_trait::async_trait]
+impl EventSink for TestEventSink {
+    type Error = RuntimeError;
+
+    async fn new(_config: &ChainConfigs) -> Result<Self> {
+        Ok(Self)
+    }
 
-    async fn trigger_event(&self, _event: Event) -> Result<()> {
+    async fn trigger(&self, _event: Event) -> Result<()> {
         Ok(())
     }
 }
@@ -0,0 +1,183 @@
+use std::{
+    cell::RefCell,
+    ptr::NonNull,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use loupe::{MemoryUsage, MemoryUsageTracker};
+use wasmer::{
+    vm::{self, MemoryError, MemoryStyle, TableStyle, VMMemoryDefinition, VMTableDefinition},
+    BaseTunables,
+    ImportObject,
+    Instance,
+    InstantiationError,
+    MemoryType,
+    Module,
+    Pages,
+    TableType,
+    Tunables,
+};
+
+thread_local! {
+    /// The flag of the instance that is being created on this thread, see
+    /// [instantiate_with_limit_flag]
+    static INSTANTIATING: RefCell<Option<MemoryLimitFlag>> = RefCell::new(None);
+}
+
+/// Set when a memory grow of an instance was refused because of the limit.
+/// Every instance has its own flag, so nested executions and reused threads
+/// can not see the flag of another instance.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryLimitFlag(Arc<AtomicBool>);
+
+impl MemoryUsage for MemoryLimitFlag {
+    fn size_of_val(&self, _tracker: &mut dyn MemoryUsageTracker) -> usize {
+        std::mem::size_of_val(self)
+    }
+}
+
+impl MemoryLimitFlag {
+    pub fn is_exceeded(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    fn set(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Instantiates the module, the memories of the instance set the flag when
+/// they refuse to grow. The memories are created synchronously while the
+/// instance is created, so the flag only has to be handed to the tunables for
+/// the duration of the call.
+pub fn instantiate_with_limit_flag(
+    module: &Module,
+    imports: &ImportObject,
+    flag: &MemoryLimitFlag,
+) -> Result<Instance, InstantiationError> {
+    let previous = INSTANTIATING.with(|instantiating| instantiating.replace(Some(flag.clone())));
+    let instance = Instance::new(module, imports);
+    INSTANTIATING.with(|instantiating| instantiating.replace(previous));
+
+    instance
+}
+
+/// Tunables that cap the linear memory of every instance to a maximum amount
+/// of pages, regardless of what the module declares.
+#[derive(MemoryUsage)]
+pub struct LimitingTunables {
+    limit: Pages,
+    base:  BaseTunables,
+}
+
+impl LimitingTunables {
+    pub fn new(base: BaseTunables, limit: Pages) -> Self {
+        Self { limit, base }
+    }
+
+    /// Lowers (or sets) the maximum of the requested memory to the limit
+    fn adjust_memory(&self, requested: &MemoryType) -> MemoryType {
+        let mut adjusted = *requested;
+        adjusted.maximum = Some(requested.maximum.map_or(self.limit, |max| max.min(self.limit)));
+
+        adjusted
+    }
+
+    fn validate_memory(&self, ty: &MemoryType) -> Result<(), MemoryError> {
+        if ty.minimum > self.limit {
+            return Err(MemoryError::Generic(format!(
+                "Minimum of {} pages exceeds the memory limit of {} pages",
+                ty.minimum.0, self.limit.0
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Tunables for LimitingTunables {
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.base.memory_style(&self.adjust_memory(memory))
+    }
+
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(table)
+    }
+
+    fn create_host_memory(&self, ty: &MemoryType, style: &MemoryStyle) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+
+        Ok(Arc::new(LimitedMemory {
+            inner: self.base.create_host_memory(&adjusted, style)?,
+            flag:  MemoryLimitFlag::default(),
+        }))
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+
+        Ok(Arc::new(LimitedMemory {
+            inner: self.base.create_vm_memory(&adjusted, style, vm_definition_location)?,
+            flag:  INSTANTIATING
+                .with(|instantiating| instantiating.borrow().clone())
+                .unwrap_or_default(),
+        }))
+    }
+
+    fn create_host_table(&self, ty: &TableType, style: &TableStyle) -> Result<Arc<dyn vm::Table>, String> {
+        self.base.create_host_table(ty, style)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        self.base.create_vm_table(ty, style, vm_definition_location)
+    }
+}
+
+/// A linear memory that records when a grow is refused because of the limit
+#[derive(Debug, MemoryUsage)]
+struct LimitedMemory {
+    inner: Arc<dyn vm::Memory>,
+    flag:  MemoryLimitFlag,
+}
+
+impl vm::Memory for LimitedMemory {
+    fn ty(&self) -> MemoryType {
+        self.inner.ty()
+    }
+
+    fn style(&self) -> &MemoryStyle {
+        self.inner.style()
+    }
+
+    fn size(&self) -> Pages {
+        self.inner.size()
+    }
+
+    fn grow(&self, delta: Pages) -> Result<Pages, MemoryError> {
+        let result = self.inner.grow(delta);
+        if let Err(MemoryError::CouldNotGrow { .. }) = result {
+            self.flag.set();
+        }
+
+        result
+    }
+
+    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
+        self.inner.vmmemory()
+    }
+}
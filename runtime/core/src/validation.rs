@@ -0,0 +1,163 @@
+use std::{sync::Arc, time::Duration};
+
+use parking_lot::Mutex;
+use seda_config::NodeConfig;
+use seda_runtime_sdk::{ExecutionContext, ABI_VERSION, ABI_VERSION_SECTION};
+use tracing::warn;
+use wasmer::{
+    wasmparser::{Parser, Payload},
+    Extern,
+    ExternType,
+    Module,
+    Store,
+};
+use wasmer_wasi::{get_wasi_version, WasiState};
+
+use crate::{
+    imports::create_wasm_imports,
+    ExecutionDeadline,
+    InMemory,
+    ProgramManifest,
+    PromiseQueue,
+    Result,
+    RuntimeError,
+    SharedMemoryTransaction,
+    VmContext,
+};
+
+/// The oldest SDK ABI version the runtime can still execute
+pub const MIN_ABI_VERSION: u32 = 2;
+
+/// The imports of older SDK versions the host no longer provides, with the
+/// reason. `ed25519_sign` signed anything with the key the node signs its
+/// transactions with.
+const REMOVED_IMPORTS: &[(&str, &str)] = &[(
+    "ed25519_sign",
+    "it was replaced by `p2p_auth_sign`, which only signs P2P auth statements",
+)];
+
+/// Returns the data of the first custom section with the name, if the WASM
+/// binary has one
+pub(crate) fn custom_section<'a>(wasm_binary: &'a [u8], name: &str) -> Result<Option<&'a [u8]>> {
+    for payload in Parser::new(0).parse_all(wasm_binary) {
+        if let Payload::CustomSection(section) = payload.map_err(|error| error.to_string())? {
+            if section.name() == name {
+                return Ok(Some(section.data()));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Checks everything that would otherwise only fail once the program is
+/// executed: the ABI version it was built against, its entrypoint and whether
+/// the host provides every import with the type the program expects. All
+/// problems are reported at once.
+pub fn validate_program(
+    name: &str,
+    store: &Store,
+    module: &Module,
+    wasm_binary: &[u8],
+    node_config: &NodeConfig,
+) -> Result<()> {
+    let mut problems = Vec::new();
+
+    match custom_section(wasm_binary, ABI_VERSION_SECTION)? {
+        Some(version) => match <[u8; 4]>::try_from(version).map(u32::from_le_bytes) {
+            Ok(version) if version > ABI_VERSION => problems.push(format!(
+                "it was built against ABI version {version}, this node only supports up to version {ABI_VERSION}"
+            )),
+            Ok(version) if version < MIN_ABI_VERSION => problems.push(format!(
+                "it was built against ABI version {version}, rebuild it with an SDK of at least version \
+                 {MIN_ABI_VERSION}"
+            )),
+            Ok(_) => {}
+            Err(_) => problems.push(format!("its `{ABI_VERSION_SECTION}` section is not a version")),
+        },
+        None => warn!("Program `{name}` does not declare the ABI version it was built against"),
+    }
+
+    let exports = |name: &str, is_kind: fn(&ExternType) -> bool| {
+        module
+            .exports()
+            .any(|export| export.name() == name && is_kind(export.ty()))
+    };
+    if !exports("_start", |ty| matches!(ty, ExternType::Function(_))) {
+        problems.push("it does not export the `_start` function".to_string());
+    }
+    if !exports("memory", |ty| matches!(ty, ExternType::Memory(_))) {
+        problems.push("it does not export its `memory`".to_string());
+    }
+
+    if get_wasi_version(module, false).is_none() {
+        problems.push("it does not import WASI, it has to be built for the `wasm32-wasi` target".to_string());
+    } else {
+        validate_imports(store, module, node_config, &mut problems)?;
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(RuntimeError::InvalidProgram {
+            name: name.to_string(),
+            problems,
+        })
+    }
+}
+
+/// Resolves every import of the module against the imports of an execution
+fn validate_imports(
+    store: &Store,
+    module: &Module,
+    node_config: &NodeConfig,
+    problems: &mut Vec<String>,
+) -> Result<()> {
+    let vm_context = VmContext::create_vm_context(
+        Arc::new(Mutex::new(InMemory::default())),
+        Arc::new(Mutex::new(SharedMemoryTransaction::default())),
+        Arc::new(Mutex::new(PromiseQueue::new())),
+        Arc::new(Mutex::new(PromiseQueue::new())),
+        String::new(),
+        Arc::new(ProgramManifest::default()),
+        Vec::new(),
+        Arc::new(ExecutionContext::default()),
+        node_config.clone(),
+        ExecutionDeadline::new(Duration::ZERO),
+        None,
+        None,
+        None,
+    );
+    let mut wasi_env = WasiState::new("validation").finalize()?;
+    let import_object = create_wasm_imports(store, vm_context, &mut wasi_env, module)?;
+
+    for import in module.imports() {
+        let provided = import_object
+            .get_namespace_exports(import.module())
+            .and_then(|exports| exports.get_extern(import.name()).map(Extern::ty));
+
+        match provided {
+            None => match REMOVED_IMPORTS.iter().find(|(name, _)| *name == import.name()) {
+                Some((_, reason)) => problems.push(format!(
+                    "the host no longer provides its import `{}::{}`, {reason}",
+                    import.module(),
+                    import.name()
+                )),
+                None => problems.push(format!(
+                    "the host does not provide its import `{}::{}`",
+                    import.module(),
+                    import.name()
+                )),
+            },
+            Some(provided) if provided != *import.ty() => problems.push(format!(
+                "its import `{}::{}` is {:?}, the host provides {provided:?}",
+                import.module(),
+                import.name(),
+                import.ty()
+            )),
+            Some(_) => {}
+        }
+    }
+
+    Ok(())
+}
@@ -1,4 +1,6 @@
+use seda_runtime_sdk::Chain;
 use serde::{Deserialize, Serialize};
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ExitInfo {
     pub exit_message: String,
@@ -17,10 +19,45 @@ impl From<(String, u8)> for ExitInfo {
 /// Represents the result of a Vm instance
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct VmResult {
-    pub stdout:    Vec<String>,
-    pub stderr:    Vec<String>,
-    pub result:    Option<Vec<u8>>,
-    pub exit_info: ExitInfo,
+    pub stdout:      Vec<String>,
+    pub stderr:      Vec<String>,
+    pub result:      Option<Vec<u8>>,
+    pub exit_info:   ExitInfo,
+    pub gas_used:    u64,
+    /// Hex encoded hash of all inputs of a deterministic execution
+    pub input_hash:  Option<String>,
+    /// The `chain_call` promises a dry run captured, in the order they were
+    /// resolved
+    #[serde(default)]
+    pub chain_calls: Vec<SimulatedChainCall>,
+}
+
+impl VmResult {
+    /// The result of an execution that stopped before running anything
+    pub fn from_status(status: VmResultStatus) -> Self {
+        Self {
+            stdout:      vec![],
+            stderr:      vec![],
+            result:      None,
+            exit_info:   status.into(),
+            gas_used:    0,
+            input_hash:  None,
+            chain_calls: vec![],
+        }
+    }
+}
+
+/// A `chain_call` a dry run captured instead of sending it
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimulatedChainCall {
+    pub chain:       Chain,
+    pub contract_id: String,
+    pub method_name: String,
+    /// The arguments as text, hex encoded when they are not valid UTF-8
+    pub args:        String,
+    pub deposit:     u128,
+    /// The gas the node would attach to the transaction
+    pub gas:         u64,
 }
 
 // TODO create a readme of all these once its better established
@@ -49,6 +86,23 @@ pub enum VmResultStatus {
     FailedToConvertVMPipeToString,
     /// An execution error from the WASM Runtime
     ExecutionError(String),
+    /// When the execution consumed more gas than its limit
+    OutOfGas,
+    /// When the execution did not finish before its deadline
+    Timeout,
+    /// When the WASM instance tried to grow its memory beyond the limit
+    MemoryLimitExceeded,
+    /// When the execution result is bigger than the limit
+    ResultLimitExceeded,
+    /// When the captured stdout/stderr is bigger than the limit
+    LogLimitExceeded,
+    /// When no program is registered under the requested name
+    ProgramNotFound(String),
+    /// When a deterministic execution runs a program with float instructions
+    /// and the node is configured to reject them
+    FloatOpsNotAllowed,
+    /// When the program would own more shared memory than the limit
+    SharedMemoryQuotaExceeded,
 }
 
 impl From<VmResultStatus> for ExitInfo {
@@ -71,6 +125,24 @@ impl From<VmResultStatus> for ExitInfo {
                 ("Error: Failed to convert VM pipe output to String".into(), 8).into()
             }
             VmResultStatus::ExecutionError(err) => (format!("Execution Error: {err}"), 8).into(),
+            VmResultStatus::OutOfGas => ("Error: Execution ran out of gas".into(), 9).into(),
+            VmResultStatus::Timeout => ("Error: Execution timed out".into(), 10).into(),
+            VmResultStatus::MemoryLimitExceeded => ("Error: Execution exceeded the memory limit".into(), 11).into(),
+            VmResultStatus::ResultLimitExceeded => {
+                ("Error: Execution result exceeded the size limit".into(), 12).into()
+            }
+            VmResultStatus::LogLimitExceeded => ("Error: Execution output exceeded the size limit".into(), 13).into(),
+            VmResultStatus::ProgramNotFound(name) => (format!("Error: Program `{name}` is not registered"), 14).into(),
+            VmResultStatus::FloatOpsNotAllowed => (
+                "Error: Program uses float instructions in a deterministic execution".into(),
+                15,
+            )
+                .into(),
+            VmResultStatus::SharedMemoryQuotaExceeded => (
+                "Error: Execution exceeded the shared memory quota of the program".into(),
+                16,
+            )
+                .into(),
         }
     }
 }
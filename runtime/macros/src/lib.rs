@@ -7,10 +7,11 @@ struct AdapterActions {
     pub db:               Option<Ident>,
     pub http:             Option<Ident>,
     pub chain_interactor: Option<Ident>,
+    pub event_sink:       Option<Ident>,
 }
 
 impl IntoIterator for AdapterActions {
-    type IntoIter = std::array::IntoIter<Self::Item, 3>;
+    type IntoIter = std::array::IntoIter<Self::Item, 4>;
     type Item = (&'static str, Option<Ident>);
 
     fn into_iter(self) -> Self::IntoIter {
@@ -18,6 +19,7 @@ impl IntoIterator for AdapterActions {
             ("database", self.db),
             ("http", self.http),
             ("chain_interactor", self.chain_interactor),
+            ("event_sink", self.event_sink),
         ]
         .into_iter()
     }
@@ -39,6 +41,7 @@ impl AdapterActions {
             db:               either(self.db, other.db)?,
             http:             either(self.http, other.http)?,
             chain_interactor: either(self.chain_interactor, other.chain_interactor)?,
+            event_sink:       either(self.event_sink, other.event_sink)?,
         })
     }
 
@@ -72,6 +75,7 @@ mod keywords {
     syn::custom_keyword!(database);
     syn::custom_keyword!(http);
     syn::custom_keyword!(chain_interactor);
+    syn::custom_keyword!(event_sink);
 }
 
 impl Parse for AdapterActions {
@@ -85,6 +89,7 @@ impl Parse for AdapterActions {
                 db:               Some(db),
                 http:             None,
                 chain_interactor: None,
+                event_sink:       None,
             })
         } else if input.peek(keywords::http) {
             input.parse::<keywords::http>()?;
@@ -94,6 +99,7 @@ impl Parse for AdapterActions {
                 http:             Some(http),
                 db:               None,
                 chain_interactor: None,
+                event_sink:       None,
             })
         } else if input.peek(keywords::chain_interactor) {
             input.parse::<keywords::chain_interactor>()?;
@@ -103,6 +109,17 @@ impl Parse for AdapterActions {
                 chain_interactor: Some(chain_interactor),
                 http:             None,
                 db:               None,
+                event_sink:       None,
+            })
+        } else if input.peek(keywords::event_sink) {
+            input.parse::<keywords::event_sink>()?;
+            input.parse::<syn::Token![=]>()?;
+            let event_sink = input.parse::<syn::Ident>()?;
+            Ok(Self {
+                event_sink:       Some(event_sink),
+                chain_interactor: None,
+                http:             None,
+                db:               None,
             })
         } else {
             Err(syn::Error::new(
@@ -124,12 +141,14 @@ pub fn adapter(input: TokenStream) -> TokenStream {
     let db = actions.db.unwrap();
     let http = actions.http.unwrap();
     let chain_interactor = actions.chain_interactor.unwrap();
+    let event_sink = actions.event_sink.unwrap();
 
     let adapter_trait_impl = quote!(
-        impl HostAdapterTypes for #name {
+        impl ::seda_runtime::HostAdapterTypes for #name {
           type Database = #db;
           type Http = #http;
           type ChainInteractor = #chain_interactor;
+          type EventSink = #event_sink;
         }
     );
 
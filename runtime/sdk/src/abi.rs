@@ -0,0 +1,8 @@
+/// The version of the interface between the SDK and the runtime: the host
+/// imports and how their data is encoded. Bumped on every breaking change, the
+/// runtime refuses binaries built against a version it does not support.
+pub const ABI_VERSION: u32 = 2;
+
+/// The custom section of a WASM binary that holds the ABI version it was built
+/// against, little endian
+pub const ABI_VERSION_SECTION: &str = "seda_abi_version";
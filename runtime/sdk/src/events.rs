@@ -13,6 +13,18 @@ pub enum EventData {
     CliCall(Vec<String>),
 }
 
+impl EventData {
+    /// The name of the variant, without its data
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Self::BatchChainTick => "BatchChainTick",
+            Self::ChainTick => "ChainTick",
+            Self::P2PMessage(_) => "P2PMessage",
+            Self::CliCall(_) => "CliCall",
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Event {
     pub id:   EventId,
@@ -0,0 +1,27 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+use crate::events::EventId;
+
+/// Everything an execution knows about the node it runs on and the reason it
+/// was started. Passed to the WASM binary borsh encoded.
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionContext {
+    /// The event that started the job, none when it was started directly
+    pub event_id:           Option<EventId>,
+    /// The name of the [crate::events::EventData] variant of the event
+    pub event_type:         Option<String>,
+    /// The account of the SEDA main chain contract
+    pub contract_id:        String,
+    pub ed25519_public_key: Vec<u8>,
+    /// Uncompressed
+    pub bn254_public_key:   Vec<u8>,
+    /// Unix timestamp (ms) at which the job started. Deterministic executions
+    /// do not see the clock.
+    pub start_timestamp:    Option<u64>,
+    /// The main chain slot the job started in, estimated from the start
+    /// timestamp. Only set when the node knows the timestamp of the first
+    /// slot, deterministic executions do not see it either.
+    pub slot:               Option<u64>,
+    pub epoch:              Option<u64>,
+}
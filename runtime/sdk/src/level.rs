@@ -11,14 +11,18 @@ pub enum Level {
 
 // TODO only log line_info with a config option.
 impl Level {
+    /// The tracing target of everything a WASM program logs or prints, so it
+    /// can be filtered apart from the node logs
+    pub const TARGET: &str = "guest";
+
     pub fn log(self, message: &str, line_info: &str) {
         let message = format!("{message}\n    at {line_info}");
         match self {
-            Level::Debug => tracing::debug!(message),
-            Level::Error => tracing::error!(message),
-            Level::Info => tracing::info!(message),
-            Level::Trace => tracing::trace!(message),
-            Level::Warn => tracing::warn!(message),
+            Level::Debug => tracing::debug!(target: Self::TARGET, message),
+            Level::Error => tracing::error!(target: Self::TARGET, message),
+            Level::Info => tracing::info!(target: Self::TARGET, message),
+            Level::Trace => tracing::trace!(target: Self::TARGET, message),
+            Level::Warn => tracing::warn!(target: Self::TARGET, message),
         }
     }
 }
@@ -1,7 +1,11 @@
+mod abi;
+pub use abi::*;
 mod chain;
 pub use chain::Chain;
 mod errors;
 pub use errors::*;
+mod execution_context;
+pub use execution_context::*;
 mod level;
 pub use level::Level;
 mod bytes;
@@ -19,6 +23,7 @@ pub mod wasm;
 pub mod events;
 
 pub use promises::{
+    CallProgramAction,
     CallSelfAction,
     ChainCallAction,
     ChainViewAction,
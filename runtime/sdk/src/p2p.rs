@@ -1,5 +1,30 @@
+use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
+/// Prefixed to every [P2PAuth] the node signs, so the signature can not be
+/// taken for the signature of a transaction or of anything else the key signs
+pub const P2P_AUTH_DOMAIN: &[u8] = b"seda-p2p-auth-v1:";
+
+/// The only statement programs can have the node sign with its Ed25519 key,
+/// it proves to peers that the payload comes from the program on this node
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct P2PAuth {
+    /// The program that asked for the signature, set by the node
+    pub program: String,
+    pub payload: Vec<u8>,
+}
+
+impl P2PAuth {
+    /// The bytes the node signs: the domain prefix and the borsh encoded
+    /// statement
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = P2P_AUTH_DOMAIN.to_vec();
+        self.serialize(&mut bytes).expect("Writing to a Vec can not fail");
+
+        bytes
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct P2PMessage {
     pub source: Option<String>,
@@ -8,6 +8,9 @@ use crate::{events::Event, Chain};
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum PromiseAction {
     CallSelf(CallSelfAction),
+    /// Runs a function of another program, with its own memory and manifest,
+    /// and is fulfilled with its execution result
+    CallProgram(CallProgramAction),
     DatabaseSet(DatabaseSetAction),
     DatabaseGet(DatabaseGetAction),
     Http(HttpAction),
@@ -15,12 +18,42 @@ pub enum PromiseAction {
     ChainCall(ChainCallAction),
     TriggerEvent(TriggerEventAction),
     P2PBroadcast(P2PBroadcastAction),
+    /// Fulfilled with the results of all its dependencies once they are all
+    /// fulfilled, rejected as soon as one of them is rejected
+    All,
+    /// Fulfilled with the result of the first dependency that is fulfilled,
+    /// rejected when all of them are rejected
+    Any,
 }
 
 impl PromiseAction {
+    /// Combinators only wait for their dependencies, they are resolved by the
+    /// runtime itself
     #[cfg(not(target_family = "wasm"))]
-    pub fn is_limited_action(&self) -> bool {
-        !matches!(self, Self::CallSelf(_) | Self::Http(_))
+    pub fn is_combinator(&self) -> bool {
+        matches!(self, Self::All | Self::Any)
+    }
+
+    /// Actions that do not change any state, the runtime resolves these
+    /// concurrently unless they depend on each other
+    #[cfg(not(target_family = "wasm"))]
+    pub fn is_independent_action(&self) -> bool {
+        matches!(self, Self::DatabaseGet(_) | Self::Http(_) | Self::ChainView(_))
+    }
+
+    /// Actions the host resolves on its own, a WASM binary can wait for these
+    /// instead of queueing them
+    #[cfg(not(target_family = "wasm"))]
+    pub fn is_awaitable(&self) -> bool {
+        matches!(
+            self,
+            Self::DatabaseSet(_)
+                | Self::DatabaseGet(_)
+                | Self::Http(_)
+                | Self::ChainView(_)
+                | Self::ChainCall(_)
+                | Self::TriggerEvent(_)
+        )
     }
 }
 
@@ -28,6 +61,7 @@ impl fmt::Display for PromiseAction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::CallSelf(_) => write!(f, "call_self"),
+            Self::CallProgram(_) => write!(f, "call_program"),
             Self::DatabaseSet(_) => write!(f, "db_set"),
             Self::DatabaseGet(_) => write!(f, "db_get"),
             Self::Http(_) => write!(f, "http"),
@@ -35,6 +69,8 @@ impl fmt::Display for PromiseAction {
             Self::ChainCall(_) => write!(f, "chain_call"),
             Self::TriggerEvent(_) => write!(f, "trigger_event"),
             Self::P2PBroadcast(_) => write!(f, "p2p_broadcast"),
+            Self::All => write!(f, "all"),
+            Self::Any => write!(f, "any"),
         }
     }
 }
@@ -43,6 +79,19 @@ impl fmt::Display for PromiseAction {
 pub struct CallSelfAction {
     pub function_name: String,
     pub args:          Vec<String>,
+    /// Raw bytes passed to the function, read them with `execution_input`
+    #[serde(default)]
+    pub input:         Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CallProgramAction {
+    pub program_name:  String,
+    pub function_name: String,
+    pub args:          Vec<String>,
+    /// Raw bytes passed to the function, read them with `execution_input`
+    #[serde(default)]
+    pub input:         Vec<u8>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -1,10 +1,12 @@
+use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
 use super::PromiseAction;
 use crate::ToBytes;
 
 // TODO: Fulfilled and Rejected could now just be our Bytes type.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// Statuses are passed to the WASM binary borsh encoded
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Clone, Debug)]
 pub enum PromiseStatus {
     /// Initial state
     Unfulfilled,
@@ -22,6 +24,13 @@ pub enum PromiseStatus {
     Rejected(Vec<u8>),
 }
 
+impl PromiseStatus {
+    /// Whether the promise is either fulfilled or rejected
+    pub fn is_settled(&self) -> bool {
+        matches!(self, Self::Fulfilled(_) | Self::Rejected(_))
+    }
+}
+
 impl<T: crate::ToBytes, E: std::error::Error> From<Result<T, E>> for PromiseStatus {
     fn from(value: Result<T, E>) -> Self {
         match value {
@@ -47,4 +56,9 @@ pub struct Promise {
 
     /// The status of the promise, will include the result if it's fulfilled
     pub status: PromiseStatus,
+
+    /// The indexes of the promises (in the same queue) that have to be settled
+    /// before this promise is executed
+    #[serde(default)]
+    pub dependencies: Vec<usize>,
 }
@@ -1,9 +1,34 @@
 use super::Promise;
-use crate::{CallSelfAction, PromiseAction};
+use crate::{CallProgramAction, CallSelfAction, PromiseAction};
 
 pub fn call_self(function_name: &str, args: Vec<String>) -> Promise {
+    call_self_with_input(function_name, args, Vec::new())
+}
+
+/// Calls the function with raw bytes as input, the function reads them with
+/// [super::execution_input]
+pub fn call_self_with_input(function_name: &str, args: Vec<String>, input: Vec<u8>) -> Promise {
     Promise::new(PromiseAction::CallSelf(CallSelfAction {
         function_name: function_name.to_string(),
         args,
+        input,
+    }))
+}
+
+/// Calls a function of another registered program. The function runs with the
+/// memory and the manifest of that program, the promise is fulfilled with its
+/// execution result.
+pub fn call_program(program_name: &str, function_name: &str, args: Vec<String>) -> Promise {
+    call_program_with_input(program_name, function_name, args, Vec::new())
+}
+
+/// Calls a function of another registered program with raw bytes as input,
+/// see [call_program]
+pub fn call_program_with_input(program_name: &str, function_name: &str, args: Vec<String>, input: Vec<u8>) -> Promise {
+    Promise::new(PromiseAction::CallProgram(CallProgramAction {
+        program_name: program_name.to_string(),
+        function_name: function_name.to_string(),
+        args,
+        input,
     }))
 }
@@ -0,0 +1,48 @@
+use super::raw;
+
+pub const ED25519_SIGNATURE_LENGTH: usize = 64;
+
+/// Signs a [crate::p2p::P2PAuth] of the program and the payload with the
+/// Ed25519 key of the node, peers verify it against its
+/// [crate::p2p::P2PAuth::signing_bytes]. The public key is available with
+/// [super::get_local_ed25519_public_key]. The program needs the
+/// `p2p_auth_sign` host function in its manifest.
+///
+/// There is no `ed25519_sign` that signs arbitrary messages: the node signs
+/// its transactions with the same key, so a program could have it sign one.
+/// Programs built against an SDK that still imports `ed25519_sign` (ABI
+/// version 1) are rejected when they are loaded and have to use this instead.
+pub fn p2p_auth_sign(payload: &[u8]) -> [u8; ED25519_SIGNATURE_LENGTH] {
+    let mut result = [0; ED25519_SIGNATURE_LENGTH];
+    unsafe {
+        raw::p2p_auth_sign(
+            payload.as_ptr(),
+            payload.len() as i64,
+            result.as_mut_ptr(),
+            ED25519_SIGNATURE_LENGTH as i64,
+        )
+    };
+
+    result
+}
+
+/// Whether the signature of the message is valid for the public key, malformed
+/// signatures and public keys are not valid
+pub fn ed25519_verify(message: &[u8], signature: &[u8], public_key: &[u8]) -> bool {
+    let result = unsafe {
+        raw::ed25519_verify(
+            message.as_ptr(),
+            message.len() as i64,
+            signature.as_ptr(),
+            signature.len() as i64,
+            public_key.as_ptr(),
+            public_key.len() as i64,
+        )
+    };
+
+    match result {
+        0 => false,
+        1 => true,
+        _ => panic!("Ed25519 verify returned invalid bool in u8: {}", result),
+    }
+}
@@ -1,11 +1,19 @@
+use super::execution_context;
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
 pub fn get_oracle_contract_id() -> String {
-    std::env::var("ORACLE_CONTRACT_ID").expect("Env 'ORACLE_CONTRACT_ID' does not exist")
+    execution_context().contract_id
 }
 
+/// Hex encoded, uncompressed
 pub fn get_local_bn254_public_key() -> String {
-    std::env::var("BN254_PUBLIC_KEY").expect("Env 'BN254_PUBLIC_KEY' does not exist")
+    encode_hex(&execution_context().bn254_public_key)
 }
 
+/// Hex encoded
 pub fn get_local_ed25519_public_key() -> String {
-    std::env::var("ED25519_PUBLIC_KEY").expect("Env 'ED25519_PUBLIC_KEY' does not exist")
+    encode_hex(&execution_context().ed25519_public_key)
 }
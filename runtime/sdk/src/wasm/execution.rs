@@ -1,5 +1,34 @@
+use borsh::BorshDeserialize;
+
 use super::{raw, Promise};
-use crate::{events::Event, PromiseAction, TriggerEventAction};
+use crate::{events::Event, ExecutionContext, PromiseAction, TriggerEventAction};
+
+/// Returns the raw input bytes this execution was called with, empty when
+/// there are none
+pub fn execution_input() -> Vec<u8> {
+    let input_length = unsafe { raw::execution_input_length() };
+    let mut input = vec![0; input_length as usize];
+
+    unsafe {
+        raw::execution_input_write(input.as_mut_ptr(), input_length);
+    }
+
+    input
+}
+
+/// Returns the details of the node and the event this execution runs for.
+/// Unlike the WASI env, the context is available in every function the job
+/// calls.
+pub fn execution_context() -> ExecutionContext {
+    let context_length = unsafe { raw::execution_context_length() };
+    let mut context = vec![0; context_length as usize];
+
+    unsafe {
+        raw::execution_context_write(context.as_mut_ptr(), context_length);
+    }
+
+    ExecutionContext::try_from_slice(&context).expect("Host returned an invalid execution context")
+}
 
 pub fn execution_result(result: Vec<u8>) {
     let result_length = result.len() as i32;
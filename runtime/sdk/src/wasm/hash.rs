@@ -0,0 +1,49 @@
+use super::raw;
+
+/// The length of the digests returned by the hash functions
+const DIGEST_LENGTH: usize = 32;
+
+pub fn sha256(data: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut result = [0; DIGEST_LENGTH];
+    unsafe {
+        raw::sha256(
+            data.as_ptr(),
+            data.len() as i64,
+            result.as_mut_ptr(),
+            DIGEST_LENGTH as i64,
+        )
+    };
+
+    result
+}
+
+/// The keccak256 hash used by Ethereum, which differs from the standardized
+/// sha3-256
+pub fn keccak256(data: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut result = [0; DIGEST_LENGTH];
+    unsafe {
+        raw::keccak256(
+            data.as_ptr(),
+            data.len() as i64,
+            result.as_mut_ptr(),
+            DIGEST_LENGTH as i64,
+        )
+    };
+
+    result
+}
+
+/// Blake2b with a 32 byte digest
+pub fn blake2b(data: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut result = [0; DIGEST_LENGTH];
+    unsafe {
+        raw::blake2b(
+            data.as_ptr(),
+            data.len() as i64,
+            result.as_mut_ptr(),
+            DIGEST_LENGTH as i64,
+        )
+    };
+
+    result
+}
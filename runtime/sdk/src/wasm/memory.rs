@@ -1,3 +1,7 @@
+use std::time::Duration;
+
+use borsh::BorshDeserialize;
+
 use super::raw;
 
 pub fn memory_read(key: &str) -> Vec<u8> {
@@ -56,3 +60,52 @@ pub fn shared_memory_contains_key(key: &str) -> bool {
         _ => unreachable!("Bn254 verify returned invalid bool in u8: {}", result),
     }
 }
+
+/// Removes the key from the shared memory, returns whether it existed
+pub fn shared_memory_remove(key: &str) -> bool {
+    let key_len = key.len() as i64;
+    let key = key.to_string().into_bytes();
+
+    let result = unsafe { raw::shared_memory_remove(key.as_ptr(), key_len) };
+
+    result == 1
+}
+
+/// Removes the key from the shared memory once the TTL has passed, returns
+/// whether the key exists. Writing the key again clears the TTL.
+pub fn shared_memory_expire(key: &str, ttl: Duration) -> bool {
+    let key_len = key.len() as i64;
+    let key = key.to_string().into_bytes();
+
+    let result = unsafe { raw::shared_memory_expire(key.as_ptr(), key_len, ttl.as_millis() as i64) };
+
+    result == 1
+}
+
+/// The shared memory keys that start with the prefix, sorted
+pub fn shared_memory_keys(prefix: &str) -> Vec<String> {
+    let prefix_len = prefix.len() as i64;
+    let prefix = prefix.to_string().into_bytes();
+    let keys_len = unsafe { raw::shared_memory_keys_length(prefix.as_ptr(), prefix_len) };
+    let mut result_data_ptr = vec![0; keys_len as usize];
+    unsafe {
+        raw::shared_memory_keys_write(prefix.as_ptr(), prefix_len, result_data_ptr.as_mut_ptr(), keys_len);
+    }
+
+    Vec::<String>::try_from_slice(&result_data_ptr).expect("Host returned invalid shared memory keys")
+}
+
+/// Shared memory writes are staged and only kept once the job succeeds. This
+/// starts a transaction: the next writes are only kept once
+/// [shared_memory_commit] is called, they are rolled back when the job ends
+/// before that.
+pub fn shared_memory_begin() {
+    unsafe { raw::shared_memory_begin() }
+}
+
+/// Keeps the shared memory writes staged so far, those of a transaction
+/// included, even when the job fails later on. A program called with
+/// [call_program](super::call_program) only keeps them when it succeeds.
+pub fn shared_memory_commit() {
+    unsafe { raw::shared_memory_commit() }
+}
@@ -4,27 +4,41 @@ mod call;
 mod chain_interactor;
 #[cfg(feature = "full")]
 mod database;
+mod ed25519;
 mod env;
 mod execution;
+mod hash;
 mod http;
 mod log;
 mod memory;
 mod p2p;
 mod promise;
 mod raw;
+mod secp256k1;
+mod vrf;
 
 pub use call::*;
 #[cfg(feature = "full")]
 pub use chain_interactor::*;
 #[cfg(feature = "full")]
 pub use database::*;
+pub use ed25519::*;
 pub use env::*;
 pub use execution::*;
+pub use hash::*;
 pub use http::*;
 pub use log::*;
 pub use memory::*;
 #[cfg(feature = "full")]
 pub use p2p::*;
 pub use promise::*;
+pub use secp256k1::*;
+pub use vrf::*;
 
 pub use self::bn254::*;
+
+/// Tells the runtime which ABI the binary was built against, must match
+/// [crate::ABI_VERSION_SECTION]
+#[used]
+#[link_section = "seda_abi_version"]
+static SEDA_ABI_VERSION: [u8; 4] = crate::ABI_VERSION.to_le_bytes();
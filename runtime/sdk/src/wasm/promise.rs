@@ -1,10 +1,19 @@
-use std::str;
+use std::{
+    str,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
+use borsh::BorshDeserialize;
 use serde::{Deserialize, Serialize};
 
 use super::raw::promise_then;
 use crate::{wasm::raw, PromiseAction, PromiseStatus};
 
+/// The amount of promises added to the queue by this execution. Every
+/// execution runs in a fresh instance, so this is also the index of the next
+/// promise in the queue.
+static QUEUE_LENGTH: AtomicUsize = AtomicUsize::new(0);
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Promise {
     /// The name of the action we should execute
@@ -13,43 +22,103 @@ pub struct Promise {
     /// The status of the promise, will include the result if it's fulfilled
     pub status: PromiseStatus,
 
+    /// The indexes of the promises that have to be settled before this one is
+    /// executed
+    pub dependencies: Vec<usize>,
+
     #[serde(skip)]
-    /// The promise we should execute after this one
-    pub after: Option<Box<Self>>,
+    /// The index of this promise in the queue, set once it is started
+    index: Option<usize>,
 }
 
 impl Promise {
     pub fn new(action: PromiseAction) -> Self {
         Self {
             action,
-            after: None,
             status: PromiseStatus::Unfulfilled,
+            dependencies: Vec::new(),
+            index: None,
         }
     }
 
-    fn add_to_queue(promise: &Self) {
+    fn add_to_queue(promise: &mut Self) {
         // the json! macro was failing
         let promise_data = serde_json::to_string(&promise).expect("Shouldn't ever fail.");
 
         unsafe {
             promise_then(promise_data.as_ptr(), promise_data.len() as i32);
         }
+
+        promise.index = Some(QUEUE_LENGTH.fetch_add(1, Ordering::Relaxed));
     }
 
-    /// Starts the promise chain, must be only called on the first promise
-    /// before chaining (calling .then())
-    pub fn start(self) -> Self {
-        Promise::add_to_queue(&self);
+    /// The handle of the promise, which can be passed to [Promise::result] by
+    /// the next execution. `None` when the promise was not started yet.
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    /// Starts the promise. Promises that are started separately do not wait
+    /// for each other (unless they change state).
+    pub fn start(mut self) -> Self {
+        if self.index.is_none() {
+            Promise::add_to_queue(&mut self);
+        }
 
         self
     }
 
-    /// Chains this promise after the previous promise
-    pub fn then(mut self, after: Self) -> Self {
-        Promise::add_to_queue(&after);
-        self.after = Some(Box::new(after));
+    /// Starts the given promise once this promise is settled, returns the
+    /// given promise so calls can be chained
+    pub fn then(self, mut after: Self) -> Self {
+        let index = self.start().index.expect("Started promises always have an index");
+        after.dependencies.push(index);
 
-        self
+        after.start()
+    }
+
+    /// Resolves the action while the execution waits for it and returns its
+    /// status, so no function has to be called back with the result. Only
+    /// actions the host resolves on its own can be awaited (`db_get`, `db_set`,
+    /// `http`, `chain_view`, `chain_call` and `trigger_event`), the
+    /// dependencies of the promise are ignored.
+    ///
+    /// ```ignore
+    /// match http_fetch("https://api.example.com/price").wait() {
+    ///     PromiseStatus::Fulfilled(Some(body)) => { /* ... */ }
+    ///     _ => { /* ... */ }
+    /// }
+    /// ```
+    pub fn wait(self) -> PromiseStatus {
+        assert!(self.index.is_none(), "Started promises can not be awaited");
+        let action_data = serde_json::to_string(&self.action).expect("Shouldn't ever fail.");
+
+        // The awaited promise is added to the queue the results are read from
+        let index = unsafe { raw::promise_await(action_data.as_ptr(), action_data.len() as i32) };
+
+        Promise::result(index)
+    }
+
+    /// Creates a promise that is fulfilled once all the given promises are
+    /// fulfilled, read the results with [Promise::all_results]
+    pub fn all(promises: Vec<Self>) -> Self {
+        Self::combine(PromiseAction::All, promises)
+    }
+
+    /// Creates a promise that is fulfilled with the result of the first given
+    /// promise that is fulfilled, the remaining promises are cancelled
+    pub fn any(promises: Vec<Self>) -> Self {
+        Self::combine(PromiseAction::Any, promises)
+    }
+
+    fn combine(action: PromiseAction, promises: Vec<Self>) -> Self {
+        let mut combined = Self::new(action);
+        combined.dependencies = promises
+            .into_iter()
+            .map(|promise| promise.start().index.expect("Started promises always have an index"))
+            .collect();
+
+        combined.start()
     }
 
     /// Returns the result of a promise action
@@ -63,9 +132,15 @@ impl Promise {
             raw::promise_status_write(index, result_data.as_mut_ptr(), promise_result_length);
         }
 
-        let result_str = String::from_utf8(result_data).unwrap();
-        let promise_status: PromiseStatus = serde_json::from_str(&result_str).unwrap();
+        PromiseStatus::try_from_slice(&result_data).unwrap()
+    }
 
-        promise_status
+    /// Returns the results of the promises combined by a fulfilled
+    /// [Promise::all], in the order they were given
+    pub fn all_results(index: i32) -> Vec<PromiseStatus> {
+        match Promise::result(index) {
+            PromiseStatus::Fulfilled(Some(results)) => Vec::<PromiseStatus>::try_from_slice(&results).unwrap(),
+            _ => Vec::new(),
+        }
     }
 }
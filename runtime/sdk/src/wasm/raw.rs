@@ -1,5 +1,6 @@
 extern "C" {
     pub fn promise_then(action_data_offset: *const u8, action_data_length: i32);
+    pub fn promise_await(action_data_offset: *const u8, action_data_length: i32) -> i32;
     pub fn promise_status_length(promise_index: i32) -> i64;
     pub fn promise_status_write(promise_index: i32, result_data_offset: *const u8, result_data_length: i64);
     pub fn memory_read(key: *const u8, key_length: i64, result_data_ptr: *const u8, result_data_length: i64);
@@ -9,6 +10,21 @@ extern "C" {
     pub fn shared_memory_contains_key(key: *const u8, key_length: i64) -> u8;
     pub fn shared_memory_read_length(key: *const u8, key_length: i64) -> i64;
     pub fn shared_memory_write(key: *const u8, key_length: i64, value: *const u8, value_length: i64);
+    pub fn shared_memory_remove(key: *const u8, key_length: i64) -> u8;
+    pub fn shared_memory_expire(key: *const u8, key_length: i64, ttl_ms: i64) -> u8;
+    pub fn shared_memory_begin();
+    pub fn shared_memory_commit();
+    pub fn shared_memory_keys_length(prefix: *const u8, prefix_length: i64) -> i64;
+    pub fn shared_memory_keys_write(
+        prefix: *const u8,
+        prefix_length: i64,
+        result_data_ptr: *const u8,
+        result_data_length: i64,
+    );
+    pub fn execution_input_length() -> i64;
+    pub fn execution_context_length() -> i64;
+    pub fn execution_context_write(result_data_ptr: *const u8, result_data_length: i64);
+    pub fn execution_input_write(result_data_ptr: *const u8, result_data_length: i64);
     pub fn execution_result(result: *const u8, result_length: i32);
     pub fn _log(
         level: *const u8,
@@ -27,4 +43,42 @@ extern "C" {
         public_key_length: i64,
     ) -> u8;
     pub fn bn254_sign(message: *const u8, message_length: i64, result_data_ptr: *const u8, result_data_length: i64);
+    pub fn sha256(data: *const u8, data_length: i64, result_data_ptr: *const u8, result_data_length: i64);
+    pub fn keccak256(data: *const u8, data_length: i64, result_data_ptr: *const u8, result_data_length: i64);
+    pub fn blake2b(data: *const u8, data_length: i64, result_data_ptr: *const u8, result_data_length: i64);
+    pub fn p2p_auth_sign(payload: *const u8, payload_length: i64, result_data_ptr: *const u8, result_data_length: i64);
+    pub fn ed25519_verify(
+        message: *const u8,
+        message_length: i64,
+        signature: *const u8,
+        signature_length: i64,
+        public_key: *const u8,
+        public_key_length: i64,
+    ) -> u8;
+    pub fn secp256k1_recover(
+        message_hash: *const u8,
+        message_hash_length: i64,
+        signature: *const u8,
+        signature_length: i64,
+        result_data_ptr: *const u8,
+        result_data_length: i64,
+    ) -> u8;
+    pub fn vrf_prove(
+        input: *const u8,
+        input_length: i64,
+        output_ptr: *const u8,
+        output_length: i64,
+        proof_ptr: *const u8,
+        proof_length: i64,
+    );
+    pub fn vrf_verify(
+        input: *const u8,
+        input_length: i64,
+        proof: *const u8,
+        proof_length: i64,
+        public_key: *const u8,
+        public_key_length: i64,
+        output_ptr: *const u8,
+        output_length: i64,
+    ) -> u8;
 }
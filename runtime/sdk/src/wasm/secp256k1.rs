@@ -0,0 +1,21 @@
+use super::raw;
+
+/// Recovers the uncompressed secp256k1 public key (without the 0x04 prefix)
+/// that signed the message hash, like `ecrecover` on Ethereum. The signature
+/// is `r`, `s` and the recovery id `v` (0, 1, 27 or 28). Returns None when no
+/// public key can be recovered.
+pub fn secp256k1_recover(message_hash: &[u8; 32], signature: &[u8; 65]) -> Option<[u8; 64]> {
+    let mut result = [0; 64];
+    let recovered = unsafe {
+        raw::secp256k1_recover(
+            message_hash.as_ptr(),
+            message_hash.len() as i64,
+            signature.as_ptr(),
+            signature.len() as i64,
+            result.as_mut_ptr(),
+            result.len() as i64,
+        )
+    };
+
+    (recovered == 1).then_some(result)
+}
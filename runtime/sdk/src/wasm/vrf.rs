@@ -0,0 +1,58 @@
+use super::{raw, Bn254PublicKey};
+
+pub const VRF_OUTPUT_LENGTH: usize = 32;
+/// The proof is an uncompressed bn254 signature
+pub const VRF_PROOF_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VrfProof {
+    /// The random output, the same for every proof of the input by the node
+    pub output: [u8; VRF_OUTPUT_LENGTH],
+    /// Lets others check the output with the bn254 public key of the node
+    pub proof:  Vec<u8>,
+}
+
+/// Proves the input with the bn254 key of the node. The output can not be
+/// predicted without the key and is always the same for the same input.
+pub fn vrf_prove(input: &[u8]) -> VrfProof {
+    let mut output = [0; VRF_OUTPUT_LENGTH];
+    let mut proof = vec![0; VRF_PROOF_LENGTH];
+    unsafe {
+        raw::vrf_prove(
+            input.as_ptr(),
+            input.len() as i64,
+            output.as_mut_ptr(),
+            output.len() as i64,
+            proof.as_mut_ptr(),
+            proof.len() as i64,
+        )
+    };
+
+    VrfProof { output, proof }
+}
+
+/// Checks the proof of the input, returns the random output when it was
+/// proven by the owner of the public key
+pub fn vrf_verify(input: &[u8], proof: &[u8], public_key: &Bn254PublicKey) -> Option<[u8; VRF_OUTPUT_LENGTH]> {
+    let public_key_bytes = public_key.to_uncompressed().expect("Public Key should be valid");
+    let mut output = [0; VRF_OUTPUT_LENGTH];
+
+    let result = unsafe {
+        raw::vrf_verify(
+            input.as_ptr(),
+            input.len() as i64,
+            proof.as_ptr(),
+            proof.len() as i64,
+            public_key_bytes.as_ptr(),
+            public_key_bytes.len() as i64,
+            output.as_mut_ptr(),
+            output.len() as i64,
+        )
+    };
+
+    match result {
+        0 => None,
+        1 => Some(output),
+        _ => panic!("VRF verify returned invalid bool in u8: {}", result),
+    }
+}
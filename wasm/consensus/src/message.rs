@@ -1,5 +1,3 @@
-use std::str::FromStr;
-
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,22 +13,13 @@ pub enum Message {
     Batch(BatchMessage),
 }
 
-// TODO: impl Bytes Trait
-// impl Message {
-//     pub fn to_bytes(&self) -> Vec<u8> {
-//         serde_json::to_vec(self).expect("Failed to convert to json bytes")
-//     }
-
-//     pub fn from_bytes(bytes: &[u8]) -> Self {
-//         serde_json::from_slice(bytes).expect("Failed to get message from json
-// bytes")     }
-// }
-
-impl FromStr for Message {
-    // TODO: Error handling for consensus
-    type Err = ();
+// TODO: Error handling for consensus
+impl Message {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("Failed to convert to json bytes")
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(serde_json::from_str(s).expect("Failed to read from json string"))
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        serde_json::from_slice(bytes).expect("Failed to get message from json bytes")
     }
 }
@@ -1,9 +1,7 @@
-use std::str::FromStr;
-
 use clap::Args;
 use seda_runtime_sdk::{
     log,
-    wasm::{bn254_verify, shared_memory_set, Bn254PublicKey, Bn254Signature},
+    wasm::{bn254_verify, execution_input, shared_memory_set, Bn254PublicKey, Bn254Signature},
     Level,
 };
 
@@ -17,11 +15,9 @@ use crate::{
     },
 };
 
+/// The received message is passed as the execution input
 #[derive(Debug, Args)]
-pub struct P2P {
-    // TODO should change to bytes for more efficiency
-    message: String,
-}
+pub struct P2P {}
 
 impl P2P {
     /// This function should behave as follows:
@@ -35,7 +31,7 @@ impl P2P {
     ///     * If unknown batch, add to future batch list (current batch was
     ///       submitted)
     pub fn handle(self) {
-        let message = Message::from_str(&self.message).expect("Failed to decode message");
+        let message = Message::from_bytes(&execution_input());
 
         match message {
             Message::Batch(batch_message) => {
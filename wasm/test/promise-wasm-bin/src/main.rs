@@ -1,23 +1,55 @@
-use std::{env, fmt::Write, num::ParseIntError};
+use std::{
+    collections::hash_map::RandomState,
+    env,
+    fmt::Write,
+    hash::{BuildHasher, Hasher},
+    num::ParseIntError,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use seda_runtime_sdk::{
+    events::{Event, EventData},
+    p2p::P2PAuth,
     wasm::{
+        blake2b,
         bn254_sign,
         bn254_verify,
+        call_program,
         call_self,
+        call_self_with_input,
+        chain_call,
         db_get,
         db_set,
+        ed25519_verify,
+        execution_context,
+        execution_input,
         execution_result,
+        get_local_bn254_public_key,
+        get_local_ed25519_public_key,
         http_fetch,
+        keccak256,
         memory_read,
         memory_write,
+        p2p_auth_sign,
+        p2p_broadcast_message,
+        secp256k1_recover,
+        sha256,
+        shared_memory_begin,
+        shared_memory_commit,
+        shared_memory_expire,
         shared_memory_get,
+        shared_memory_keys,
+        shared_memory_remove,
         shared_memory_set,
+        trigger_event,
+        vrf_prove,
+        vrf_verify,
         Bn254PrivateKey,
         Bn254PublicKey,
         Bn254Signature,
         Promise,
     },
+    Chain,
     FromBytes,
     PromiseStatus,
     ToBytes,
@@ -204,3 +236,355 @@ fn shared_memory_success() {
     let bar = String::from_bytes_vec(foo_get).unwrap();
     assert_eq!("bar", bar);
 }
+
+#[no_mangle]
+fn infinite_loop_test() {
+    let mut counter: u64 = 0;
+    loop {
+        counter = std::hint::black_box(counter.wrapping_add(1));
+    }
+}
+
+#[no_mangle]
+fn memory_hog_test() {
+    let mut chunks: Vec<Vec<u8>> = Vec::new();
+    loop {
+        chunks.push(std::hint::black_box(vec![1; 1024 * 1024]));
+    }
+}
+
+#[no_mangle]
+fn concurrent_db_get_test() {
+    db_set("concurrent_a", "a")
+        .start()
+        .then(db_set("concurrent_b", "b"))
+        .then(call_self("concurrent_db_get_test_step1", vec![]));
+}
+
+#[no_mangle]
+fn concurrent_db_get_test_step1() {
+    Promise::all(vec![
+        db_get("concurrent_a"),
+        db_get("concurrent_b"),
+        db_get("concurrent_missing"),
+    ])
+    .then(call_self("concurrent_db_get_test_success", vec![]));
+}
+
+#[no_mangle]
+fn concurrent_db_get_test_success() {
+    let results: Vec<String> = (0..3)
+        .map(|index| match Promise::result(index) {
+            PromiseStatus::Fulfilled(Some(bytes)) => String::from_bytes_vec(bytes).unwrap(),
+            PromiseStatus::Fulfilled(None) => "none".to_string(),
+            _ => "rejected".to_string(),
+        })
+        .collect();
+
+    db_set("concurrent_db_get_result", &results.join(",")).start();
+}
+
+#[no_mangle]
+fn promise_combinators_test() {
+    Promise::any(vec![http_fetch("fail!"), db_get("combinators_missing")]);
+    Promise::all(vec![db_get("combinators_missing"), http_fetch("fail!")]);
+    Promise::all(vec![db_get("combinators_missing"), db_get("combinators_missing")]);
+    // Calls run after everything that was queued before them
+    call_self("promise_combinators_test_success", vec![]).start();
+}
+
+#[no_mangle]
+fn promise_combinators_test_success() {
+    let status = |status: PromiseStatus| match status {
+        PromiseStatus::Fulfilled(_) => "fulfilled",
+        PromiseStatus::Rejected(_) => "rejected",
+        _ => "unsettled",
+    };
+
+    let any = status(Promise::result(2));
+    let all_rejected = status(Promise::result(5));
+    let all_results = Promise::all_results(8).len();
+
+    db_set(
+        "promise_combinators_result",
+        &format!("{any},{all_rejected},{all_results}"),
+    )
+    .start();
+}
+
+#[no_mangle]
+fn recording_test() {
+    let seed = shared_memory_get("recording_seed");
+    shared_memory_set("recording_copy", seed);
+    db_get("recording_key")
+        .start()
+        .then(call_self("recording_test_success", vec![]));
+}
+
+#[no_mangle]
+fn recording_test_success() {
+    if let PromiseStatus::Fulfilled(Some(value)) = Promise::result(0) {
+        execution_result(value);
+    }
+}
+
+#[no_mangle]
+fn deterministic_test() {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    // Uses the WASI random source for its keys
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u8(1);
+    let has_public_key = env::var("ED25519_PUBLIC_KEY").is_ok();
+
+    execution_result(format!("{now},{},{has_public_key}", hasher.finish()).into_bytes());
+}
+
+#[no_mangle]
+fn float_test() {
+    let value = env::args().count() as f64 * 1.5;
+    execution_result(value.to_string().into_bytes());
+}
+
+#[no_mangle]
+fn execution_input_test() {
+    let mut input = execution_input();
+    input.push(255);
+    call_self_with_input("execution_input_test_success", vec![], input).start();
+}
+
+#[no_mangle]
+fn execution_input_test_success() {
+    execution_result(execution_input());
+}
+
+#[no_mangle]
+fn shared_memory_management_test() {
+    shared_memory_set("management_a", "a".into());
+    shared_memory_set("management_b", "b".into());
+    shared_memory_set("management_c", "c".into());
+    let removed = shared_memory_remove("management_b");
+    shared_memory_expire("management_c", Duration::ZERO);
+
+    let keys = shared_memory_keys("management_").join(",");
+    execution_result(format!("{removed},{keys}").into_bytes());
+}
+
+#[no_mangle]
+fn shared_memory_set_test() {
+    let args: Vec<String> = env::args().collect();
+    shared_memory_set(&args[1], "set".into());
+}
+
+#[no_mangle]
+fn shared_memory_quota_test() {
+    shared_memory_set("quota", vec![0; 1024]);
+}
+
+#[no_mangle]
+fn shared_memory_rollback_test() {
+    shared_memory_set("rollback", "staged".into());
+    call_self("shared_memory_rollback_test_fail", vec![]).start();
+}
+
+#[no_mangle]
+fn shared_memory_rollback_test_fail() {
+    panic!("fails after a shared memory write");
+}
+
+#[no_mangle]
+fn shared_memory_commit_test() {
+    shared_memory_set("commit_a", "committed".into());
+    shared_memory_commit();
+    shared_memory_set("commit_b", "staged".into());
+    panic!("fails after a shared memory commit");
+}
+
+#[no_mangle]
+fn shared_memory_begin_test() {
+    shared_memory_set("begin_a", "committed".into());
+    shared_memory_begin();
+    shared_memory_set("begin_b", "staged".into());
+}
+
+#[no_mangle]
+fn hash_test() {
+    let data = b"seda";
+    let digests = [sha256(data), keccak256(data), blake2b(data)].map(|digest| encode_hex(&digest));
+
+    execution_result(digests.join(",").into_bytes());
+}
+
+#[no_mangle]
+fn ed25519_test() {
+    // The node signs a statement of the program that asked, not the payload
+    let message = &P2PAuth {
+        program: String::from_utf8(execution_input()).unwrap(),
+        payload: b"seda".to_vec(),
+    }
+    .signing_bytes();
+    let public_key = decode_hex(&get_local_ed25519_public_key()).unwrap();
+    let signature = p2p_auth_sign(b"seda");
+    let mut tampered_signature = signature;
+    tampered_signature[0] ^= 1;
+
+    let valid = ed25519_verify(message, &signature, &public_key);
+    let tampered = ed25519_verify(message, &tampered_signature, &public_key);
+    let malformed = ed25519_verify(message, &signature, &public_key[1..]);
+    execution_result(format!("{valid},{tampered},{malformed},{}", encode_hex(&signature)).into_bytes());
+}
+
+#[no_mangle]
+fn secp256k1_recover_test() {
+    let input = execution_input();
+    let message_hash = input[..32].try_into().unwrap();
+    let signature = input[32..].try_into().unwrap();
+    let public_key = secp256k1_recover(&message_hash, &signature).expect("Could not recover the public key");
+
+    execution_result(public_key.to_vec());
+}
+
+#[no_mangle]
+fn signing_test() {
+    let host_function = String::from_utf8(execution_input()).unwrap();
+    match host_function.as_str() {
+        "bn254_sign" => drop(bn254_sign(b"seda")),
+        "p2p_auth_sign" => drop(p2p_auth_sign(b"seda")),
+        "vrf_prove" => drop(vrf_prove(b"seda")),
+        _ => panic!("Unknown signing host function `{host_function}`"),
+    }
+
+    execution_result(b"signed".to_vec());
+}
+
+#[no_mangle]
+fn vrf_test() {
+    let input = execution_input();
+    let public_key = Bn254PublicKey::from_uncompressed(decode_hex(&get_local_bn254_public_key()).unwrap()).unwrap();
+    let vrf_proof = vrf_prove(&input);
+
+    assert_eq!(
+        vrf_verify(&input, &vrf_proof.proof, &public_key),
+        Some(vrf_proof.output)
+    );
+    assert_eq!(vrf_verify(b"other input", &vrf_proof.proof, &public_key), None);
+    execution_result([vrf_proof.output.as_slice(), &vrf_proof.proof].concat());
+}
+
+#[no_mangle]
+fn execution_context_test() {
+    call_self("execution_context_test_success", vec![]).start();
+}
+
+#[no_mangle]
+fn execution_context_test_success() {
+    let context = execution_context();
+    execution_result(
+        format!(
+            "{},{},{},{},{}",
+            context.event_id.unwrap_or_default(),
+            context.event_type.unwrap_or_default(),
+            get_local_ed25519_public_key(),
+            context.start_timestamp.is_some(),
+            context.slot.map(|slot| slot / 32) == context.epoch && context.epoch.is_some(),
+        )
+        .into_bytes(),
+    );
+}
+
+#[no_mangle]
+fn dry_run_test() {
+    chain_call(Chain::Near, "mc.seda.near", "post", br#"{"value":1}"#.to_vec(), 1)
+        .start()
+        .then(call_self("dry_run_test_result", vec![]));
+}
+
+#[no_mangle]
+fn dry_run_test_result() {
+    if let PromiseStatus::Fulfilled(Some(result)) = Promise::result(0) {
+        execution_result(result);
+    } else {
+        panic!("chain_call was not fulfilled");
+    }
+}
+
+#[no_mangle]
+fn dry_run_side_effects_test() {
+    shared_memory_set("dry_run", b"written".to_vec());
+    shared_memory_begin();
+    shared_memory_set("dry_run_committed", b"written".to_vec());
+    shared_memory_commit();
+
+    db_set("dry_run", "written").start();
+    trigger_event(Event::new("dry_run", EventData::BatchChainTick)).start();
+    p2p_broadcast_message(b"dry run".to_vec()).start();
+}
+
+#[no_mangle]
+fn call_program_test() {
+    let args: Vec<String> = env::args().collect();
+    call_program(&args[1], "call_program_test_callee", vec![])
+        .start()
+        .then(call_self("call_program_test_result", vec![]));
+}
+
+#[no_mangle]
+fn call_program_function_test() {
+    let args: Vec<String> = env::args().collect();
+    call_program(&args[1], &args[2], vec![])
+        .start()
+        .then(call_self("call_program_test_result", vec![]));
+}
+
+#[no_mangle]
+fn call_program_test_callee() {
+    execution_result(b"hello from the callee".to_vec());
+}
+
+#[no_mangle]
+fn call_program_test_result() {
+    match Promise::result(0) {
+        PromiseStatus::Fulfilled(Some(result)) | PromiseStatus::Rejected(result) => execution_result(result),
+        _ => panic!("call_program did not settle"),
+    }
+}
+
+#[no_mangle]
+fn call_program_rollback_test() {
+    let args: Vec<String> = env::args().collect();
+    shared_memory_set("caller/before", b"kept".to_vec());
+    call_program(&args[1], "call_program_rollback_test_callee", vec![])
+        .start()
+        .then(call_self("call_program_rollback_test_result", vec![]));
+}
+
+#[no_mangle]
+fn call_program_rollback_test_callee() {
+    shared_memory_set("callee/partial", b"rolled back".to_vec());
+    panic!("the callee failed after writing");
+}
+
+#[no_mangle]
+fn call_program_rollback_test_result() {
+    shared_memory_set("caller/after", b"kept".to_vec());
+    call_program_test_result();
+}
+
+#[no_mangle]
+fn await_test() {
+    db_set("await_test", "awaited").wait();
+    // The awaited promises do not take the place of queued promises
+    call_self("await_test_result", vec![]).start();
+
+    match db_get("await_test").wait() {
+        PromiseStatus::Fulfilled(Some(value)) => {
+            memory_write("await_test", String::from_bytes_vec(value).unwrap().into_bytes())
+        }
+        PromiseStatus::Rejected(error) => memory_write("await_test", error),
+        other => panic!("db_get was not settled: {other:?}"),
+    }
+}
+
+#[no_mangle]
+fn await_test_result() {
+    execution_result(memory_read("await_test"));
+}